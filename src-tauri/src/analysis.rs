@@ -0,0 +1,769 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::oui;
+use crate::radiotap::SsidState;
+use crate::spectrum::{spectral_mask_dbr, Band, Span};
+use crate::wifi_scanner::WiFiNetwork;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NetworkRef {
+    pub ssid: String,
+    pub bssid: String,
+    pub signal: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BandSummary {
+    pub band: Band,
+    pub network_count: usize,
+    /// Mean of the networks' average signal in dBm
+    pub average_signal: Option<f32>,
+    pub strongest_network: Option<NetworkRef>,
+    /// Channel with the most networks in this band
+    pub most_congested_channel: Option<u32>,
+}
+
+pub fn band_summary(networks: &[WiFiNetwork]) -> Vec<BandSummary> {
+    Band::ALL
+        .iter()
+        .map(|&band| {
+            let in_band: Vec<&WiFiNetwork> = networks
+                .iter()
+                .filter(|n| Band::from_frequency(n.frequency) == Some(band))
+                .collect();
+
+            let average_signal = if in_band.is_empty() {
+                None
+            } else {
+                let total: i32 = in_band.iter().map(|n| n.avg_signal).sum();
+                Some(total as f32 / in_band.len() as f32)
+            };
+
+            let strongest_network =
+                in_band
+                    .iter()
+                    .max_by_key(|n| n.avg_signal)
+                    .map(|n| NetworkRef {
+                        ssid: n.ssid.clone(),
+                        bssid: n.bssid.clone(),
+                        signal: n.avg_signal,
+                    });
+
+            let mut channel_count: HashMap<u32, usize> = HashMap::new();
+            for network in &in_band {
+                *channel_count.entry(network.channel).or_insert(0) += 1;
+            }
+            // Ties go to the lower channel so the result is stable
+            let most_congested_channel = channel_count
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                .map(|(channel, _)| channel);
+
+            BandSummary {
+                band,
+                network_count: in_band.len(),
+                average_signal,
+                strongest_network,
+                most_congested_channel,
+            }
+        })
+        .collect()
+}
+
+// BSSIDs weaker than this are unlikely to interfere with each other
+const STRONG_SIGNAL_DBM: i32 = -70;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChannelUsage {
+    pub channel: u32,
+    pub bssids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NetworkAudit {
+    pub ssid: String,
+    pub bssid_count: usize,
+    pub channel_distribution: Vec<ChannelUsage>,
+    pub warnings: Vec<String>,
+}
+
+/// Audits the deployment of a single SSID: how many BSSIDs serve it, on
+/// which channels, and whether strong BSSIDs share a channel.
+pub fn audit_ssid(ssid: &str, networks: &[WiFiNetwork]) -> NetworkAudit {
+    let members: Vec<&WiFiNetwork> = networks.iter().filter(|n| n.ssid == ssid).collect();
+
+    let mut by_channel: HashMap<u32, Vec<&WiFiNetwork>> = HashMap::new();
+    for network in &members {
+        by_channel.entry(network.channel).or_default().push(network);
+    }
+
+    let mut channels: Vec<u32> = by_channel.keys().copied().collect();
+    channels.sort_unstable();
+
+    let mut channel_distribution = Vec::new();
+    let mut warnings = Vec::new();
+
+    for channel in channels {
+        let on_channel = &by_channel[&channel];
+
+        let strong: Vec<String> = on_channel
+            .iter()
+            .filter(|n| n.avg_signal >= STRONG_SIGNAL_DBM)
+            .map(|n| format!("{} ({} dBm)", n.bssid, n.avg_signal))
+            .collect();
+        if strong.len() > 1 {
+            warnings.push(format!(
+                "{} strong BSSIDs of \"{}\" share channel {}: {}",
+                strong.len(),
+                ssid,
+                channel,
+                strong.join(", ")
+            ));
+        }
+
+        channel_distribution.push(ChannelUsage {
+            channel,
+            bssids: on_channel.iter().map(|n| n.bssid.clone()).collect(),
+        });
+    }
+
+    NetworkAudit {
+        ssid: ssid.to_string(),
+        bssid_count: members.len(),
+        channel_distribution,
+        warnings,
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterferenceMatrix {
+    pub band: Band,
+    /// Row/column labels of `matrix`
+    pub channels: Vec<u32>,
+    /// `matrix[i][j]` is the power in dBm that networks on `channels[i]`
+    /// put onto `channels[j]`, or None when nothing reaches it
+    pub matrix: Vec<Vec<Option<f64>>>,
+}
+
+/// Estimates how much each channel's networks bleed into every other
+/// channel of `band`. A network's average received power is spread evenly
+/// over the span its channel width occupies around its center, so a bonded
+/// network lands in full on every 20 MHz channel its span intersects. Past
+/// the span, the 20 MHz segment at its edge leaks onto other channels as
+/// the OFDM spectral mask allows. Contributions are summed in mW.
+pub fn interference_matrix(networks: &[WiFiNetwork], band: Band) -> InterferenceMatrix {
+    let channels = band.channels();
+    let mut power_mw = vec![vec![0.0f64; channels.len()]; channels.len()];
+
+    for network in networks {
+        if Band::from_frequency(network.frequency) != Some(band) || network.avg_signal >= 0 {
+            continue;
+        }
+        let Some(source) = channels.iter().position(|c| *c == network.channel) else {
+            continue;
+        };
+
+        let occupied = Span::centered(network.center_frequency, network.channel_width_mhz);
+        let total_mw = 10f64.powf(network.avg_signal as f64 / 10.0);
+        for (target, channel) in channels.iter().enumerate() {
+            let target_span = Span::centered(band.channel_frequency(*channel), 20);
+            let overlap = occupied.overlap(&target_span);
+            power_mw[source][target] += if overlap > 0 {
+                total_mw * overlap as f64 / occupied.width() as f64
+            } else {
+                // Centers of the edge segment and the target are a channel
+                // width plus the gap apart
+                let edge_mw = total_mw * 20.0 / occupied.width() as f64;
+                let offset = (occupied.gap(&target_span) + 20) as f64;
+                spectral_mask_dbr(offset).map_or(0.0, |dbr| edge_mw * 10f64.powf(dbr / 10.0))
+            };
+        }
+    }
+
+    let matrix = power_mw
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|mw| (mw > 0.0).then(|| 10.0 * mw.log10()))
+                .collect()
+        })
+        .collect();
+
+    InterferenceMatrix {
+        band,
+        channels,
+        matrix,
+    }
+}
+
+// Observed beacon spacing shorter than this fraction of the advertised
+// interval counts as drift. Longer spacing is expected whenever beacons are
+// missed (e.g. while hopping channels), so it isn't flagged.
+const INTERVAL_DRIFT_RATIO: f64 = 0.75;
+// Beacons per second above which a BSSID is flooding (the usual 100 TU
+// interval is ~9.8/s)
+const BEACON_FLOOD_RATE: f64 = 20.0;
+// Shorter observation windows give too noisy a rate
+const MIN_OBSERVATION_SECS: f64 = 2.0;
+const TU_MS: f64 = 1.024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    IntervalDrift,
+    SsidChanged,
+    BeaconFlood,
+    MultiChannel,
+    ForcedRoaming,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BeaconAnomaly {
+    pub bssid: String,
+    pub ssid: String,
+    pub kind: AnomalyKind,
+    pub detail: String,
+}
+
+/// Flags BSSIDs whose beacons arrive faster than advertised, that changed
+/// SSID, that beacon on several channels at once, that push clients away
+/// with BSS Transition requests, or that beacon at an abnormally high rate.
+pub fn beacon_anomalies(networks: &[WiFiNetwork]) -> Vec<BeaconAnomaly> {
+    let mut anomalies = Vec::new();
+
+    for network in networks {
+        let anomaly = |kind, detail| BeaconAnomaly {
+            bssid: network.bssid.clone(),
+            ssid: network.ssid.clone(),
+            kind,
+            detail,
+        };
+
+        if let Some((_, earlier)) = network.ssid_history.split_last() {
+            if !earlier.is_empty() {
+                anomalies.push(anomaly(
+                    AnomalyKind::SsidChanged,
+                    format!(
+                        "Advertised {} before \"{}\"",
+                        earlier
+                            .iter()
+                            .map(|(_, ssid)| format!("\"{}\"", ssid))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        network.ssid
+                    ),
+                ));
+            }
+        }
+
+        if network.multi_channel_anomaly {
+            anomalies.push(anomaly(
+                AnomalyKind::MultiChannel,
+                format!(
+                    "Beaconing on channels {:?} at once",
+                    network.recent_channels
+                ),
+            ));
+        }
+
+        if network.btm_disassociation_imminent > 0 {
+            anomalies.push(anomaly(
+                AnomalyKind::ForcedRoaming,
+                format!(
+                    "{} of {} BSS Transition requests threatened disassociation",
+                    network.btm_disassociation_imminent, network.btm_requests
+                ),
+            ));
+        }
+
+        let observed_secs = network
+            .last_seen
+            .duration_since(network.first_seen)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        if observed_secs < MIN_OBSERVATION_SECS || network.sighting_beacon_count < 2 {
+            continue;
+        }
+
+        let rate = (network.sighting_beacon_count - 1) as f64 / observed_secs;
+        if rate > BEACON_FLOOD_RATE {
+            anomalies.push(anomaly(
+                AnomalyKind::BeaconFlood,
+                format!("{:.1} beacons/s over {:.0}s", rate, observed_secs),
+            ));
+        }
+
+        if let Some(interval) = network.beacon_interval.filter(|tu| *tu > 0) {
+            let advertised_ms = interval as f64 * TU_MS;
+            let observed_ms = 1000.0 / rate;
+            if observed_ms < advertised_ms * INTERVAL_DRIFT_RATIO {
+                anomalies.push(anomaly(
+                    AnomalyKind::IntervalDrift,
+                    format!(
+                        "Beacons every {:.1} ms, advertised {} TU ({:.1} ms)",
+                        observed_ms, interval, advertised_ms
+                    ),
+                ));
+            }
+        }
+    }
+
+    anomalies
+}
+
+/// Risk points per network by security label, out of `MAX_RISK_POINTS`.
+/// Unencrypted and broken encryption score highest, PSK-only WPA2 is
+/// exposed to offline dictionary attacks, and SAE/OWE/enterprise score
+/// lowest. Advertising WPS adds `WPS_RISK_POINTS` on top (PIN brute force).
+fn risk_points(security: &str) -> u32 {
+    match security {
+        "Open" | "WEP" => 10,
+        "WPA" => 8,
+        "WPA2-PSK" | "WPA2" => 4,
+        "WPA2/WPA3 Transition" => 3,
+        "WPA2-Enterprise" | "OWE" => 2,
+        "WPA3-SAE" | "WPA3-Enterprise" => 0,
+        _ => 5,
+    }
+}
+
+const WPS_RISK_POINTS: u32 = 3;
+const MAX_RISK_POINTS: u32 = 13;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SecuritySummary {
+    pub network_count: usize,
+    /// Network count per security label, most common first
+    pub by_security: Vec<(String, usize)>,
+    pub wps_enabled: usize,
+    pub hidden: usize,
+    /// 0 (every network WPA3 without WPS) to 100 (every network open with
+    /// WPS): the mean of the per-network risk points, scaled
+    pub risk_score: u32,
+}
+
+pub fn security_summary(networks: &[WiFiNetwork]) -> SecuritySummary {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut total_points = 0;
+    for network in networks {
+        *counts.entry(network.security.as_str()).or_insert(0) += 1;
+        total_points += risk_points(&network.security);
+        if network.wps {
+            total_points += WPS_RISK_POINTS;
+        }
+    }
+
+    let mut by_security: Vec<(String, usize)> = counts
+        .into_iter()
+        .map(|(label, count)| (label.to_string(), count))
+        .collect();
+    by_security.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let risk_score = if networks.is_empty() {
+        0
+    } else {
+        total_points * 100 / (MAX_RISK_POINTS * networks.len() as u32)
+    };
+
+    SecuritySummary {
+        network_count: networks.len(),
+        by_security,
+        wps_enabled: networks.iter().filter(|n| n.wps).count(),
+        hidden: networks
+            .iter()
+            .filter(|n| !matches!(n.ssid_state, SsidState::Broadcast(_)))
+            .count(),
+        risk_score,
+    }
+}
+
+/// Tuning for `group_physical_aps`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ApGroupingOptions {
+    /// Largest difference between the last BSSID octets of one radio's
+    /// virtual interfaces. Vendors usually vary the low nibble only.
+    pub max_last_octet_delta: u8,
+    /// Treat BSSIDs that differ only in the locally administered bit of the
+    /// first octet as the same base address, as some vendors derive their
+    /// virtual BSSIDs that way
+    pub ignore_local_bit: bool,
+    pub require_same_channel: bool,
+    /// Largest TSF uptime difference in seconds. Virtual BSSIDs share the
+    /// radio's timer, so their uptimes agree closely. Networks without an
+    /// uptime estimate aren't held to this.
+    pub max_uptime_delta_secs: u64,
+}
+
+impl Default for ApGroupingOptions {
+    fn default() -> Self {
+        ApGroupingOptions {
+            max_last_octet_delta: 15,
+            ignore_local_bit: true,
+            require_same_channel: true,
+            max_uptime_delta_secs: 5,
+        }
+    }
+}
+
+/// BSSIDs that most likely belong to one physical radio.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PhysicalAp {
+    pub bssids: Vec<String>,
+    pub ssids: Vec<String>,
+    pub channel: u32,
+    pub vendor: Option<String>,
+    pub estimated_uptime_secs: Option<u64>,
+}
+
+/// Parses a colon-separated BSSID of exactly six two-digit octets.
+fn parse_bssid(bssid: &str) -> Option<[u8; 6]> {
+    let mut octets = [0u8; 6];
+    let mut parts = bssid.split(':');
+    for octet in octets.iter_mut() {
+        let part = parts
+            .next()
+            .filter(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()))?;
+        *octet = u8::from_str_radix(part, 16).ok()?;
+    }
+    parts.next().is_none().then_some(octets)
+}
+
+fn same_radio(a: &WiFiNetwork, b: &WiFiNetwork, options: &ApGroupingOptions) -> bool {
+    let (Some(mac_a), Some(mac_b)) = (parse_bssid(&a.bssid), parse_bssid(&b.bssid)) else {
+        return false;
+    };
+
+    let local_mask = if options.ignore_local_bit {
+        !0x02
+    } else {
+        0xFF
+    };
+    let same_base = mac_a[0] & local_mask == mac_b[0] & local_mask && mac_a[1..5] == mac_b[1..5];
+    let close = mac_a[5].abs_diff(mac_b[5]) <= options.max_last_octet_delta;
+    let channel_ok = !options.require_same_channel || a.channel == b.channel;
+    let uptime_ok = match (a.estimated_uptime_secs, b.estimated_uptime_secs) {
+        (Some(x), Some(y)) => x.abs_diff(y) <= options.max_uptime_delta_secs,
+        _ => true,
+    };
+
+    same_base && close && channel_ok && uptime_ok
+}
+
+/// Clusters BSSIDs that a multi-SSID AP broadcasts from one radio. Every
+/// network ends up in exactly one group; single-SSID APs form their own.
+/// A network joins the first group whose every member it matches, so a
+/// chain of near neighbours can't drag unrelated BSSIDs together.
+pub fn group_physical_aps(
+    networks: &[WiFiNetwork],
+    options: &ApGroupingOptions,
+) -> Vec<PhysicalAp> {
+    let mut sorted: Vec<&WiFiNetwork> = networks.iter().collect();
+    sorted.sort_by(|a, b| a.bssid.cmp(&b.bssid));
+
+    let mut groups: Vec<Vec<&WiFiNetwork>> = Vec::new();
+    for network in sorted {
+        let group = groups.iter_mut().find(|group| {
+            group
+                .iter()
+                .all(|member| same_radio(member, network, options))
+        });
+        match group {
+            Some(group) => group.push(network),
+            None => groups.push(vec![network]),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|members| {
+            let mut ssids: Vec<String> = members
+                .iter()
+                .filter(|n| !n.ssid.is_empty())
+                .map(|n| n.ssid.clone())
+                .collect();
+            ssids.sort();
+            ssids.dedup();
+
+            PhysicalAp {
+                bssids: members.iter().map(|n| n.bssid.clone()).collect(),
+                ssids,
+                channel: members[0].channel,
+                vendor: oui::lookup(&members[0].bssid).map(str::to_string),
+                estimated_uptime_secs: members.iter().find_map(|n| n.estimated_uptime_secs),
+            }
+        })
+        .collect()
+}
+
+/// How busy a channel is, judged against `CongestionThresholds`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Clear,
+    Moderate,
+    Congested,
+}
+
+/// Channel occupancy at which a channel stops counting as clear
+/// (`moderate`) and becomes congested (`congested`).
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy)]
+pub struct CongestionThresholds {
+    pub moderate: f32,
+    pub congested: f32,
+}
+
+impl Default for CongestionThresholds {
+    fn default() -> Self {
+        CongestionThresholds {
+            moderate: 0.2,
+            congested: 0.5,
+        }
+    }
+}
+
+impl CongestionThresholds {
+    /// Occupancy is a 0-1 share, so both thresholds must fall in that range
+    /// with `moderate` below `congested`.
+    pub fn new(moderate: f32, congested: f32) -> Option<Self> {
+        let in_range = |t: f32| (0.0..=1.0).contains(&t);
+        (in_range(moderate) && in_range(congested) && moderate < congested).then_some(
+            CongestionThresholds {
+                moderate,
+                congested,
+            },
+        )
+    }
+
+    /// A channel exactly at a threshold falls into the higher severity.
+    pub fn classify(&self, occupancy: f32) -> Severity {
+        if occupancy >= self.congested {
+            Severity::Congested
+        } else if occupancy >= self.moderate {
+            Severity::Moderate
+        } else {
+            Severity::Clear
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(bssid: &str, frequency: u32, avg_signal: i32) -> WiFiNetwork {
+        let mut network = WiFiNetwork::new(
+            bssid.to_string(),
+            String::new(),
+            SsidState::Absent,
+            frequency,
+            None,
+        );
+        network.avg_signal = avg_signal;
+        network
+    }
+
+    fn power(matrix: &InterferenceMatrix, from: u32, to: u32) -> Option<f64> {
+        let index = |channel| matrix.channels.iter().position(|c| *c == channel).unwrap();
+        matrix.matrix[index(from)][index(to)]
+    }
+
+    #[test]
+    fn bonded_network_covers_every_channel_of_its_span() {
+        let mut wide = network("00:00:00:00:00:01", 5180, -40);
+        (wide.channel_width_mhz, wide.center_frequency) = (80, 5210);
+        let matrix = interference_matrix(&[wide], Band::Five);
+
+        // -40 dBm spread over four 20 MHz channels is ~-46 dBm on each
+        for channel in [36, 40, 44, 48] {
+            let dbm = power(&matrix, 36, channel).unwrap();
+            assert!((dbm - -46.02).abs() < 0.01, "channel {}: {}", channel, dbm);
+        }
+        // The adjacent channel only gets mask leakage
+        assert!(power(&matrix, 36, 52).unwrap() < -46.02 - 20.0);
+        assert_eq!(power(&matrix, 36, 100), None);
+    }
+
+    #[test]
+    fn narrow_network_stays_on_its_channel() {
+        let matrix = interference_matrix(&[network("00:00:00:00:00:01", 5180, -40)], Band::Five);
+        assert_eq!(power(&matrix, 36, 36), Some(-40.0));
+        assert!(power(&matrix, 36, 40).unwrap() < -60.0);
+    }
+
+    fn secured(bssid: &str, ssid: &str, security: &str, wps: bool) -> WiFiNetwork {
+        let mut network = WiFiNetwork::new(
+            bssid.to_string(),
+            ssid.to_string(),
+            SsidState::Broadcast(ssid.to_string()),
+            2437,
+            Some(6),
+        );
+        network.security = security.to_string();
+        network.wps = wps;
+        network
+    }
+
+    #[test]
+    fn security_summary_of_no_networks() {
+        let summary = security_summary(&[]);
+        assert_eq!(summary.network_count, 0);
+        assert!(summary.by_security.is_empty());
+        assert_eq!(summary.wps_enabled, 0);
+        assert_eq!(summary.hidden, 0);
+        assert_eq!(summary.risk_score, 0);
+    }
+
+    #[test]
+    fn security_summary_of_mixed_networks() {
+        let mut hidden = secured("00:00:00:00:00:04", "", "WPA2-PSK", false);
+        hidden.ssid_state = SsidState::NullHidden;
+        let networks = [
+            secured("00:00:00:00:00:01", "cafe", "Open", true),
+            secured("00:00:00:00:00:02", "home", "WPA3-SAE", false),
+            secured("00:00:00:00:00:03", "office", "WPA2-PSK", true),
+            hidden,
+        ];
+
+        let summary = security_summary(&networks);
+
+        assert_eq!(summary.network_count, 4);
+        // Most common first, ties by label
+        assert_eq!(
+            summary.by_security,
+            vec![
+                ("WPA2-PSK".to_string(), 2),
+                ("Open".to_string(), 1),
+                ("WPA3-SAE".to_string(), 1),
+            ]
+        );
+        assert_eq!(summary.wps_enabled, 2);
+        assert_eq!(summary.hidden, 1);
+        // (10 + 3) + 0 + (4 + 3) + 4 = 24 of 4 * 13 points
+        assert_eq!(summary.risk_score, 24 * 100 / 52);
+    }
+
+    #[test]
+    fn security_summary_risk_score_extremes() {
+        let worst = [secured("00:00:00:00:00:01", "a", "Open", true)];
+        assert_eq!(security_summary(&worst).risk_score, 100);
+
+        let best = [secured("00:00:00:00:00:01", "a", "WPA3-Enterprise", false)];
+        assert_eq!(security_summary(&best).risk_score, 0);
+    }
+
+    #[test]
+    fn parse_bssid_rejects_crafted_addresses() {
+        assert_eq!(
+            parse_bssid("00:1a:2B:3c:4D:5e"),
+            Some([0x00, 0x1A, 0x2B, 0x3C, 0x4D, 0x5E])
+        );
+        for bssid in [
+            "",
+            "00:11:22:33:44",
+            "00:11:22:33:44:",
+            "00:11:22:33:44:55:66",
+            "000:11:22:33:44:55",
+            "0:11:22:33:44:55",
+            "100:11:22:33:44:55",
+            "+0:11:22:33:44:55",
+            "zz:11:22:33:44:55",
+            "00-11-22-33-44-55",
+            "\u{e9}\u{e9}:11:22:33:44:55",
+        ] {
+            assert_eq!(parse_bssid(bssid), None, "{:?}", bssid);
+        }
+    }
+
+    #[test]
+    fn crafted_bssids_group_alone() {
+        let options = ApGroupingOptions::default();
+        let networks = [
+            network("00:11:22:33:44:50", 2437, -50),
+            network("00:11:22:33:44:51", 2437, -50),
+            network("00:11:22:33:44", 2437, -50),
+            network("00:11:22:33:44:52:53", 2437, -50),
+            network("", 2437, -50),
+        ];
+
+        let groups = group_physical_aps(&networks, &options);
+
+        assert_eq!(groups.len(), 4);
+        let valid = groups.iter().find(|g| g.bssids.len() == 2).unwrap();
+        assert_eq!(valid.bssids, ["00:11:22:33:44:50", "00:11:22:33:44:51"]);
+        assert!(groups
+            .iter()
+            .filter(|g| g.bssids.len() == 1)
+            .all(|g| parse_bssid(&g.bssids[0]).is_none()));
+    }
+
+    #[test]
+    fn group_physical_aps_of_no_networks() {
+        assert!(group_physical_aps(&[], &ApGroupingOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn congestion_severity_at_each_threshold() {
+        let thresholds = CongestionThresholds::new(0.25, 0.75).unwrap();
+        let just = 0.0001;
+
+        assert_eq!(thresholds.classify(0.0), Severity::Clear);
+        assert_eq!(thresholds.classify(0.25 - just), Severity::Clear);
+        assert_eq!(thresholds.classify(0.25), Severity::Moderate);
+        assert_eq!(thresholds.classify(0.25 + just), Severity::Moderate);
+        assert_eq!(thresholds.classify(0.75 - just), Severity::Moderate);
+        assert_eq!(thresholds.classify(0.75), Severity::Congested);
+        assert_eq!(thresholds.classify(0.75 + just), Severity::Congested);
+        assert_eq!(thresholds.classify(1.0), Severity::Congested);
+    }
+
+    #[test]
+    fn default_congestion_thresholds() {
+        let thresholds = CongestionThresholds::default();
+        assert_eq!(thresholds.classify(0.19), Severity::Clear);
+        assert_eq!(thresholds.classify(0.2), Severity::Moderate);
+        assert_eq!(thresholds.classify(0.49), Severity::Moderate);
+        assert_eq!(thresholds.classify(0.5), Severity::Congested);
+    }
+
+    #[test]
+    fn congestion_thresholds_must_be_ordered_shares() {
+        assert!(CongestionThresholds::new(0.0, 1.0).is_some());
+        assert!(CongestionThresholds::new(0.5, 0.5).is_none());
+        assert!(CongestionThresholds::new(0.6, 0.4).is_none());
+        assert!(CongestionThresholds::new(-0.1, 0.5).is_none());
+        assert!(CongestionThresholds::new(0.2, 1.1).is_none());
+        assert!(CongestionThresholds::new(f32::NAN, 0.5).is_none());
+    }
+
+    /// A 100 TU network whose current sighting lasted `secs` with `beacons`
+    /// beacons, after `earlier` beacons in previous sightings.
+    fn sighted(earlier: u32, beacons: u32, secs: u64) -> WiFiNetwork {
+        let mut network = network("00:00:00:00:00:01", 2437, -50);
+        network.beacon_interval = Some(100);
+        network.beacon_count = earlier + beacons;
+        network.sighting_beacon_count = beacons;
+        network.last_seen = network.first_seen + std::time::Duration::from_secs(secs);
+        network
+    }
+
+    #[test]
+    fn reappeared_network_rate_counts_only_the_current_sighting() {
+        // ~9.7 beacons/s now; the cumulative count would read as ~100/s
+        assert!(beacon_anomalies(&[sighted(900, 98, 10)]).is_empty());
+    }
+
+    #[test]
+    fn flooding_within_a_sighting_is_still_flagged() {
+        let kinds: Vec<AnomalyKind> = beacon_anomalies(&[sighted(900, 301, 10)])
+            .iter()
+            .map(|a| a.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [AnomalyKind::BeaconFlood, AnomalyKind::IntervalDrift]
+        );
+    }
+}
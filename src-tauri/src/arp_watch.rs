@@ -0,0 +1,318 @@
+use log::{debug, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::packet_sniffer::{Layer, PacketInfo};
+
+/// A MAC change for an IP is held this many seconds before it's alerted on,
+/// so a gateway failover that fails back doesn't raise one. Further changes
+/// within this many seconds of an alert are logged but not alerted on again,
+/// so two stations fighting over an address raise one alert rather than one
+/// per ARP reply.
+const REBIND_GRACE_SECS: u64 = 60;
+/// Changes during a held one after which the IP is alerted on at once, since
+/// stations are fighting over it. A failover and failback is one change.
+const FLIPS_BEFORE_ALERT: u32 = 2;
+/// Distinct IPs one MAC may hold before it's flagged. Routers answering
+/// proxy ARP legitimately go over this.
+const MAX_IPS_PER_MAC: usize = 4;
+// Bounds memory on a flood of forged ARP replies
+const MAX_BINDINGS: usize = 4096;
+const MAX_CONFLICTS: usize = 500;
+
+/// A sign of ARP spoofing, payload of the "arp_spoof_alert" event.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BindingConflict {
+    /// `ip` moved from `old_mac` to `new_mac` and stayed there for the grace
+    /// period, or kept moving between MACs. Either another station is
+    /// claiming the address or it legitimately moved, e.g. a gateway failover
+    /// that didn't fail back.
+    MacChanged {
+        ip: String,
+        old_mac: String,
+        new_mac: String,
+        /// When `old_mac` last claimed the IP
+        old_last_seen: u64,
+        detected_at: u64,
+    },
+    /// One MAC answering for more IPs than a single host normally has
+    ManyIps {
+        mac: String,
+        ips: Vec<String>,
+        detected_at: u64,
+    },
+}
+
+struct Binding {
+    mac: String,
+    last_seen: u64,
+    /// When a MAC change for the IP was last alerted on
+    alerted_at: Option<u64>,
+    /// A MAC change not alerted on yet
+    held: Option<HeldChange>,
+}
+
+/// The binding before a MAC change that's being held.
+struct HeldChange {
+    old_mac: String,
+    old_last_seen: u64,
+    since: u64,
+    /// Changes since the held one
+    flips: u32,
+}
+
+impl Binding {
+    /// Alerts on the move away from `held.old_mac`, unless the IP was alerted
+    /// on within the grace period.
+    fn alert(&mut self, ip: &str, held: HeldChange, now: u64) -> Option<BindingConflict> {
+        let repeat = self
+            .alerted_at
+            .is_some_and(|alerted_at| now.saturating_sub(alerted_at) < REBIND_GRACE_SECS);
+        if repeat {
+            debug!("{} moved from {} to {} again", ip, held.old_mac, self.mac);
+            return None;
+        }
+        self.alerted_at = Some(now);
+        Some(BindingConflict::MacChanged {
+            ip: ip.to_string(),
+            old_mac: held.old_mac,
+            new_mac: self.mac.clone(),
+            old_last_seen: held.old_last_seen,
+            detected_at: now,
+        })
+    }
+}
+
+/// IP to MAC bindings learned from ARP senders. Other traffic isn't used
+/// since routed packets carry the gateway's MAC with remote IPs.
+#[derive(Default)]
+pub struct BindingTracker {
+    bindings: HashMap<String, Binding>,
+    ips_by_mac: HashMap<String, HashSet<String>>,
+    flagged_macs: HashSet<String>,
+    conflicts: Vec<BindingConflict>,
+}
+
+impl BindingTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Records the binding an ARP packet announces, returning any conflicts
+    /// with what was seen before.
+    pub fn observe(&mut self, packet: &PacketInfo) -> Vec<BindingConflict> {
+        let Some((mac, ip)) = packet.layers.iter().find_map(|layer| match layer {
+            // 0.0.0.0 is an address probe, which claims nothing
+            Layer::Arp {
+                sender_mac,
+                sender_ip,
+                ..
+            } if sender_ip != "0.0.0.0" => Some((sender_mac, sender_ip)),
+            _ => None,
+        }) else {
+            return Vec::new();
+        };
+        let now = packet.timestamp;
+
+        let conflicts: Vec<BindingConflict> = [self.bind(ip, mac, now), self.claim(mac, ip, now)]
+            .into_iter()
+            .flatten()
+            .collect();
+        for conflict in &conflicts {
+            warn!("Possible ARP spoofing: {:?}", conflict);
+            if self.conflicts.len() >= MAX_CONFLICTS {
+                self.conflicts.remove(0);
+            }
+            self.conflicts.push(conflict.clone());
+        }
+        conflicts
+    }
+
+    pub fn conflicts(&self) -> Vec<BindingConflict> {
+        self.conflicts.clone()
+    }
+
+    /// Records that `mac` claimed `ip`. A change of MAC is held for the
+    /// grace period and alerted on by the first claim after it if the new
+    /// MAC still holds the IP, or at once if the IP keeps changing hands.
+    fn bind(&mut self, ip: &str, mac: &str, now: u64) -> Option<BindingConflict> {
+        let Some(binding) = self.bindings.get_mut(ip) else {
+            if self.bindings.len() < MAX_BINDINGS {
+                self.bindings.insert(
+                    ip.to_string(),
+                    Binding {
+                        mac: mac.to_string(),
+                        last_seen: now,
+                        alerted_at: None,
+                        held: None,
+                    },
+                );
+            }
+            return None;
+        };
+
+        let mut conflict = None;
+        let held_expired = binding
+            .held
+            .as_ref()
+            .is_some_and(|held| now.saturating_sub(held.since) >= REBIND_GRACE_SECS);
+        if held_expired {
+            let held = binding.held.take().expect("held change");
+            // Back on the old MAC means it was a blip, such as a failback
+            if binding.mac != held.old_mac {
+                conflict = binding.alert(ip, held, now);
+            }
+        }
+
+        if binding.mac == mac {
+            binding.last_seen = now;
+            return conflict;
+        }
+
+        if let Some(ips) = self.ips_by_mac.get_mut(&binding.mac) {
+            ips.remove(ip);
+        }
+        let old_mac = std::mem::replace(&mut binding.mac, mac.to_string());
+        let old_last_seen = std::mem::replace(&mut binding.last_seen, now);
+        match &mut binding.held {
+            None => {
+                debug!(
+                    "{} moved from {} to {}, holding the change",
+                    ip, old_mac, mac
+                );
+                binding.held = Some(HeldChange {
+                    old_mac,
+                    old_last_seen,
+                    since: now,
+                    flips: 0,
+                });
+            }
+            Some(held) => {
+                held.flips += 1;
+                if held.flips >= FLIPS_BEFORE_ALERT {
+                    let held = binding.held.take().expect("held change");
+                    conflict = conflict.or(binding.alert(ip, held, now));
+                }
+            }
+        }
+        conflict
+    }
+
+    fn claim(&mut self, mac: &str, ip: &str, now: u64) -> Option<BindingConflict> {
+        if self.ips_by_mac.len() >= MAX_BINDINGS && !self.ips_by_mac.contains_key(mac) {
+            return None;
+        }
+        let ips = self.ips_by_mac.entry(mac.to_string()).or_default();
+        ips.insert(ip.to_string());
+        // Flag each MAC once rather than on every further IP
+        if ips.len() <= MAX_IPS_PER_MAC || !self.flagged_macs.insert(mac.to_string()) {
+            return None;
+        }
+
+        let mut ips: Vec<String> = ips.iter().cloned().collect();
+        ips.sort();
+        Some(BindingConflict::ManyIps {
+            mac: mac.to_string(),
+            ips,
+            detected_at: now,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP: &str = "192.168.1.1";
+    const GATEWAY: &str = "00:11:22:33:44:55";
+    const ATTACKER: &str = "66:77:88:99:AA:BB";
+
+    #[test]
+    fn first_binding_and_repeats_are_not_conflicts() {
+        let mut tracker = BindingTracker::new();
+        assert!(tracker.bind(IP, GATEWAY, 100).is_none());
+        assert!(tracker.bind(IP, GATEWAY, 200).is_none());
+    }
+
+    #[test]
+    fn change_that_persists_past_the_grace_period_alerts() {
+        let mut tracker = BindingTracker::new();
+        tracker.bind(IP, GATEWAY, 100);
+
+        assert!(tracker.bind(IP, ATTACKER, 1000).is_none());
+        assert!(tracker
+            .bind(IP, ATTACKER, 1000 + REBIND_GRACE_SECS - 1)
+            .is_none());
+        let conflict = tracker.bind(IP, ATTACKER, 1000 + REBIND_GRACE_SECS);
+
+        assert!(matches!(
+            conflict,
+            Some(BindingConflict::MacChanged { ref old_mac, ref new_mac, old_last_seen: 100, detected_at, .. })
+                if old_mac == GATEWAY && new_mac == ATTACKER && detected_at == 1000 + REBIND_GRACE_SECS
+        ));
+        // Alerted once; the new MAC is the binding from here on
+        assert!(tracker.bind(IP, ATTACKER, 2000).is_none());
+    }
+
+    #[test]
+    fn failover_and_failback_within_the_grace_period_is_quiet() {
+        let mut tracker = BindingTracker::new();
+        tracker.bind(IP, GATEWAY, 100);
+
+        assert!(tracker.bind(IP, ATTACKER, 200).is_none());
+        assert!(tracker.bind(IP, GATEWAY, 230).is_none());
+        assert!(tracker.bind(IP, GATEWAY, 200 + REBIND_GRACE_SECS).is_none());
+        assert!(tracker.bind(IP, GATEWAY, 1000).is_none());
+    }
+
+    #[test]
+    fn flipping_back_and_forth_alerts_at_once_and_once() {
+        let mut tracker = BindingTracker::new();
+        tracker.bind(IP, GATEWAY, 100);
+
+        assert!(tracker.bind(IP, ATTACKER, 200).is_none());
+        assert!(tracker.bind(IP, GATEWAY, 201).is_none());
+        assert!(matches!(
+            tracker.bind(IP, ATTACKER, 202),
+            Some(BindingConflict::MacChanged {
+                detected_at: 202,
+                ..
+            })
+        ));
+        // Further fighting within the grace period since the alert is quiet
+        for now in 203..210 {
+            let mac = if now % 2 == 0 { ATTACKER } else { GATEWAY };
+            assert!(tracker.bind(IP, mac, now).is_none());
+        }
+        // Once the grace period since the alert is over, it alerts again
+        let later = 202 + REBIND_GRACE_SECS + 10;
+        let alerts = [ATTACKER, GATEWAY, ATTACKER, GATEWAY]
+            .into_iter()
+            .zip(later..)
+            .filter(|&(mac, now)| tracker.bind(IP, mac, now).is_some())
+            .count();
+        assert_eq!(alerts, 1);
+    }
+
+    #[test]
+    fn grace_period_is_per_ip() {
+        let mut tracker = BindingTracker::new();
+        tracker.bind(IP, GATEWAY, 100);
+        tracker.bind("192.168.1.2", GATEWAY, 100);
+
+        tracker.bind(IP, ATTACKER, 200);
+        tracker.bind("192.168.1.2", ATTACKER, 200);
+        assert!(tracker
+            .bind(IP, ATTACKER, 200 + REBIND_GRACE_SECS)
+            .is_some());
+        assert!(tracker
+            .bind("192.168.1.2", ATTACKER, 200 + REBIND_GRACE_SECS)
+            .is_some());
+    }
+}
@@ -0,0 +1,100 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::WifiError;
+use crate::export::open_capture_file;
+use crate::packet_sniffer::{parse_captured_packet, ParseDepth, CAPTURE_PRECISION};
+use crate::reassembly::Ipv4Reassembler;
+
+/// Parse time spent on packets of one protocol.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProtocolTiming {
+    pub protocol: String,
+    pub packets: u64,
+    pub total_micros: u64,
+    pub avg_nanos: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParseBenchmark {
+    pub packets: u64,
+    pub failed: u64,
+    pub bytes: u64,
+    pub elapsed_ms: f64,
+    pub packets_per_sec: f64,
+    pub mb_per_sec: f64,
+    /// Slowest protocols first
+    pub protocols: Vec<ProtocolTiming>,
+}
+
+/// Runs every packet of a capture file through the parser and measures
+/// throughput. Only parsing is timed, not reading the file.
+pub fn benchmark_file(path: &str) -> Result<ParseBenchmark, WifiError> {
+    let mut cap = open_capture_file(Path::new(path))?;
+    let linktype = cap.get_datalink();
+    let mut reassembler = Ipv4Reassembler::new();
+
+    let mut packets = 0u64;
+    let mut failed = 0u64;
+    let mut bytes = 0u64;
+    let mut elapsed = Duration::ZERO;
+    let mut timings: HashMap<String, (u64, Duration)> = HashMap::new();
+
+    loop {
+        let packet = match cap.next_packet() {
+            Ok(packet) => packet,
+            Err(pcap::Error::NoMorePackets) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let start = Instant::now();
+        let parsed = parse_captured_packet(
+            &packet,
+            linktype,
+            CAPTURE_PRECISION,
+            &mut reassembler,
+            ParseDepth::Application,
+        );
+        let took = start.elapsed();
+
+        elapsed += took;
+        packets += 1;
+        bytes += packet.data.len() as u64;
+        if parsed.malformed.is_some() {
+            failed += 1;
+        }
+        let entry = timings.entry(parsed.protocol).or_default();
+        entry.0 += 1;
+        entry.1 += took;
+    }
+
+    let secs = elapsed.as_secs_f64();
+    let rate = |amount: f64| if secs > 0.0 { amount / secs } else { 0.0 };
+    let mut protocols: Vec<ProtocolTiming> = timings
+        .into_iter()
+        .map(|(protocol, (count, total))| ProtocolTiming {
+            protocol,
+            packets: count,
+            total_micros: total.as_micros() as u64,
+            avg_nanos: (total.as_nanos() / u128::from(count)) as u64,
+        })
+        .collect();
+    protocols.sort_by_key(|p| std::cmp::Reverse(p.total_micros));
+
+    info!(
+        "Parsed {} packets ({} failed) from {} in {:?}",
+        packets, failed, path, elapsed
+    );
+    Ok(ParseBenchmark {
+        packets,
+        failed,
+        bytes,
+        elapsed_ms: secs * 1000.0,
+        packets_per_sec: rate(packets as f64),
+        mb_per_sec: rate(bytes as f64 / 1_000_000.0),
+        protocols,
+    })
+}
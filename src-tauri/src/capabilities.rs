@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::process::Command;
+
+use crate::error::WifiError;
+use crate::spectrum::{channel_number, Band};
+
+/// Channels an adapter can use in one band.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BandSupport {
+    pub band: Band,
+    /// Enabled 20 MHz channels; channels the regulatory domain disables are
+    /// left out
+    pub channels: Vec<u32>,
+}
+
+/// Bands and channels the radio behind an interface supports.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterfaceCapabilities {
+    pub interface: String,
+    pub phy: String,
+    pub bands: Vec<BandSupport>,
+}
+
+impl InterfaceCapabilities {
+    pub fn supports(&self, band: Band) -> bool {
+        self.bands
+            .iter()
+            .any(|support| support.band == band && !support.channels.is_empty())
+    }
+}
+
+/// Looks up the wiphy behind `interface` and asks nl80211, through
+/// `iw phy <phy> info`, which frequencies it can tune to. Linux only.
+pub fn interface_capabilities(interface: &str) -> Result<InterfaceCapabilities, WifiError> {
+    let phy = fs::read_to_string(format!("/sys/class/net/{}/phy80211/name", interface))
+        .map(|name| name.trim().to_string())
+        .map_err(|e| {
+            WifiError::DeviceNotFound(format!("{} is not a wireless interface: {}", interface, e))
+        })?;
+
+    let output = Command::new("iw")
+        .args(["phy", &phy, "info"])
+        .output()
+        .map_err(|e| WifiError::CaptureError(format!("Failed to run iw: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(WifiError::DeviceNotFound(format!(
+            "iw can't query {}: {}",
+            phy,
+            stderr.trim()
+        )));
+    }
+
+    Ok(InterfaceCapabilities {
+        interface: interface.to_string(),
+        bands: parse_phy_frequencies(&String::from_utf8_lossy(&output.stdout)),
+        phy,
+    })
+}
+
+/// Collects the frequency list lines of `iw phy info`, which look like:
+///
+/// ```text
+///         Frequencies:
+///                 * 2412.0 MHz [1] (20.0 dBm)
+///                 * 5260.0 MHz [52] (20.0 dBm) (radar detection)
+///                 * 5500.0 MHz [100] (disabled)
+/// ```
+fn parse_phy_frequencies(output: &str) -> Vec<BandSupport> {
+    let mut bands: Vec<BandSupport> = Vec::new();
+
+    for line in output.lines().map(str::trim) {
+        let Some(entry) = line.strip_prefix("* ") else {
+            continue;
+        };
+        if !entry.contains(" MHz") || entry.contains("(disabled)") {
+            continue;
+        }
+        // Newer iw versions print fractional frequencies like "2412.0"
+        let Some(frequency) = entry
+            .split([' ', '.'])
+            .next()
+            .and_then(|f| f.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Some(band) = Band::from_frequency(frequency) else {
+            continue;
+        };
+
+        let channel = channel_number(frequency, None);
+        match bands.iter_mut().find(|support| support.band == band) {
+            Some(support) => support.channels.push(channel),
+            None => bands.push(BandSupport {
+                band,
+                channels: vec![channel],
+            }),
+        }
+    }
+
+    bands
+}
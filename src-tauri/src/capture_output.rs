@@ -0,0 +1,243 @@
+use log::{error, info, warn};
+use pcap::{Packet, Savefile};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::WifiError;
+use crate::export::gzip_file;
+use crate::timestamp::format_basic_utc;
+
+pub const DEFAULT_TEMPLATE: &str = "{interface}_{timestamp}.pcap";
+const PLACEHOLDERS: [&str; 3] = ["{interface}", "{timestamp}", "{index}"];
+// Guards against a directory full of numbered captures
+const MAX_INDEX: u32 = 10_000;
+
+/// Where saved captures go and how they're named.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureOutput {
+    pub directory: PathBuf,
+    /// File name with `{interface}`, `{timestamp}` (compact UTC) and
+    /// `{index}` (lowest number not already taken) placeholders
+    pub template: String,
+}
+
+/// A change to the file the capture thread records raw packets to.
+pub enum SaveRequest {
+    /// Record to the path, gzipping the file once recording stops if set
+    Start(PathBuf, bool),
+    Stop,
+}
+
+/// A pcap file the capture thread is recording to.
+pub struct ActiveSave {
+    file: Savefile,
+    path: PathBuf,
+    compress: bool,
+}
+
+impl ActiveSave {
+    pub fn new(file: Savefile, path: PathBuf, compress: bool) -> Self {
+        ActiveSave {
+            file,
+            path,
+            compress,
+        }
+    }
+
+    pub fn write(&mut self, packet: &Packet) {
+        self.file.write(packet);
+    }
+
+    /// Closes the file and, if requested, compresses it on its own thread so
+    /// the capture isn't held up by a large file.
+    pub fn finish(mut self) {
+        if let Err(e) = self.file.flush() {
+            warn!("Error flushing capture file: {}", e);
+        }
+        // Dropping the savefile closes it, so it's complete before compressing
+        drop(self.file);
+        info!("Stopped saving captured packets to {}", self.path.display());
+
+        if self.compress {
+            let path = self.path;
+            thread::spawn(move || match gzip_file(&path) {
+                Ok(compressed) => info!("Compressed capture to {}", compressed.display()),
+                Err(e) => error!("{}", e),
+            });
+        }
+    }
+}
+
+impl Default for CaptureOutput {
+    fn default() -> Self {
+        CaptureOutput {
+            directory: std::env::temp_dir(),
+            template: DEFAULT_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl CaptureOutput {
+    /// Validates the settings, creating `directory` if needed and checking
+    /// that files can be written there.
+    pub fn new(directory: PathBuf, template: String) -> Result<Self, WifiError> {
+        let template = template.trim().to_string();
+        if template.is_empty() {
+            return Err(WifiError::CaptureError(
+                "File name template is empty".to_string(),
+            ));
+        }
+        if template.contains(['/', '\\']) {
+            return Err(WifiError::CaptureError(format!(
+                "File name template '{}' must not contain path separators",
+                template
+            )));
+        }
+        let unknown = unknown_placeholder(&template);
+        if let Some(unknown) = unknown {
+            return Err(WifiError::CaptureError(format!(
+                "Unknown placeholder '{}' in file name template",
+                unknown
+            )));
+        }
+
+        check_writable(&directory)?;
+        Ok(CaptureOutput {
+            directory,
+            template,
+        })
+    }
+
+    /// Resolves the template into a path for a new capture of `interface`.
+    /// Without `{index}`, an existing file of the same name is an error
+    /// rather than being overwritten. The directory is created and checked
+    /// here too, since the default one may not exist yet or have become
+    /// unwritable since the settings were saved.
+    pub fn next_path(&self, interface: &str) -> Result<PathBuf, WifiError> {
+        check_writable(&self.directory)?;
+
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        let name = self
+            .template
+            .replace("{interface}", &sanitize(interface))
+            .replace("{timestamp}", &format_basic_utc(secs as i64));
+
+        if !name.contains("{index}") {
+            let path = self.directory.join(&name);
+            if path.exists() {
+                return Err(WifiError::CaptureError(format!(
+                    "{} already exists; add {{index}} to the template to number captures",
+                    path.display()
+                )));
+            }
+            return Ok(path);
+        }
+
+        (1..=MAX_INDEX)
+            .map(|index| {
+                self.directory
+                    .join(name.replace("{index}", &index.to_string()))
+            })
+            .find(|path| !path.exists())
+            .ok_or_else(|| {
+                WifiError::CaptureError(format!(
+                    "No free capture index in {}",
+                    self.directory.display()
+                ))
+            })
+    }
+}
+
+fn unknown_placeholder(template: &str) -> Option<&str> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')? + start + 1;
+        let placeholder = &rest[start..end];
+        if !PLACEHOLDERS.contains(&placeholder) {
+            return Some(placeholder);
+        }
+        rest = &rest[end..];
+    }
+    None
+}
+
+/// Keeps interface names like `\Device\NPF_{GUID}` from escaping the directory.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn check_writable(directory: &Path) -> Result<(), WifiError> {
+    let not_writable = |e: std::io::Error| {
+        WifiError::PermissionDenied(format!(
+            "Can't write captures to {}: {}",
+            directory.display(),
+            e
+        ))
+    };
+
+    fs::create_dir_all(directory).map_err(not_writable)?;
+    let probe = directory.join(".wifi-analyzer-write-test");
+    fs::write(&probe, b"").map_err(not_writable)?;
+    fs::remove_file(&probe).map_err(not_writable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wifi-analyzer-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn next_path_creates_a_missing_directory() {
+        let root = scratch_dir("missing");
+        let output = CaptureOutput {
+            directory: root.join("captures"),
+            template: "{interface}_{index}.pcap".to_string(),
+        };
+
+        let path = output.next_path("wlan0").unwrap();
+
+        assert!(output.directory.is_dir());
+        assert_eq!(path, output.directory.join("wlan0_1.pcap"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn next_path_fails_when_the_directory_is_a_file() {
+        let root = scratch_dir("file");
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("captures");
+        fs::write(&file, b"").unwrap();
+        let output = CaptureOutput {
+            directory: file,
+            template: DEFAULT_TEMPLATE.to_string(),
+        };
+
+        let result = output.next_path("wlan0");
+
+        assert!(matches!(result, Err(WifiError::PermissionDenied(_))));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
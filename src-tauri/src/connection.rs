@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::error::WifiError;
+use crate::spectrum::{channel_number, Band};
+
+/// The network an interface is currently associated with.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CurrentConnection {
+    pub interface: String,
+    pub bssid: String,
+    pub ssid: String,
+    pub frequency: u32,
+    pub channel: u32,
+    pub band: Option<Band>,
+    pub signal_dbm: Option<i32>,
+}
+
+/// Asks the OS which network `interface` is associated with, or None when
+/// it isn't connected. Uses `iw dev <interface> link`, so Linux only.
+pub fn current_connection(interface: &str) -> Result<Option<CurrentConnection>, WifiError> {
+    let output = Command::new("iw")
+        .args(["dev", interface, "link"])
+        .output()
+        .map_err(|e| WifiError::CaptureError(format!("Failed to run iw: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(WifiError::DeviceNotFound(format!(
+            "iw can't query {}: {}",
+            interface,
+            stderr.trim()
+        )));
+    }
+
+    Ok(parse_iw_link(
+        interface,
+        &String::from_utf8_lossy(&output.stdout),
+    ))
+}
+
+/// Parses `iw dev <interface> link` output, which looks like:
+///
+/// ```text
+/// Connected to aa:bb:cc:dd:ee:ff (on wlan0)
+///         SSID: Home
+///         freq: 5180
+///         signal: -52 dBm
+/// ```
+///
+/// or "Not connected." when the interface isn't associated.
+fn parse_iw_link(interface: &str, output: &str) -> Option<CurrentConnection> {
+    let mut lines = output.lines().map(str::trim);
+    let bssid = lines
+        .next()?
+        .strip_prefix("Connected to ")?
+        .split_whitespace()
+        .next()?
+        // Upper case like the scanner's BSSIDs, so the two can be matched
+        .to_uppercase();
+
+    let mut ssid = String::new();
+    let mut frequency = 0;
+    let mut signal_dbm = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "SSID" => ssid = value.to_string(),
+            // Newer iw versions print fractional frequencies like "5180.0"
+            "freq" => {
+                frequency = value
+                    .split('.')
+                    .next()
+                    .and_then(|f| f.parse().ok())
+                    .unwrap_or(0)
+            }
+            "signal" => signal_dbm = value.split_whitespace().next().and_then(|s| s.parse().ok()),
+            _ => {}
+        }
+    }
+
+    Some(CurrentConnection {
+        interface: interface.to_string(),
+        bssid,
+        ssid,
+        frequency,
+        channel: channel_number(frequency, None),
+        band: Band::from_frequency(frequency),
+        signal_dbm,
+    })
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::spectrum::channel_number;
+
+/// RTS frames per CTS above which a channel is reported as likely having
+/// hidden nodes: RTS frames that go unanswered are colliding at a receiver
+/// their sender can't hear.
+const HIDDEN_NODE_RTS_CTS_RATIO: f64 = 1.5;
+/// RTS frames needed before the ratio means anything.
+const MIN_RTS_FOR_RATIO: u64 = 20;
+
+const SUBTYPE_BLOCK_ACK_REQUEST: u16 = 8;
+const SUBTYPE_BLOCK_ACK: u16 = 9;
+const SUBTYPE_RTS: u16 = 11;
+const SUBTYPE_CTS: u16 = 12;
+const SUBTYPE_ACK: u16 = 13;
+
+/// Frames of one control subtype and the airtime they reserved.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct ControlTypeStats {
+    pub frames: u64,
+    /// Sum of the frames' Duration fields, the time they hold the medium
+    /// for the rest of the exchange, in microseconds
+    pub airtime_us: u64,
+}
+
+impl ControlTypeStats {
+    fn record(&mut self, duration_us: Option<u16>) {
+        self.frames += 1;
+        self.airtime_us += duration_us.unwrap_or(0) as u64;
+    }
+}
+
+/// Control frames heard on one channel during a scan.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ControlFrameStats {
+    pub channel: u32,
+    pub frequency: u32,
+    pub rts: ControlTypeStats,
+    pub cts: ControlTypeStats,
+    pub ack: ControlTypeStats,
+    pub block_ack_request: u64,
+    pub block_ack: u64,
+    pub other: u64,
+    /// RTS frames per CTS, None until a CTS is heard
+    pub rts_cts_ratio: Option<f64>,
+    pub hidden_node_suspected: bool,
+}
+
+/// Control frame counts per frequency.
+#[derive(Default)]
+pub struct ControlFrameTracker {
+    channels: HashMap<u32, ControlFrameStats>,
+}
+
+impl ControlFrameTracker {
+    /// Records a control frame heard on `frequency` MHz. `duration` is its
+    /// Duration/ID field, if it could be read.
+    pub fn record(&mut self, frequency: u32, frame_control: u16, duration: Option<u16>) {
+        let stats = self
+            .channels
+            .entry(frequency)
+            .or_insert_with(|| ControlFrameStats {
+                channel: channel_number(frequency, None),
+                frequency,
+                ..Default::default()
+            });
+        // Values with bit 15 set are association IDs, not durations
+        let duration = duration.filter(|d| d & 0x8000 == 0);
+
+        match (frame_control & 0x00F0) >> 4 {
+            SUBTYPE_RTS => stats.rts.record(duration),
+            SUBTYPE_CTS => stats.cts.record(duration),
+            SUBTYPE_ACK => stats.ack.record(duration),
+            SUBTYPE_BLOCK_ACK_REQUEST => stats.block_ack_request += 1,
+            SUBTYPE_BLOCK_ACK => stats.block_ack += 1,
+            _ => stats.other += 1,
+        }
+    }
+
+    /// Per-channel statistics, ordered by frequency.
+    pub fn stats(&self) -> Vec<ControlFrameStats> {
+        let mut stats: Vec<ControlFrameStats> = self
+            .channels
+            .values()
+            .map(|channel| {
+                let mut channel = channel.clone();
+                channel.rts_cts_ratio = (channel.cts.frames > 0)
+                    .then(|| channel.rts.frames as f64 / channel.cts.frames as f64);
+                channel.hidden_node_suspected = channel.rts.frames >= MIN_RTS_FOR_RATIO
+                    && channel
+                        .rts_cts_ratio
+                        .is_none_or(|ratio| ratio > HIDDEN_NODE_RTS_CTS_RATIO);
+                channel
+            })
+            .collect();
+        stats.sort_by_key(|channel| channel.frequency);
+        stats
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+
+use crate::wifi_scanner::WiFiNetwork;
+
+/// What may follow a default SSID's fixed prefix.
+#[derive(Clone, Copy)]
+enum Suffix {
+    /// Nothing: the SSID is the prefix alone
+    None,
+    /// Exactly this many decimal digits
+    Digits(usize),
+    /// Exactly this many hex digits, usually the end of the BSSID
+    Hex(usize),
+    /// Between the two counts of hex digits, inclusive
+    HexRange(usize, usize),
+}
+
+impl Suffix {
+    fn matches(self, rest: &str) -> bool {
+        let hex = |min: usize, max: usize| {
+            (min..=max).contains(&rest.len()) && rest.chars().all(|c| c.is_ascii_hexdigit())
+        };
+        match self {
+            Suffix::None => rest.is_empty(),
+            Suffix::Digits(n) => rest.len() == n && rest.chars().all(|c| c.is_ascii_digit()),
+            Suffix::Hex(n) => hex(n, n),
+            Suffix::HexRange(min, max) => hex(min, max),
+        }
+    }
+
+    fn describe(self) -> String {
+        match self {
+            Suffix::None => String::new(),
+            Suffix::Digits(n) => "#".repeat(n),
+            Suffix::Hex(n) => "X".repeat(n),
+            Suffix::HexRange(min, max) => format!("X{{{},{}}}", min, max),
+        }
+    }
+}
+
+/// (vendor, SSID prefix, suffix). Prefixes match case-insensitively.
+const DEFAULT_SSIDS: &[(&str, &str, Suffix)] = &[
+    ("NETGEAR", "NETGEAR", Suffix::None),
+    ("NETGEAR", "NETGEAR", Suffix::Digits(2)),
+    ("NETGEAR", "NETGEAR", Suffix::Digits(3)),
+    ("Linksys", "linksys", Suffix::None),
+    ("Linksys", "Linksys", Suffix::Digits(5)),
+    ("D-Link", "dlink", Suffix::None),
+    ("D-Link", "dlink-", Suffix::Hex(4)),
+    ("D-Link", "DIR-", Suffix::Digits(3)),
+    ("TP-Link", "TP-Link_", Suffix::Hex(4)),
+    ("TP-Link", "TP-Link_", Suffix::Hex(6)),
+    ("ASUS", "ASUS", Suffix::None),
+    ("ASUS", "ASUS_", Suffix::HexRange(2, 4)),
+    ("Belkin", "belkin.", Suffix::HexRange(3, 4)),
+    ("Huawei", "HUAWEI-", Suffix::Hex(4)),
+    ("ZTE", "ZTE_", Suffix::Hex(4)),
+    ("Tenda", "Tenda_", Suffix::Hex(6)),
+    ("MERCURY", "MERCURY_", Suffix::Hex(4)),
+    ("Xiaomi", "Xiaomi_", Suffix::Hex(4)),
+    ("Ubiquiti", "UBNT", Suffix::None),
+    ("AVM", "FRITZ!Box 7490", Suffix::None),
+    ("AVM", "FRITZ!Box 7530", Suffix::None),
+    ("AVM", "FRITZ!Box 7590", Suffix::None),
+    ("BT", "BTHub6-", Suffix::Hex(4)),
+    ("BT", "BT-", Suffix::Hex(6)),
+    ("Sky", "SKY", Suffix::Hex(5)),
+    ("Virgin Media", "VM", Suffix::Digits(7)),
+    ("TalkTalk", "TALKTALK", Suffix::Hex(6)),
+    ("Plusnet", "PLUSNET-", Suffix::Hex(4)),
+    ("EE", "EE-", Suffix::Hex(6)),
+    ("Vodafone", "Vodafone-", Suffix::Hex(6)),
+    ("Vodafone", "VodafoneConnect", Suffix::Digits(8)),
+    ("Orange", "Livebox-", Suffix::Hex(4)),
+    ("Bouygues", "Bbox-", Suffix::Hex(8)),
+    ("SFR", "SFR_", Suffix::Hex(4)),
+    ("Ziggo", "Ziggo", Suffix::Digits(7)),
+    ("UPC", "UPC", Suffix::Digits(7)),
+    ("AT&T", "ATT", Suffix::HexRange(6, 7)),
+    ("Spectrum", "MySpectrumWiFi", Suffix::Hex(2)),
+    ("Telus", "TELUS", Suffix::Digits(4)),
+    ("Bell", "BELL", Suffix::Digits(3)),
+    ("Generic", "default", Suffix::None),
+    ("Generic", "wireless", Suffix::None),
+    ("Generic", "WLAN", Suffix::None),
+];
+
+/// A network whose SSID looks like its vendor's out-of-the-box name. Such
+/// routers often still have the default password and WPS enabled too.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DefaultNetwork {
+    pub bssid: String,
+    pub ssid: String,
+    pub vendor: String,
+    /// The default naming scheme matched, e.g. "NETGEAR##"
+    pub pattern: String,
+    pub wps: bool,
+}
+
+/// Matches `ssid` against the known default SSIDs, returning the vendor and
+/// a description of the pattern.
+fn match_default(ssid: &str) -> Option<(&'static str, String)> {
+    DEFAULT_SSIDS.iter().find_map(|&(vendor, prefix, suffix)| {
+        let head = ssid.get(..prefix.len())?;
+        let rest = &ssid[prefix.len()..];
+        (head.eq_ignore_ascii_case(prefix) && suffix.matches(rest))
+            .then(|| (vendor, format!("{}{}", prefix, suffix.describe())))
+    })
+}
+
+pub fn default_networks(networks: &[WiFiNetwork]) -> Vec<DefaultNetwork> {
+    networks
+        .iter()
+        .filter_map(|network| {
+            let (vendor, pattern) = match_default(&network.ssid)?;
+            Some(DefaultNetwork {
+                bssid: network.bssid.clone(),
+                ssid: network.ssid.clone(),
+                vendor: vendor.to_string(),
+                pattern,
+                wps: network.wps,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiotap::SsidState;
+
+    fn vendor(ssid: &str) -> Option<&'static str> {
+        match_default(ssid).map(|(vendor, _)| vendor)
+    }
+
+    #[test]
+    fn vendor_prefix_with_its_suffix() {
+        assert_eq!(
+            match_default("TP-Link_A1B2"),
+            Some(("TP-Link", "TP-Link_XXXX".to_string()))
+        );
+        assert_eq!(
+            match_default("NETGEAR42"),
+            Some(("NETGEAR", "NETGEAR##".to_string()))
+        );
+        assert_eq!(
+            match_default("belkin.1a2"),
+            Some(("Belkin", "belkin.X{3,4}".to_string()))
+        );
+    }
+
+    #[test]
+    fn prefixes_match_case_insensitively() {
+        assert_eq!(vendor("netgear"), Some("NETGEAR"));
+        assert_eq!(vendor("tp-link_a1b2c3"), Some("TP-Link"));
+    }
+
+    #[test]
+    fn lookalikes_do_not_match() {
+        // Wrong suffix length, non-hex suffix, extra text and a renamed SSID
+        for ssid in [
+            "TP-Link_A1B",
+            "TP-Link_A1B2C",
+            "HUAWEI-GHIJ",
+            "ASUS_5G",
+            "NETGEAR-Guest",
+            "NETGEAR1234",
+            "My NETGEAR",
+            "Linksys1234",
+            "",
+        ] {
+            assert_eq!(vendor(ssid), None, "{:?}", ssid);
+        }
+    }
+
+    #[test]
+    fn multibyte_ssids_do_not_panic() {
+        assert_eq!(vendor("ÄSUS"), None);
+        assert_eq!(vendor("TP-Link_ÄÖ"), None);
+    }
+
+    #[test]
+    fn default_networks_carry_vendor_and_wps() {
+        let mut network = WiFiNetwork::new(
+            "00:11:22:33:44:55".to_string(),
+            "Livebox-1A2B".to_string(),
+            SsidState::Broadcast("Livebox-1A2B".to_string()),
+            2437,
+            Some(6),
+        );
+        network.wps = true;
+        let renamed = WiFiNetwork::new(
+            "00:11:22:33:44:56".to_string(),
+            "Smith family".to_string(),
+            SsidState::Broadcast("Smith family".to_string()),
+            2437,
+            Some(6),
+        );
+
+        let defaults = default_networks(&[network, renamed]);
+        assert_eq!(defaults.len(), 1);
+        assert_eq!(defaults[0].bssid, "00:11:22:33:44:55");
+        assert_eq!(defaults[0].vendor, "Orange");
+        assert_eq!(defaults[0].pattern, "Livebox-XXXX");
+        assert!(defaults[0].wps);
+    }
+}
@@ -0,0 +1,70 @@
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use crate::error::WifiError;
+
+/// Which subsystem currently holds a capture device open.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceUser {
+    Scan,
+    Capture,
+    SignalMeter,
+}
+
+impl fmt::Display for DeviceUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceUser::Scan => write!(f, "scan"),
+            DeviceUser::Capture => write!(f, "capture"),
+            DeviceUser::SignalMeter => write!(f, "signal meter"),
+        }
+    }
+}
+
+/// Tracks open devices so the scanner and the sniffer don't open the same
+/// adapter twice and hit an opaque pcap "device busy" error.
+#[derive(Default)]
+pub struct DeviceRegistry {
+    in_use: Arc<Mutex<HashMap<String, DeviceUser>>>,
+}
+
+impl DeviceRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Claims `device` for `user`. The claim is released when the returned
+    /// lease is dropped.
+    pub fn acquire(&self, device: &str, user: DeviceUser) -> Result<DeviceLease, WifiError> {
+        let mut in_use = self.in_use.lock().unwrap();
+        if let Some(current) = in_use.get(device) {
+            return Err(WifiError::DeviceBusy(format!(
+                "device {} already in use by {}",
+                device, current
+            )));
+        }
+
+        debug!("Device {} acquired by {}", device, user);
+        in_use.insert(device.to_string(), user);
+        Ok(DeviceLease {
+            device: device.to_string(),
+            in_use: Arc::clone(&self.in_use),
+        })
+    }
+}
+
+pub struct DeviceLease {
+    device: String,
+    in_use: Arc<Mutex<HashMap<String, DeviceUser>>>,
+}
+
+impl Drop for DeviceLease {
+    fn drop(&mut self) {
+        debug!("Device {} released", self.device);
+        self.in_use.lock().unwrap().remove(&self.device);
+    }
+}
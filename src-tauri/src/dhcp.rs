@@ -0,0 +1,108 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCP_CLIENT_PORT: u16 = 68;
+
+const MAGIC_COOKIE: [u8; 4] = [0x63, 0x82, 0x53, 0x63];
+// Fixed BOOTP header (236) + magic cookie (4)
+const OPTIONS_OFFSET: usize = 240;
+const CHADDR_OFFSET: usize = 28;
+
+// Options
+const OPTION_PAD: u8 = 0;
+const OPTION_REQUESTED_IP: u8 = 50;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_END: u8 = 255;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct DhcpInfo {
+    pub message_type: Option<u8>,
+    /// Client hardware address from the BOOTP header
+    pub client_mac: String,
+    /// Requested IP (option 50), or the assigned address in server replies
+    pub requested_ip: Option<String>,
+}
+
+impl DhcpInfo {
+    pub fn message_name(&self) -> &'static str {
+        match self.message_type {
+            Some(1) => "DISCOVER",
+            Some(2) => "OFFER",
+            Some(3) => "REQUEST",
+            Some(4) => "DECLINE",
+            Some(5) => "ACK",
+            Some(6) => "NAK",
+            Some(7) => "RELEASE",
+            Some(8) => "INFORM",
+            _ => "BOOTP",
+        }
+    }
+}
+
+/// Parses a DHCP message (the UDP payload on ports 67/68).
+pub fn parse_dhcp(data: &[u8]) -> Option<DhcpInfo> {
+    if data.len() < OPTIONS_OFFSET || data[236..240] != MAGIC_COOKIE {
+        return None;
+    }
+
+    // Only Ethernet hardware addresses are meaningful to us
+    if data[1] != 1 || data[2] != 6 {
+        return None;
+    }
+
+    let client_mac = data[CHADDR_OFFSET..CHADDR_OFFSET + 6]
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":");
+
+    let mut message_type = None;
+    let mut requested_ip = None;
+
+    let mut offset = OPTIONS_OFFSET;
+    while offset < data.len() {
+        let code = data[offset];
+        match code {
+            OPTION_PAD => {
+                offset += 1;
+                continue;
+            }
+            OPTION_END => break,
+            _ => {}
+        }
+
+        if offset + 2 > data.len() {
+            break;
+        }
+        let len = data[offset + 1] as usize;
+        let value = match data.get(offset + 2..offset + 2 + len) {
+            Some(value) => value,
+            None => break,
+        };
+
+        match code {
+            OPTION_MESSAGE_TYPE if len == 1 => message_type = Some(value[0]),
+            OPTION_REQUESTED_IP if len == 4 => {
+                requested_ip = Some(Ipv4Addr::new(value[0], value[1], value[2], value[3]));
+            }
+            _ => {}
+        }
+        offset += 2 + len;
+    }
+
+    // Server replies carry the lease in yiaddr instead of option 50
+    if requested_ip.is_none() {
+        let yiaddr = Ipv4Addr::new(data[16], data[17], data[18], data[19]);
+        if !yiaddr.is_unspecified() {
+            requested_ip = Some(yiaddr);
+        }
+    }
+
+    Some(DhcpInfo {
+        message_type,
+        client_mac,
+        requested_ip: requested_ip.map(|ip| ip.to_string()),
+    })
+}
@@ -0,0 +1,284 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const ETHERTYPE_EAPOL: u16 = 0x888E;
+
+// EAPOL packet types
+const EAPOL_TYPE_EAP_PACKET: u8 = 0;
+const EAPOL_TYPE_KEY: u8 = 3;
+
+// EAP codes and the types that don't name a method
+const EAP_CODE_REQUEST: u8 = 1;
+const EAP_CODE_RESPONSE: u8 = 2;
+const EAP_TYPE_IDENTITY: u8 = 1;
+const EAP_TYPE_NOTIFICATION: u8 = 2;
+const EAP_TYPE_NAK: u8 = 3;
+
+// Key information bits
+const KEY_INFO_PAIRWISE: u16 = 0x0008;
+const KEY_INFO_INSTALL: u16 = 0x0040;
+const KEY_INFO_ACK: u16 = 0x0080;
+const KEY_INFO_MIC: u16 = 0x0100;
+const KEY_INFO_SECURE: u16 = 0x0200;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct EapolKeyInfo {
+    pub key_info: u16,
+    pub replay_counter: u64,
+    /// 4-way handshake message number (1-4), if it could be identified
+    pub handshake_message: Option<u8>,
+}
+
+/// Parses an EAPOL frame (the bytes following the 0x888E ethertype) and, for
+/// EAPOL-Key frames, identifies the 4-way handshake message.
+pub fn parse_eapol_key(data: &[u8]) -> Option<EapolKeyInfo> {
+    // EAPOL header (4) + descriptor type (1) + key info (2)
+    // + key length (2) + replay counter (8)
+    if data.len() < 17 || data[1] != EAPOL_TYPE_KEY {
+        return None;
+    }
+
+    let key_info = u16::from_be_bytes([data[5], data[6]]);
+    let replay_counter = u64::from_be_bytes([
+        data[9], data[10], data[11], data[12], data[13], data[14], data[15], data[16],
+    ]);
+
+    Some(EapolKeyInfo {
+        key_info,
+        replay_counter,
+        handshake_message: handshake_message(key_info),
+    })
+}
+
+fn handshake_message(key_info: u16) -> Option<u8> {
+    if key_info & KEY_INFO_PAIRWISE == 0 {
+        // Group key handshake, not part of the 4-way handshake
+        return None;
+    }
+
+    let ack = key_info & KEY_INFO_ACK != 0;
+    let mic = key_info & KEY_INFO_MIC != 0;
+    let install = key_info & KEY_INFO_INSTALL != 0;
+    let secure = key_info & KEY_INFO_SECURE != 0;
+
+    match (ack, mic, install, secure) {
+        (true, false, _, _) => Some(1),
+        (false, true, _, false) => Some(2),
+        (true, true, true, _) => Some(3),
+        (false, true, _, true) => Some(4),
+        _ => None,
+    }
+}
+
+/// EAP method, from the type field of an EAP request or response.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EapMethod {
+    Md5,
+    Tls,
+    Sim,
+    Ttls,
+    Aka,
+    Peap,
+    MsChapV2,
+    Fast,
+    AkaPrime,
+    Other(u8),
+}
+
+impl From<u8> for EapMethod {
+    fn from(number: u8) -> Self {
+        match number {
+            4 => EapMethod::Md5,
+            13 => EapMethod::Tls,
+            18 => EapMethod::Sim,
+            21 => EapMethod::Ttls,
+            23 => EapMethod::Aka,
+            25 => EapMethod::Peap,
+            26 => EapMethod::MsChapV2,
+            43 => EapMethod::Fast,
+            50 => EapMethod::AkaPrime,
+            n => EapMethod::Other(n),
+        }
+    }
+}
+
+/// What an EAP request or response reveals about an 802.1X exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EapInfo {
+    /// The identity a client answered with, sent in the clear before the
+    /// tunnel of PEAP or TTLS is set up
+    Identity(String),
+    Method(EapMethod),
+}
+
+/// Parses an EAPOL frame (the bytes following the 0x888E ethertype) carrying
+/// an EAP request or response. Notifications, NAKs and success/failure
+/// packets give None.
+pub fn parse_eap(data: &[u8]) -> Option<EapInfo> {
+    // EAPOL header (4), then EAP code (1), identifier (1), length (2), type (1)
+    if data.len() < 9 || data[1] != EAPOL_TYPE_EAP_PACKET {
+        return None;
+    }
+    let code = data[4];
+    if code != EAP_CODE_REQUEST && code != EAP_CODE_RESPONSE {
+        return None;
+    }
+
+    let eap_length = u16::from_be_bytes([data[6], data[7]]) as usize;
+    let eap_end = (4 + eap_length).min(data.len());
+    match data[8] {
+        EAP_TYPE_IDENTITY if code == EAP_CODE_RESPONSE => {
+            let identity = String::from_utf8_lossy(data.get(9..eap_end)?);
+            let identity = identity.trim_end_matches('\0');
+            (!identity.is_empty()).then(|| EapInfo::Identity(identity.to_string()))
+        }
+        EAP_TYPE_IDENTITY | EAP_TYPE_NOTIFICATION | EAP_TYPE_NAK => None,
+        method => Some(EapInfo::Method(EapMethod::from(method))),
+    }
+}
+
+/// Hides the user part of an EAP identity but keeps the realm, which is
+/// enough to tell which RADIUS server a client is sent to.
+pub fn redact_identity(identity: &str) -> String {
+    match identity.rfind('@') {
+        Some(at) => format!("***{}", &identity[at..]),
+        None => "***".to_string(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HandshakeStatus {
+    pub ap_mac: String,
+    pub client_mac: String,
+    pub messages_seen: [bool; 4],
+    pub complete: bool,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+/// Tracks 4-way handshake progress per (AP, client) pair.
+#[derive(Default)]
+pub struct HandshakeTracker {
+    handshakes: HashMap<(String, String), HandshakeStatus>,
+}
+
+impl HandshakeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an observed handshake message. Messages 1 and 3 are sent by
+    /// the AP, messages 2 and 4 by the client.
+    pub fn observe(&mut self, src_mac: &str, dst_mac: &str, message: u8, timestamp: u64) {
+        if !(1..=4).contains(&message) {
+            return;
+        }
+
+        let (ap_mac, client_mac) = if message % 2 == 1 {
+            (src_mac, dst_mac)
+        } else {
+            (dst_mac, src_mac)
+        };
+
+        let status = self
+            .handshakes
+            .entry((ap_mac.to_string(), client_mac.to_string()))
+            .or_insert_with(|| HandshakeStatus {
+                ap_mac: ap_mac.to_string(),
+                client_mac: client_mac.to_string(),
+                messages_seen: [false; 4],
+                complete: false,
+                first_seen: timestamp,
+                last_seen: timestamp,
+            });
+
+        // A fresh M1 after a completed handshake starts a new exchange
+        if message == 1 && status.complete {
+            status.messages_seen = [false; 4];
+            status.complete = false;
+            status.first_seen = timestamp;
+        }
+
+        status.messages_seen[(message - 1) as usize] = true;
+        status.complete = status.messages_seen.iter().all(|seen| *seen);
+        status.last_seen = timestamp;
+    }
+
+    pub fn handshakes(&self) -> Vec<HandshakeStatus> {
+        self.handshakes.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Key information of a WPA2-CCMP 4-way handshake (descriptor version 2)
+    const M1: u16 = 0x008A;
+    const M2: u16 = 0x010A;
+    const M3: u16 = 0x13CA;
+    const M4: u16 = 0x030A;
+    // Group key handshake message 1: ACK, MIC, secure, no pairwise bit
+    const GROUP_M1: u16 = 0x1382;
+
+    /// An EAPOL-Key frame header with the given key information.
+    fn key_frame(key_info: u16, replay_counter: u64) -> Vec<u8> {
+        let mut frame = vec![2, EAPOL_TYPE_KEY, 0, 95, 2];
+        frame.extend_from_slice(&key_info.to_be_bytes());
+        frame.extend_from_slice(&16u16.to_be_bytes());
+        frame.extend_from_slice(&replay_counter.to_be_bytes());
+        frame
+    }
+
+    fn message(key_info: u16) -> Option<u8> {
+        parse_eapol_key(&key_frame(key_info, 1))
+            .unwrap()
+            .handshake_message
+    }
+
+    #[test]
+    fn message_1() {
+        assert_eq!(message(M1), Some(1));
+    }
+
+    #[test]
+    fn message_2() {
+        assert_eq!(message(M2), Some(2));
+    }
+
+    #[test]
+    fn message_3() {
+        assert_eq!(message(M3), Some(3));
+        // WPA1 leaves the secure bit clear in message 3
+        assert_eq!(message(0x01C9), Some(3));
+    }
+
+    #[test]
+    fn message_4() {
+        assert_eq!(message(M4), Some(4));
+    }
+
+    #[test]
+    fn group_key_frames_are_not_in_the_4_way_handshake() {
+        assert_eq!(message(GROUP_M1), None);
+        assert_eq!(message(0x0302), None);
+    }
+
+    #[test]
+    fn key_info_and_replay_counter_are_read() {
+        let key = parse_eapol_key(&key_frame(M3, 0x0102_0304_0506_0708)).unwrap();
+        assert_eq!(key.key_info, M3);
+        assert_eq!(key.replay_counter, 0x0102_0304_0506_0708);
+    }
+
+    #[test]
+    fn short_or_non_key_frames_are_ignored() {
+        let frame = key_frame(M1, 1);
+        assert!(parse_eapol_key(&frame[..16]).is_none());
+        let mut eap = frame;
+        eap[1] = EAPOL_TYPE_EAP_PACKET;
+        assert!(parse_eapol_key(&eap).is_none());
+    }
+}
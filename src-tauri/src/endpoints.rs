@@ -0,0 +1,247 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::packet_sniffer::{Layer, PacketInfo};
+
+/// Address range an IP belongs to.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IpScope {
+    /// RFC 1918 (IPv4) or unique local fc00::/7 (IPv6)
+    Private,
+    Loopback,
+    LinkLocal,
+    /// Carrier-grade NAT shared space, 100.64.0.0/10 (RFC 6598)
+    Cgnat,
+    Multicast,
+    Broadcast,
+    Unspecified,
+    Public,
+}
+
+fn ipv4_scope(ip: &Ipv4Addr) -> IpScope {
+    let octets = ip.octets();
+    if ip.is_unspecified() {
+        IpScope::Unspecified
+    } else if ip.is_loopback() {
+        IpScope::Loopback
+    } else if ip.is_private() {
+        IpScope::Private
+    } else if ip.is_link_local() {
+        IpScope::LinkLocal
+    } else if octets[0] == 100 && octets[1] & 0xC0 == 64 {
+        IpScope::Cgnat
+    } else if ip.is_multicast() {
+        IpScope::Multicast
+    } else if ip.is_broadcast() {
+        IpScope::Broadcast
+    } else {
+        IpScope::Public
+    }
+}
+
+fn ipv6_scope(ip: &Ipv6Addr) -> IpScope {
+    if let Some(mapped) = ip.to_ipv4_mapped() {
+        return ipv4_scope(&mapped);
+    }
+
+    let first = ip.segments()[0];
+    if ip.is_unspecified() {
+        IpScope::Unspecified
+    } else if ip.is_loopback() {
+        IpScope::Loopback
+    } else if first & 0xFFC0 == 0xFE80 {
+        IpScope::LinkLocal
+    } else if first & 0xFE00 == 0xFC00 {
+        IpScope::Private
+    } else if ip.is_multicast() {
+        IpScope::Multicast
+    } else {
+        IpScope::Public
+    }
+}
+
+pub fn ip_scope(ip: &IpAddr) -> IpScope {
+    match ip {
+        IpAddr::V4(ip) => ipv4_scope(ip),
+        IpAddr::V6(ip) => ipv6_scope(ip),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExternalEndpoint {
+    pub ip: String,
+    /// Name the address was resolved from in an observed DNS answer
+    pub hostname: Option<String>,
+    pub packets: u64,
+    pub bytes: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+/// Summarizes the public addresses seen in captured traffic, naming them
+/// from DNS answers observed in the same capture.
+#[derive(Default)]
+pub struct EndpointTracker {
+    endpoints: HashMap<String, ExternalEndpoint>,
+    hostnames: HashMap<String, String>,
+}
+
+impl EndpointTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.endpoints.clear();
+        self.hostnames.clear();
+    }
+
+    pub fn observe(&mut self, packet: &PacketInfo) {
+        for layer in &packet.layers {
+            if let Layer::Dns {
+                response: true,
+                query: Some(query),
+                answers,
+                ..
+            } = layer
+            {
+                for answer in answers {
+                    self.hostnames.insert(answer.clone(), query.clone());
+                    if let Some(endpoint) = self.endpoints.get_mut(answer) {
+                        endpoint.hostname = Some(query.clone());
+                    }
+                }
+            }
+        }
+
+        // The remote side is whichever end is public, preferring the destination
+        let remote = match (packet.dst_ip_scope, packet.src_ip_scope) {
+            (Some(IpScope::Public), _) => packet.dst_ip.as_ref(),
+            (_, Some(IpScope::Public)) => packet.src_ip.as_ref(),
+            _ => None,
+        };
+        let Some(ip) = remote else {
+            return;
+        };
+
+        let endpoint = self
+            .endpoints
+            .entry(ip.clone())
+            .or_insert_with(|| ExternalEndpoint {
+                ip: ip.clone(),
+                hostname: self.hostnames.get(ip).cloned(),
+                packets: 0,
+                bytes: 0,
+                first_seen: packet.timestamp,
+                last_seen: packet.timestamp,
+            });
+        endpoint.packets += 1;
+        endpoint.bytes += packet.length as u64;
+        endpoint.last_seen = packet.timestamp;
+    }
+
+    /// Endpoints ordered by bytes exchanged, largest first.
+    pub fn endpoints(&self) -> Vec<ExternalEndpoint> {
+        let mut endpoints: Vec<ExternalEndpoint> = self.endpoints.values().cloned().collect();
+        endpoints.sort_by_key(|e| std::cmp::Reverse(e.bytes));
+        endpoints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(ip: &str) -> IpScope {
+        ip_scope(&ip.parse().unwrap())
+    }
+
+    #[test]
+    fn ipv4_private_ranges_and_their_edges() {
+        assert_eq!(scope("9.255.255.255"), IpScope::Public);
+        assert_eq!(scope("10.0.0.0"), IpScope::Private);
+        assert_eq!(scope("10.255.255.255"), IpScope::Private);
+        assert_eq!(scope("11.0.0.0"), IpScope::Public);
+
+        assert_eq!(scope("172.15.255.255"), IpScope::Public);
+        assert_eq!(scope("172.16.0.0"), IpScope::Private);
+        assert_eq!(scope("172.31.255.255"), IpScope::Private);
+        assert_eq!(scope("172.32.0.0"), IpScope::Public);
+
+        assert_eq!(scope("192.167.255.255"), IpScope::Public);
+        assert_eq!(scope("192.168.0.0"), IpScope::Private);
+        assert_eq!(scope("192.168.255.255"), IpScope::Private);
+        assert_eq!(scope("192.169.0.0"), IpScope::Public);
+    }
+
+    #[test]
+    fn ipv4_link_local_and_cgnat_edges() {
+        assert_eq!(scope("169.253.255.255"), IpScope::Public);
+        assert_eq!(scope("169.254.0.0"), IpScope::LinkLocal);
+        assert_eq!(scope("169.254.255.255"), IpScope::LinkLocal);
+        assert_eq!(scope("169.255.0.0"), IpScope::Public);
+
+        assert_eq!(scope("100.63.255.255"), IpScope::Public);
+        assert_eq!(scope("100.64.0.0"), IpScope::Cgnat);
+        assert_eq!(scope("100.127.255.255"), IpScope::Cgnat);
+        assert_eq!(scope("100.128.0.0"), IpScope::Public);
+    }
+
+    #[test]
+    fn ipv4_special_addresses() {
+        assert_eq!(scope("0.0.0.0"), IpScope::Unspecified);
+        assert_eq!(scope("127.0.0.1"), IpScope::Loopback);
+        assert_eq!(scope("127.255.255.255"), IpScope::Loopback);
+        assert_eq!(scope("223.255.255.255"), IpScope::Public);
+        assert_eq!(scope("224.0.0.0"), IpScope::Multicast);
+        assert_eq!(scope("239.255.255.255"), IpScope::Multicast);
+        assert_eq!(scope("255.255.255.255"), IpScope::Broadcast);
+    }
+
+    #[test]
+    fn ipv6_scopes_and_their_edges() {
+        assert_eq!(scope("::"), IpScope::Unspecified);
+        assert_eq!(scope("::1"), IpScope::Loopback);
+
+        assert_eq!(
+            scope("fbff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            IpScope::Public
+        );
+        assert_eq!(scope("fc00::"), IpScope::Private);
+        assert_eq!(
+            scope("fdff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            IpScope::Private
+        );
+        assert_eq!(scope("fe00::"), IpScope::Public);
+
+        assert_eq!(
+            scope("fe7f:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            IpScope::Public
+        );
+        assert_eq!(scope("fe80::"), IpScope::LinkLocal);
+        assert_eq!(
+            scope("febf:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            IpScope::LinkLocal
+        );
+        assert_eq!(scope("fec0::"), IpScope::Public);
+
+        assert_eq!(
+            scope("feff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            IpScope::Public
+        );
+        assert_eq!(scope("ff00::"), IpScope::Multicast);
+        assert_eq!(scope("ff02::1"), IpScope::Multicast);
+
+        assert_eq!(scope("2001:4860:4860::8888"), IpScope::Public);
+    }
+
+    #[test]
+    fn ipv4_mapped_ipv6_uses_the_ipv4_scope() {
+        assert_eq!(scope("::ffff:192.168.1.1"), IpScope::Private);
+        assert_eq!(scope("::ffff:100.64.0.1"), IpScope::Cgnat);
+        assert_eq!(scope("::ffff:8.8.8.8"), IpScope::Public);
+    }
+}
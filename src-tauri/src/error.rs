@@ -0,0 +1,58 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Error type shared by the scanner, the sniffer and the Tauri commands.
+///
+/// Serialized as `{ "kind": "...", "message": "..." }` so the frontend can
+/// switch on `kind` instead of matching on error strings.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(tag = "kind", content = "message", rename_all = "snake_case")]
+pub enum WifiError {
+    DeviceNotFound(String),
+    DeviceBusy(String),
+    PermissionDenied(String),
+    MonitorModeUnavailable(String),
+    ParseError(String),
+    CaptureError(String),
+    /// An unknown session, or a session command that doesn't fit the current
+    /// session state
+    SessionError(String),
+}
+
+impl fmt::Display for WifiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WifiError::DeviceNotFound(msg) => write!(f, "Device not found: {}", msg),
+            WifiError::DeviceBusy(msg) => write!(f, "Device busy: {}", msg),
+            WifiError::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            WifiError::MonitorModeUnavailable(msg) => {
+                write!(f, "Monitor mode unavailable: {}", msg)
+            }
+            WifiError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            WifiError::CaptureError(msg) => write!(f, "Capture error: {}", msg),
+            WifiError::SessionError(msg) => write!(f, "Session error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for WifiError {}
+
+impl From<pcap::Error> for WifiError {
+    fn from(err: pcap::Error) -> Self {
+        let msg = err.to_string();
+        let lower = msg.to_lowercase();
+
+        // libpcap only reports most failures as free-form text, so classify
+        // the common ones by their message.
+        if lower.contains("no such device") || lower.contains("doesn't exist") {
+            WifiError::DeviceNotFound(msg)
+        } else if lower.contains("permission") || lower.contains("not permitted") {
+            WifiError::PermissionDenied(msg)
+        } else if lower.contains("rfmon") || lower.contains("monitor mode") {
+            WifiError::MonitorModeUnavailable(msg)
+        } else {
+            WifiError::CaptureError(msg)
+        }
+    }
+}
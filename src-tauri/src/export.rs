@@ -0,0 +1,190 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{debug, warn};
+use pcap::{Capture, Offline};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::WifiError;
+use crate::packet_sniffer::{PacketInfo, CAPTURE_PRECISION};
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+/// Writes `packets` to `writer` as newline-delimited JSON, one `PacketInfo`
+/// per line, serializing each straight into the writer.
+pub fn write_ndjson<W: Write>(packets: &[PacketInfo], writer: W) -> Result<usize, WifiError> {
+    let mut writer = BufWriter::new(writer);
+    let write_error = |e: std::io::Error| WifiError::CaptureError(format!("Write failed: {}", e));
+
+    for packet in packets {
+        serde_json::to_writer(&mut writer, packet)
+            .map_err(|e| WifiError::CaptureError(format!("Failed to serialize packet: {}", e)))?;
+        writer.write_all(b"\n").map_err(write_error)?;
+    }
+    writer.flush().map_err(write_error)?;
+    Ok(packets.len())
+}
+
+/// Exports `packets` as NDJSON to the file at `path`, replacing it. With
+/// `compress` the file is gzipped as it is written.
+pub fn export_ndjson(
+    packets: &[PacketInfo],
+    path: &Path,
+    compress: bool,
+) -> Result<usize, WifiError> {
+    let file = File::create(path)
+        .map_err(|e| WifiError::CaptureError(format!("Failed to create {:?}: {}", path, e)))?;
+    if !compress {
+        return write_ndjson(packets, file);
+    }
+
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    let written = write_ndjson(packets, &mut encoder)?;
+    encoder
+        .finish()
+        .map_err(|e| WifiError::CaptureError(format!("Failed to compress {:?}: {}", path, e)))?;
+    Ok(written)
+}
+
+/// `path` with ".gz" appended.
+pub fn gzip_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// Compresses the file at `path` into `path.gz` a block at a time, then
+/// removes the original. Returns the compressed file's path.
+pub fn gzip_file(path: &Path) -> Result<PathBuf, WifiError> {
+    let compressed = gzip_path(path);
+    let io_error =
+        |e: io::Error| WifiError::CaptureError(format!("Failed to compress {:?}: {}", path, e));
+
+    let mut input = File::open(path).map_err(io_error)?;
+    let output = File::create(&compressed).map_err(io_error)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::default());
+    io::copy(&mut input, &mut encoder).map_err(io_error)?;
+    encoder
+        .finish()
+        .map_err(io_error)?
+        .flush()
+        .map_err(io_error)?;
+
+    fs::remove_file(path).map_err(io_error)?;
+    Ok(compressed)
+}
+
+fn is_gzipped(path: &Path) -> Result<bool, WifiError> {
+    let mut magic = [0u8; 2];
+    let read = File::open(path).and_then(|mut file| file.read_exact(&mut magic));
+    match read {
+        Ok(()) => Ok(magic == GZIP_MAGIC),
+        // Too short to be gzip; let pcap report what's wrong with it
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(WifiError::CaptureError(format!(
+            "Failed to open {:?}: {}",
+            path, e
+        ))),
+    }
+}
+
+/// Opens a saved capture for reading, decompressing gzipped files (detected
+/// by their magic bytes, whatever the extension) into a temporary file
+/// first, since libpcap can only read plain files.
+pub fn open_capture_file(path: &Path) -> Result<Capture<Offline>, WifiError> {
+    if !is_gzipped(path)? {
+        return Ok(Capture::from_file_with_precision(path, CAPTURE_PRECISION)?);
+    }
+
+    let temp = decompress_to_temp(path)?;
+    let opened = Capture::from_file_with_precision(&temp, CAPTURE_PRECISION);
+    // libpcap keeps its own handle open, so the file can go right away where
+    // the OS allows it
+    if let Err(e) = fs::remove_file(&temp) {
+        warn!("Couldn't remove {:?}: {}", temp, e);
+    }
+    Ok(opened?)
+}
+
+/// Decompresses the gzipped file at `path` into a new temporary file and
+/// returns its path. Names are unique per call, so captures with the same
+/// name opened at once don't overwrite each other.
+fn decompress_to_temp(path: &Path) -> Result<PathBuf, WifiError> {
+    static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+    let io_error =
+        |e: io::Error| WifiError::CaptureError(format!("Failed to decompress {:?}: {}", path, e));
+    let temp = std::env::temp_dir().join(format!(
+        "wifi-analyzer-{}-{}-{}.pcap",
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed),
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("capture")
+    ));
+    debug!("Decompressing {:?} to {:?}", path, temp);
+
+    let input = File::open(path).map_err(io_error)?;
+    let mut decoder = GzDecoder::new(BufReader::new(input));
+    // create_new so a stale file of the same name is never reused
+    let file = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temp)
+        .map_err(io_error)?;
+    let mut output = BufWriter::new(file);
+    let copied = io::copy(&mut decoder, &mut output).and_then(|_| output.flush());
+    drop(output);
+    if let Err(e) = copied {
+        let _ = fs::remove_file(&temp);
+        return Err(io_error(e));
+    }
+    Ok(temp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gzip_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("wifi-analyzer-test-{}-gzip", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("capture.pcap");
+        let contents: Vec<u8> = (0..70_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        fs::write(&path, &contents).unwrap();
+
+        let compressed = gzip_file(&path).unwrap();
+
+        assert_eq!(compressed, dir.join("capture.pcap.gz"));
+        assert!(!path.exists());
+        assert!(is_gzipped(&compressed).unwrap());
+
+        let first = decompress_to_temp(&compressed).unwrap();
+        let second = decompress_to_temp(&compressed).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&first).unwrap(), contents);
+        assert_eq!(fs::read(&second).unwrap(), contents);
+
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn plain_files_are_not_gzipped() {
+        let path =
+            std::env::temp_dir().join(format!("wifi-analyzer-test-{}-plain", std::process::id()));
+        fs::write(&path, [0xD4, 0xC3, 0xB2, 0xA1]).unwrap();
+        assert!(!is_gzipped(&path).unwrap());
+
+        fs::write(&path, [0x1F]).unwrap();
+        assert!(!is_gzipped(&path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::error::WifiError;
+use crate::packet_sniffer::{Layer, PacketInfo};
+
+// Keeps a capture full of port scans from growing the table without bound
+const MAX_FLOWS: usize = 4096;
+
+const HTTPS_PORT: u16 = 443;
+
+/// Inter-arrival statistics for one direction of a flow.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlowJitter {
+    pub flow_key: String,
+    pub packets: u64,
+    pub mean_interarrival_ms: f64,
+    /// Mean deviation of the gaps between packets from the mean gap,
+    /// smoothed with RFC 3550's 1/16 gain
+    pub jitter_ms: f64,
+}
+
+/// Both directions of a flow, with side A being the one heard from first.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Conversation {
+    pub transport: String,
+    pub a_ip: String,
+    pub a_port: u16,
+    pub b_ip: String,
+    pub b_port: u16,
+    pub packets_a_to_b: u64,
+    pub bytes_a_to_b: u64,
+    pub packets_b_to_a: u64,
+    pub bytes_b_to_a: u64,
+    /// First and last packet, in milliseconds since the epoch
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub duration_ms: u64,
+    /// Application protocol seen in the most packets, if any was decoded
+    pub app_protocol: Option<String>,
+}
+
+type Endpoint = (String, u16);
+
+struct FlowTiming {
+    transport: &'static str,
+    src: Endpoint,
+    dst: Endpoint,
+    packets: u64,
+    bytes: u64,
+    first_us: u64,
+    last_us: u64,
+    jitter_us: f64,
+    app_protocols: HashMap<&'static str, u64>,
+}
+
+/// Tracks packet timing and volume per directional flow, for jitter on
+/// real-time media and for the conversation list.
+#[derive(Default)]
+pub struct FlowTracker {
+    flows: HashMap<String, FlowTiming>,
+}
+
+fn transport_name(packet: &PacketInfo) -> Option<&'static str> {
+    packet.layers.iter().find_map(|layer| match layer {
+        Layer::Tcp { .. } => Some("TCP"),
+        Layer::Udp { .. } => Some("UDP"),
+        Layer::Sctp { .. } => Some("SCTP"),
+        _ => None,
+    })
+}
+
+fn app_protocol(packet: &PacketInfo) -> Option<&'static str> {
+    packet.layers.iter().find_map(|layer| match layer {
+        Layer::Http { .. } => Some("HTTP"),
+        Layer::Dns { .. } => Some("DNS"),
+        Layer::Dhcp(_) => Some("DHCP"),
+        Layer::Quic { .. } => Some("QUIC"),
+        // The sniffer doesn't decode TLS records, so go by the HTTPS port
+        Layer::Tcp { src_port, dst_port } if *src_port == HTTPS_PORT || *dst_port == HTTPS_PORT => {
+            Some("TLS")
+        }
+        _ => None,
+    })
+}
+
+/// Identifies the direction of a flow a packet belongs to, as
+/// `"UDP 10.0.0.2:5004 > 10.0.0.9:5006"`. Only TCP, UDP and SCTP packets
+/// have one.
+pub fn flow_key(packet: &PacketInfo) -> Option<String> {
+    Some(format!(
+        "{} {}:{} > {}:{}",
+        transport_name(packet)?,
+        packet.src_ip.as_ref()?,
+        packet.src_port?,
+        packet.dst_ip.as_ref()?,
+        packet.dst_port?
+    ))
+}
+
+impl FlowTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.flows.clear();
+    }
+
+    /// Records `packet` arriving at `arrival_us`, the capture timestamp in
+    /// microseconds since the epoch.
+    pub fn observe(&mut self, packet: &PacketInfo, arrival_us: u64) {
+        let Some(key) = flow_key(packet) else {
+            return;
+        };
+        if self.flows.len() >= MAX_FLOWS && !self.flows.contains_key(&key) {
+            return;
+        }
+
+        // flow_key succeeding guarantees the addresses, ports and transport
+        let flow = self.flows.entry(key).or_insert_with(|| FlowTiming {
+            transport: transport_name(packet).unwrap_or_default(),
+            src: (
+                packet.src_ip.clone().unwrap_or_default(),
+                packet.src_port.unwrap_or_default(),
+            ),
+            dst: (
+                packet.dst_ip.clone().unwrap_or_default(),
+                packet.dst_port.unwrap_or_default(),
+            ),
+            packets: 0,
+            bytes: 0,
+            first_us: arrival_us,
+            last_us: arrival_us,
+            jitter_us: 0.0,
+            app_protocols: HashMap::new(),
+        });
+        if flow.packets > 0 {
+            // Captures can be slightly out of order across queues
+            let gap = arrival_us.saturating_sub(flow.last_us) as f64;
+            // Without the sender's RTP timestamps the expected spacing isn't
+            // known, so each gap is compared with the mean gap so far, which
+            // holds for the constant-rate senders jitter matters for.
+            let mean_gap =
+                (arrival_us.max(flow.last_us) - flow.first_us) as f64 / flow.packets as f64;
+            let d = (gap - mean_gap).abs();
+            flow.jitter_us += (d - flow.jitter_us) / 16.0;
+        }
+        flow.last_us = arrival_us.max(flow.last_us);
+        flow.packets += 1;
+        flow.bytes += packet.length as u64;
+        if let Some(protocol) = app_protocol(packet) {
+            *flow.app_protocols.entry(protocol).or_insert(0) += 1;
+        }
+    }
+
+    pub fn jitter(&self, flow_key: &str) -> Result<FlowJitter, WifiError> {
+        let flow = self.flows.get(flow_key).ok_or_else(|| {
+            WifiError::ParseError(format!("No packets captured for flow '{}'", flow_key))
+        })?;
+
+        let mean_interarrival_ms = if flow.packets > 1 {
+            (flow.last_us - flow.first_us) as f64 / (flow.packets - 1) as f64 / 1000.0
+        } else {
+            0.0
+        };
+        Ok(FlowJitter {
+            flow_key: flow_key.to_string(),
+            packets: flow.packets,
+            mean_interarrival_ms,
+            jitter_ms: flow.jitter_us / 1000.0,
+        })
+    }
+
+    /// Pairs each flow with its reverse direction, ordered by start time.
+    pub fn conversations(&self) -> Vec<Conversation> {
+        let mut pairs: HashMap<(&str, &Endpoint, &Endpoint), Vec<&FlowTiming>> = HashMap::new();
+        for flow in self.flows.values() {
+            let (low, high) = if flow.src <= flow.dst {
+                (&flow.src, &flow.dst)
+            } else {
+                (&flow.dst, &flow.src)
+            };
+            pairs
+                .entry((flow.transport, low, high))
+                .or_default()
+                .push(flow);
+        }
+
+        let mut conversations: Vec<Conversation> = pairs
+            .into_values()
+            .map(|mut flows| {
+                flows.sort_by_key(|flow| flow.first_us);
+                let forward = flows[0];
+                let reverse = flows.get(1);
+
+                let start_us = forward.first_us;
+                let end_us = flows
+                    .iter()
+                    .map(|flow| flow.last_us)
+                    .max()
+                    .unwrap_or(start_us);
+
+                let mut app_protocols: HashMap<&str, u64> = HashMap::new();
+                for flow in &flows {
+                    for (protocol, count) in &flow.app_protocols {
+                        *app_protocols.entry(protocol).or_insert(0) += count;
+                    }
+                }
+                // Ties go to the protocol first by name, so the answer is stable
+                let app_protocol = app_protocols
+                    .into_iter()
+                    .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(b.cmp(a)))
+                    .map(|(protocol, _)| protocol.to_string());
+
+                Conversation {
+                    transport: forward.transport.to_string(),
+                    a_ip: forward.src.0.clone(),
+                    a_port: forward.src.1,
+                    b_ip: forward.dst.0.clone(),
+                    b_port: forward.dst.1,
+                    packets_a_to_b: forward.packets,
+                    bytes_a_to_b: forward.bytes,
+                    packets_b_to_a: reverse.map_or(0, |flow| flow.packets),
+                    bytes_b_to_a: reverse.map_or(0, |flow| flow.bytes),
+                    start_ms: start_us / 1000,
+                    end_ms: end_us / 1000,
+                    duration_ms: (end_us - start_us) / 1000,
+                    app_protocol,
+                }
+            })
+            .collect();
+        conversations.sort_by_key(|c| c.start_ms);
+        conversations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet_sniffer::ParsedPacket;
+
+    /// A TCP packet of `length` bytes, with `extra` layers on top.
+    fn tcp(src: (&str, u16), dst: (&str, u16), length: usize, extra: Vec<Layer>) -> PacketInfo {
+        let mut layers = vec![
+            Layer::Ipv4 {
+                src_ip: src.0.to_string(),
+                dst_ip: dst.0.to_string(),
+                protocol: 6,
+                fragment: false,
+            },
+            Layer::Tcp {
+                src_port: src.1,
+                dst_port: dst.1,
+            },
+        ];
+        layers.extend(extra);
+        PacketInfo::from(ParsedPacket {
+            layers,
+            length,
+            timestamp: 0,
+            timestamp_iso: String::new(),
+            reassembled: false,
+            malformed: None,
+        })
+    }
+
+    fn http() -> Layer {
+        Layer::Http {
+            payload: String::new(),
+            host: None,
+        }
+    }
+
+    fn dns() -> Layer {
+        Layer::Dns {
+            id: 1,
+            response: false,
+            query: None,
+            answers: Vec::new(),
+        }
+    }
+
+    const CLIENT: (&str, u16) = ("10.0.0.2", 50000);
+    const SERVER: (&str, u16) = ("10.0.0.9", 8080);
+
+    #[test]
+    fn both_directions_pair_into_one_conversation() {
+        let mut flows = FlowTracker::new();
+        flows.observe(&tcp(SERVER, CLIENT, 1500, Vec::new()), 3_000);
+        flows.observe(&tcp(CLIENT, SERVER, 60, Vec::new()), 1_000);
+        flows.observe(&tcp(CLIENT, SERVER, 40, Vec::new()), 2_000);
+        flows.observe(&tcp(SERVER, CLIENT, 1500, Vec::new()), 9_000);
+        // Another client's flow to the same server is its own conversation
+        flows.observe(&tcp(("10.0.0.3", 50000), SERVER, 60, Vec::new()), 5_000);
+
+        let conversations = flows.conversations();
+        assert_eq!(conversations.len(), 2);
+
+        // Side A is the direction heard first, even when observed later
+        let c = &conversations[0];
+        assert_eq!((c.a_ip.as_str(), c.a_port), CLIENT);
+        assert_eq!((c.b_ip.as_str(), c.b_port), SERVER);
+        assert_eq!((c.packets_a_to_b, c.bytes_a_to_b), (2, 100));
+        assert_eq!((c.packets_b_to_a, c.bytes_b_to_a), (2, 3000));
+        assert_eq!((c.start_ms, c.end_ms, c.duration_ms), (1, 9, 8));
+
+        let c = &conversations[1];
+        assert_eq!(c.a_ip, "10.0.0.3");
+        assert_eq!((c.packets_a_to_b, c.packets_b_to_a), (1, 0));
+    }
+
+    #[test]
+    fn https_port_counts_as_tls() {
+        let mut flows = FlowTracker::new();
+        flows.observe(&tcp(CLIENT, ("10.0.0.9", 443), 60, Vec::new()), 1_000);
+        flows.observe(&tcp(("10.0.0.9", 443), CLIENT, 60, Vec::new()), 2_000);
+
+        let conversations = flows.conversations();
+        assert_eq!(conversations[0].app_protocol.as_deref(), Some("TLS"));
+    }
+
+    #[test]
+    fn dominant_protocol_ties_break_by_name() {
+        let mut flows = FlowTracker::new();
+        flows.observe(&tcp(CLIENT, SERVER, 60, vec![http()]), 1_000);
+        flows.observe(&tcp(SERVER, CLIENT, 60, vec![dns()]), 2_000);
+        assert_eq!(
+            flows.conversations()[0].app_protocol.as_deref(),
+            Some("DNS")
+        );
+
+        flows.observe(&tcp(CLIENT, SERVER, 60, vec![http()]), 3_000);
+        assert_eq!(
+            flows.conversations()[0].app_protocol.as_deref(),
+            Some("HTTP")
+        );
+    }
+
+    #[test]
+    fn no_app_protocol_without_a_decoded_one() {
+        let mut flows = FlowTracker::new();
+        flows.observe(&tcp(CLIENT, SERVER, 60, Vec::new()), 1_000);
+        assert_eq!(flows.conversations()[0].app_protocol, None);
+    }
+
+    fn udp_flow(gaps_us: impl Iterator<Item = u64>) -> FlowJitter {
+        let packet = PacketInfo::from(ParsedPacket {
+            layers: vec![
+                Layer::Ipv4 {
+                    src_ip: "10.0.0.2".to_string(),
+                    dst_ip: "10.0.0.9".to_string(),
+                    protocol: 17,
+                    fragment: false,
+                },
+                Layer::Udp {
+                    src_port: 5004,
+                    dst_port: 5006,
+                },
+            ],
+            length: 200,
+            timestamp: 0,
+            timestamp_iso: String::new(),
+            reassembled: false,
+            malformed: None,
+        });
+        let mut flows = FlowTracker::new();
+        let mut arrival_us = 1_000_000;
+        flows.observe(&packet, arrival_us);
+        for gap in gaps_us {
+            arrival_us += gap;
+            flows.observe(&packet, arrival_us);
+        }
+        flows.jitter(&flow_key(&packet).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn constant_spacing_has_no_jitter() {
+        let jitter = udp_flow([20_000; 100].into_iter());
+        assert_eq!(jitter.packets, 101);
+        assert_eq!(jitter.mean_interarrival_ms, 20.0);
+        assert_eq!(jitter.jitter_ms, 0.0);
+    }
+
+    #[test]
+    fn alternating_spacing_converges_on_its_deviation() {
+        // 10 and 30 ms gaps are each 10 ms off the 20 ms mean
+        let jitter = udp_flow([10_000, 30_000].into_iter().cycle().take(400));
+        assert_eq!(jitter.mean_interarrival_ms, 20.0);
+        assert!(
+            (jitter.jitter_ms - 10.0).abs() < 0.1,
+            "jitter {} ms",
+            jitter.jitter_ms
+        );
+    }
+
+    #[test]
+    fn jitter_of_an_unknown_flow() {
+        assert!(FlowTracker::new()
+            .jitter("UDP 1.2.3.4:1 > 5.6.7.8:2")
+            .is_err());
+    }
+}
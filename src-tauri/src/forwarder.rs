@@ -0,0 +1,140 @@
+use log::{info, warn};
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::WifiError;
+use crate::export::write_ndjson;
+use crate::packet_sniffer::PacketInfo;
+
+/// Packets held while the collector is slow or unreachable. Once full, new
+/// packets are dropped so the capture thread never waits on the network.
+const QUEUE_LEN: usize = 10_000;
+const MAX_BATCH: usize = 256;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// How often an idle worker checks whether forwarding was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Streams captured packets to a remote collector as NDJSON over TCP.
+pub struct Forwarder {
+    target: String,
+    queue: SyncSender<PacketInfo>,
+    running: Arc<AtomicBool>,
+    dropped: AtomicU64,
+}
+
+impl Forwarder {
+    /// Spawns a worker that connects to `host:port` and sends it everything
+    /// queued with `send`, reconnecting with exponential backoff whenever the
+    /// connection fails. Packets buffered in a failed write are lost.
+    pub fn start(host: String, port: u16) -> Self {
+        let (queue, packets) = mpsc::sync_channel(QUEUE_LEN);
+        let running = Arc::new(AtomicBool::new(true));
+        let target = format!("{}:{}", host, port);
+
+        {
+            let running = Arc::clone(&running);
+            thread::spawn(move || forward(&host, port, &packets, &running));
+        }
+
+        Forwarder {
+            target,
+            queue,
+            running,
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queues `packet` for the collector without blocking.
+    pub fn send(&self, packet: &PacketInfo) {
+        if let Err(TrySendError::Full(_)) = self.queue.try_send(packet.clone()) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops the worker. Returns how many packets were dropped on a full queue.
+    pub fn stop(self) -> u64 {
+        self.running.store(false, Ordering::Relaxed);
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        info!(
+            "Stopped forwarding to {} ({} packets dropped)",
+            self.target, dropped
+        );
+        dropped
+    }
+}
+
+fn forward(host: &str, port: u16, packets: &Receiver<PacketInfo>, running: &AtomicBool) {
+    let mut backoff = MIN_BACKOFF;
+
+    while running.load(Ordering::Relaxed) {
+        let stream = match connect(host, port) {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!(
+                    "Can't reach collector {}:{}: {}, retrying in {:?}",
+                    host, port, e, backoff
+                );
+                sleep_while_running(backoff, running);
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        };
+
+        info!("Forwarding packets to {}:{}", host, port);
+        backoff = MIN_BACKOFF;
+        match stream_packets(&stream, packets, running) {
+            Ok(()) => return,
+            Err(e) => warn!("Lost connection to collector {}:{}: {}", host, port, e),
+        }
+    }
+}
+
+fn connect(host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "host has no addresses");
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                // A stalled collector fails the write instead of hanging the worker
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Writes queued packets in batches until forwarding stops or a write fails.
+fn stream_packets(
+    stream: &TcpStream,
+    packets: &Receiver<PacketInfo>,
+    running: &AtomicBool,
+) -> Result<(), WifiError> {
+    while running.load(Ordering::Relaxed) {
+        let mut batch = match packets.recv_timeout(POLL_INTERVAL) {
+            Ok(packet) => vec![packet],
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        batch.extend(packets.try_iter().take(MAX_BATCH - 1));
+        write_ndjson(&batch, stream)?;
+    }
+    Ok(())
+}
+
+fn sleep_while_running(duration: Duration, running: &AtomicBool) {
+    let mut remaining = duration;
+    while !remaining.is_zero() && running.load(Ordering::Relaxed) {
+        let step = remaining.min(POLL_INTERVAL);
+        thread::sleep(step);
+        remaining -= step;
+    }
+}
@@ -0,0 +1,42 @@
+use crate::error::WifiError;
+
+/// Encodes bytes as a contiguous lowercase hex string.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes a hex dump into bytes. Whitespace, `:`/`-` separators and `0x`
+/// prefixes are ignored so dumps copied from Wireshark or xxd can be pasted
+/// as-is.
+pub fn decode(input: &str) -> Result<Vec<u8>, WifiError> {
+    let digits: String = input
+        .split_whitespace()
+        .map(|chunk| chunk.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect::<String>()
+        .chars()
+        .filter(|c| *c != ':' && *c != '-')
+        .collect();
+
+    if digits.is_empty() {
+        return Err(WifiError::ParseError("Hex input is empty".to_string()));
+    }
+
+    if let Some((index, c)) = digits.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(WifiError::ParseError(format!(
+            "Invalid hex character '{}' at position {}",
+            c, index
+        )));
+    }
+
+    if !digits.len().is_multiple_of(2) {
+        return Err(WifiError::ParseError(format!(
+            "Hex input has an odd number of digits ({})",
+            digits.len()
+        )));
+    }
+
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect())
+}
@@ -0,0 +1,119 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant};
+
+use crate::error::WifiError;
+use crate::wifi_scanner::{scan_wifi_internal, ScannerConfig, WiFiNetwork};
+
+/// What one adapter saw during a comparison scan.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterfaceScan {
+    pub interface: String,
+    /// Why the adapter couldn't scan, in which case it saw nothing
+    pub error: Option<WifiError>,
+    pub network_count: usize,
+    /// Sorted
+    pub bssids: Vec<String>,
+    /// Mean of the networks' average beacon signal in dBm, None without networks
+    pub average_rssi: Option<f64>,
+}
+
+impl InterfaceScan {
+    fn new(interface: &str, result: Result<Vec<WiFiNetwork>, WifiError>) -> Self {
+        match result {
+            Ok(networks) => {
+                let bssids: BTreeSet<String> = networks.iter().map(|n| n.bssid.clone()).collect();
+                let average_rssi = (!networks.is_empty()).then(|| {
+                    networks.iter().map(|n| n.avg_signal as f64).sum::<f64>()
+                        / networks.len() as f64
+                });
+                InterfaceScan {
+                    interface: interface.to_string(),
+                    error: None,
+                    network_count: networks.len(),
+                    bssids: bssids.into_iter().collect(),
+                    average_rssi,
+                }
+            }
+            Err(e) => InterfaceScan {
+                interface: interface.to_string(),
+                error: Some(e),
+                network_count: 0,
+                bssids: Vec::new(),
+                average_rssi: None,
+            },
+        }
+    }
+}
+
+/// Two adapters scanned side by side, with the BSSIDs only one of them saw.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterfaceComparison {
+    pub a: InterfaceScan,
+    pub b: InterfaceScan,
+    pub only_a: Vec<String>,
+    pub only_b: Vec<String>,
+    pub common: usize,
+}
+
+impl InterfaceComparison {
+    pub fn new(
+        iface_a: &str,
+        a: Result<Vec<WiFiNetwork>, WifiError>,
+        iface_b: &str,
+        b: Result<Vec<WiFiNetwork>, WifiError>,
+    ) -> Self {
+        let a = InterfaceScan::new(iface_a, a);
+        let b = InterfaceScan::new(iface_b, b);
+        let seen_a: BTreeSet<&String> = a.bssids.iter().collect();
+        let seen_b: BTreeSet<&String> = b.bssids.iter().collect();
+        let only_a = seen_a.difference(&seen_b).map(|s| s.to_string()).collect();
+        let only_b = seen_b.difference(&seen_a).map(|s| s.to_string()).collect();
+        let common = seen_a.intersection(&seen_b).count();
+        InterfaceComparison {
+            a,
+            b,
+            only_a,
+            only_b,
+            common,
+        }
+    }
+}
+
+/// Scans `interface` for `duration`, or until the scanner finishes on its
+/// own, returning the networks it found.
+pub fn scan_for(
+    interface: &str,
+    config: &ScannerConfig,
+    duration: Duration,
+) -> Result<Vec<WiFiNetwork>, WifiError> {
+    let (stop_tx, progress_rx) = scan_wifi_internal(interface, config)?;
+    let started = Instant::now();
+    let mut networks = Vec::new();
+
+    while started.elapsed() < duration {
+        match progress_rx.try_recv() {
+            Ok(progress) => {
+                networks = progress.networks;
+                if progress.completion.is_some() {
+                    break;
+                }
+            }
+            Err(TryRecvError::Empty) => std::thread::sleep(Duration::from_millis(100)),
+            Err(TryRecvError::Disconnected) => {
+                warn!("Scanner on {} stopped unexpectedly", interface);
+                break;
+            }
+        }
+    }
+
+    let _ = stop_tx.send(());
+    info!(
+        "Comparison scan on {} found {} networks",
+        interface,
+        networks.len()
+    );
+    Ok(networks)
+}
@@ -0,0 +1,98 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Distinct SSIDs one BSSID may advertise before it's flagged on that alone.
+/// APs serving several SSIDs normally give each its own BSSID.
+const MAX_SSIDS_PER_BSSID: usize = 4;
+/// Fewer distinct SSIDs are enough when they answer what clients probed for.
+const MIN_SSIDS_WITH_PROBES: usize = 3;
+const MIN_PROBED_MATCHES: usize = 2;
+/// How long after a probe request a response for its SSID counts as an answer.
+const PROBE_WINDOW_SECS: u64 = 30;
+// Bounds memory when a responder makes up SSIDs faster than it's flagged
+const MAX_TRACKED_BSSIDS: usize = 1024;
+const MAX_TRACKED_PROBES: usize = 1024;
+
+/// Payload of the "karma_attack" event: a BSSID that answers to more
+/// network names than a real AP would, the way KARMA and the PineAP lure
+/// clients into connecting.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct KarmaAlert {
+    pub bssid: String,
+    /// Every SSID the BSSID has beaconed or probe-responded with, sorted
+    pub ssids: Vec<String>,
+    /// Those of `ssids` a client probed for shortly before
+    pub probed_ssids: Vec<String>,
+    pub timestamp: u64,
+}
+
+#[derive(Default)]
+struct Advertised {
+    ssids: HashSet<String>,
+    probed: HashSet<String>,
+}
+
+/// Relates the SSIDs each BSSID advertises to the SSIDs clients probe for.
+#[derive(Default)]
+pub struct KarmaDetector {
+    advertised: HashMap<String, Advertised>,
+    /// When each SSID was last probed for
+    probes: HashMap<String, u64>,
+    flagged: HashSet<String>,
+}
+
+impl KarmaDetector {
+    pub fn record_probe(&mut self, ssid: &str, now: u64) {
+        if self.probes.len() >= MAX_TRACKED_PROBES {
+            self.probes
+                .retain(|_, at| now.saturating_sub(*at) < PROBE_WINDOW_SECS);
+            if self.probes.len() >= MAX_TRACKED_PROBES {
+                return;
+            }
+        }
+        self.probes.insert(ssid.to_string(), now);
+    }
+
+    /// Records `bssid` beaconing or probe-responding as `ssid`, returning an
+    /// alert the first time it looks like a KARMA responder.
+    pub fn record_advertised(&mut self, bssid: &str, ssid: &str, now: u64) -> Option<KarmaAlert> {
+        if ssid.is_empty() || self.flagged.contains(bssid) {
+            return None;
+        }
+        if self.advertised.len() >= MAX_TRACKED_BSSIDS && !self.advertised.contains_key(bssid) {
+            return None;
+        }
+
+        let answered_probe = self
+            .probes
+            .get(ssid)
+            .is_some_and(|at| now.saturating_sub(*at) < PROBE_WINDOW_SECS);
+        let advertised = self.advertised.entry(bssid.to_string()).or_default();
+        if !advertised.ssids.insert(ssid.to_string()) && !answered_probe {
+            return None;
+        }
+        if answered_probe {
+            advertised.probed.insert(ssid.to_string());
+        }
+
+        let suspicious = advertised.ssids.len() > MAX_SSIDS_PER_BSSID
+            || (advertised.ssids.len() >= MIN_SSIDS_WITH_PROBES
+                && advertised.probed.len() >= MIN_PROBED_MATCHES);
+        if !suspicious {
+            return None;
+        }
+
+        self.flagged.insert(bssid.to_string());
+        let mut ssids: Vec<String> = advertised.ssids.iter().cloned().collect();
+        ssids.sort();
+        let mut probed_ssids: Vec<String> = advertised.probed.iter().cloned().collect();
+        probed_ssids.sort();
+        Some(KarmaAlert {
+            bssid: bssid.to_string(),
+            ssids,
+            probed_ssids,
+            timestamp: now,
+        })
+    }
+}
@@ -0,0 +1,165 @@
+use log::{info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::WifiError;
+use crate::oui;
+use crate::packet_sniffer::{Layer, PacketInfo};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct KnownDevice {
+    pub mac: String,
+    pub vendor: Option<String>,
+    /// First IP learned for the device from ARP or DHCP
+    pub first_ip: Option<String>,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub trusted: bool,
+}
+
+/// MAC addresses seen on the LAN, persisted so that "new device" alerts
+/// survive restarts. Changes from captured packets are only marked here and
+/// written by `flush`, keeping disk writes out of the capture loop.
+#[derive(Default)]
+pub struct DeviceInventory {
+    devices: HashMap<String, KnownDevice>,
+    path: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl DeviceInventory {
+    /// Loads the inventory from `path`, starting empty if it doesn't exist
+    /// or can't be read.
+    pub fn load(path: PathBuf) -> Self {
+        let devices = match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<Vec<KnownDevice>>(&contents) {
+                Ok(devices) => devices.into_iter().map(|d| (d.mac.clone(), d)).collect(),
+                Err(e) => {
+                    warn!("Ignoring unreadable device inventory {:?}: {}", path, e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+
+        info!("Loaded {} known LAN devices", devices.len());
+        DeviceInventory {
+            devices,
+            path: Some(path),
+            dirty: false,
+        }
+    }
+
+    /// Records the sender of a LAN packet. DHCP messages are attributed to
+    /// the client hardware address, and only ARP/DHCP are trusted for the
+    /// device's IP since routed traffic carries remote source addresses.
+    pub fn observe_packet(&mut self, packet: &PacketInfo) -> Option<KnownDevice> {
+        if packet.wifi.is_some() {
+            return None;
+        }
+
+        let (mac, ip) = match &packet.dhcp {
+            Some(dhcp) => (dhcp.client_mac.as_str(), dhcp.requested_ip.as_deref()),
+            None => {
+                let arp_sender = packet.layers.iter().find_map(|layer| match layer {
+                    Layer::Arp { sender_ip, .. } if sender_ip != "0.0.0.0" => {
+                        Some(sender_ip.as_str())
+                    }
+                    _ => None,
+                });
+                (packet.src_mac.as_str(), arp_sender)
+            }
+        };
+
+        if !is_unicast(mac) {
+            return None;
+        }
+        self.observe(mac, ip, packet.timestamp)
+    }
+
+    /// Records a frame from `mac`, with `ip` if the frame revealed one.
+    /// Returns the new entry the first time a MAC is seen.
+    fn observe(&mut self, mac: &str, ip: Option<&str>, timestamp: u64) -> Option<KnownDevice> {
+        if let Some(device) = self.devices.get_mut(mac) {
+            device.last_seen = timestamp;
+            if device.first_ip.is_none() && ip.is_some() {
+                device.first_ip = ip.map(str::to_string);
+                self.dirty = true;
+            }
+            return None;
+        }
+
+        let device = KnownDevice {
+            mac: mac.to_string(),
+            vendor: oui::lookup(mac).map(str::to_string),
+            first_ip: ip.map(str::to_string),
+            first_seen: timestamp,
+            last_seen: timestamp,
+            trusted: false,
+        };
+        info!("New LAN device: {} ({:?})", mac, device.vendor);
+        self.devices.insert(mac.to_string(), device.clone());
+        self.dirty = true;
+        Some(device)
+    }
+
+    pub fn set_trusted(&mut self, mac: &str, trusted: bool) -> Result<(), WifiError> {
+        let device = self
+            .devices
+            .get_mut(&mac.to_uppercase())
+            .ok_or_else(|| WifiError::ParseError(format!("Unknown LAN device {}", mac)))?;
+        device.trusted = trusted;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn devices(&self) -> Vec<KnownDevice> {
+        self.devices.values().cloned().collect()
+    }
+
+    /// Writes the inventory to disk if it changed since the last flush. The
+    /// file is written after the lock is released.
+    pub fn flush(inventory: &Mutex<DeviceInventory>) {
+        let snapshot = {
+            let mut inventory = inventory.lock().unwrap();
+            if !inventory.dirty {
+                return;
+            }
+            inventory.dirty = false;
+            inventory
+                .path
+                .clone()
+                .map(|path| (path, inventory.devices()))
+        };
+        if let Some((path, devices)) = snapshot {
+            save(&path, &devices);
+        }
+    }
+}
+
+fn save(path: &Path, devices: &[KnownDevice]) {
+    let result = serde_json::to_string_pretty(devices)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(path, json).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        warn!("Failed to save device inventory to {:?}: {}", path, e);
+    }
+}
+
+fn is_unicast(mac: &str) -> bool {
+    let first_octet = mac
+        .get(..2)
+        .and_then(|octet| u8::from_str_radix(octet, 16).ok());
+    match first_octet {
+        Some(octet) => octet & 0x01 == 0 && mac != "00:00:00:00:00:00",
+        None => false,
+    }
+}
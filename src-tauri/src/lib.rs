@@ -1,38 +1,201 @@
 use log::{debug, error, info, warn};
-use pcap::{Capture, Device};
+use pcap::Device;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::result::Result;
-use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use tauri::Emitter;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
 
-use packet_sniffer::{parse_packet, PacketCapture, PacketInfo};
-use wifi_scanner::{scan_wifi_internal, WiFiNetwork};
+use analysis::{
+    audit_ssid, band_summary, beacon_anomalies, ApGroupingOptions, BandSummary, BeaconAnomaly,
+    CongestionThresholds, InterferenceMatrix, NetworkAudit, PhysicalAp, SecuritySummary, Severity,
+};
+use arp_watch::BindingConflict;
+use benchmark::ParseBenchmark;
+use capabilities::{interface_capabilities, InterfaceCapabilities};
+use capture_output::{ActiveSave, CaptureOutput, SaveRequest};
+use connection::CurrentConnection;
+use control_frames::ControlFrameStats;
+use default_ssids::DefaultNetwork;
+use devices::{DeviceRegistry, DeviceUser};
+use eapol::HandshakeStatus;
+use endpoints::ExternalEndpoint;
+use error::WifiError;
+use flows::{Conversation, FlowJitter};
+use forwarder::Forwarder;
+use interface_compare::InterfaceComparison;
+use lan_devices::{DeviceInventory, KnownDevice};
+use network_labels::{NetworkLabel, NetworkLabels};
+use packet_sniffer::{
+    apply_filter, capture_packets, open_capture_with_retry, open_stream_capture,
+    parse_captured_packet, parse_loopback_layers, parse_packet_bytes, protocol_filter,
+    validate_filter, CaptureComplete, CaptureRate, CaptureStalled, CaptureSummary, LocalAddresses,
+    LocalTraffic, PacketCapture, PacketInfo, ParseDepth, ParseFailures,
+};
+use probing::{ProbingClient, ProbingClients};
+use radiotap::{RadiotapParser, WiFiFrame};
+use reassembly::Ipv4Reassembler;
+use regulatory::RegulatoryViolation;
+use replay::{Replay, ReplayEvent, ReplayFile, ReplayInfo, ReplayStatus};
+use reverse_dns::ResolverConfig;
+use schemas::PayloadSchemas;
+use sessions::{Session, SessionManager, SessionMeta};
+use signal_meter::SignalMeter;
+use spectrum::{Band, Span};
+use timestamp::TimestampPrecision;
+use wifi_scanner::{
+    scan_wifi_internal, FrameClass, MgmtSubtype, NetworkSortKey, ScannerConfig, SsidChange,
+    WiFiNetwork, MAX_RETENTION_SECS, MIN_RETENTION_SECS,
+};
 
+mod analysis;
+mod arp_watch;
+mod benchmark;
+mod capabilities;
+mod capture_output;
+mod connection;
+mod control_frames;
+mod default_ssids;
+mod devices;
+mod dhcp;
+mod eapol;
+mod endpoints;
+mod error;
+mod export;
+mod flows;
+mod forwarder;
+mod hex;
+mod interface_compare;
+mod karma;
+mod lan_devices;
+mod network_labels;
+mod oui;
 mod packet_sniffer;
+mod probing;
 mod radiotap;
+mod reassembly;
+mod regulatory;
+mod replay;
+mod reverse_dns;
+mod roaming;
+mod schemas;
+mod security;
+mod sessions;
+mod signal_meter;
+mod spectrum;
+mod timestamp;
 mod wifi_scanner;
 
+/// Monitor-mode adapter used for scanning and signal metering
+const WIFI_INTERFACE: &str = "wlxa86e84531e13";
+
+// Each piece of managed state a scan feeds is its own argument
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
-async fn scan_wifi(window: tauri::Window) -> Result<Vec<WiFiNetwork>, String> {
+async fn scan_wifi(
+    window: tauri::Window,
+    devices: tauri::State<'_, DeviceRegistry>,
+    scanner_config: tauri::State<'_, Mutex<ScannerConfig>>,
+    capture: tauri::State<'_, PacketCapture>,
+    occupancy: tauri::State<'_, Mutex<OccupancyHistory>>,
+    congestion: tauri::State<'_, Mutex<CongestionThresholds>>,
+    probing: tauri::State<'_, Mutex<ProbingClients>>,
+    control_stats: tauri::State<'_, Mutex<Vec<ControlFrameStats>>>,
+    labels: tauri::State<'_, Mutex<NetworkLabels>>,
+    sort_by: Option<NetworkSortKey>,
+) -> Result<Vec<WiFiNetwork>, WifiError> {
     info!("Scanning WiFi networks");
 
-    match scan_wifi_internal("wlxa86e84531e13") {
+    let interface = WIFI_INTERFACE;
+    let _lease = devices.acquire(interface, DeviceUser::Scan)?;
+    let mut config = scanner_config.lock().unwrap().clone();
+    if let Some(sort_by) = sort_by {
+        config.sort_by = sort_by;
+    }
+    let thresholds = *congestion.lock().unwrap();
+    if let Some(bands) = &config.bands {
+        check_bands_supported(interface, bands)?;
+    }
+
+    match scan_wifi_internal(interface, &config) {
         Ok((stop_tx, progress_rx)) => {
             let mut final_networks = Vec::new();
-            let timeout = std::time::Duration::from_secs(10);
+            let mut alerted_bssids = HashSet::new();
+            let mut known_ssid_counts: HashMap<String, usize> = HashMap::new();
+            // The scanner ends itself; this only guards against it stalling
+            let timeout = std::time::Duration::from_secs(config.scan_timeout_secs + 2);
             let start_time = std::time::Instant::now();
+            *occupancy.lock().unwrap() = OccupancyHistory::default();
+            control_stats.lock().unwrap().clear();
 
             while start_time.elapsed() < timeout {
                 match progress_rx.try_recv() {
-                    Ok(progress) => {
+                    Ok(mut progress) => {
+                        labels.lock().unwrap().apply(&mut progress.networks);
+                        occupancy
+                            .lock()
+                            .unwrap()
+                            .record(&progress.networks, &thresholds);
+                        {
+                            let mut probing = probing.lock().unwrap();
+                            for probe in &progress.probe_requests {
+                                probing.record(probe);
+                            }
+                        }
+                        if !progress.control_frames.is_empty() {
+                            *control_stats.lock().unwrap() = progress.control_frames.clone();
+                        }
+                        for alert in &progress.karma_alerts {
+                            if let Err(e) = window.emit("karma_attack", alert) {
+                                warn!("Failed to emit KARMA alert: {}", e);
+                            }
+                        }
+                        for exchange in &progress.auth_exchanges {
+                            if let Err(e) = window.emit("auth_exchange", exchange) {
+                                warn!("Failed to emit auth exchange: {}", e);
+                            }
+                        }
+
                         // Emit progress through window
                         if let Err(e) = window.emit("wifi_scan_progress", &progress.networks) {
                             warn!("Failed to emit progress: {}", e);
                         }
 
-                        if progress.is_complete {
+                        for network in &progress.networks {
+                            let history = &network.ssid_history;
+                            let known = known_ssid_counts
+                                .insert(network.bssid.clone(), history.len())
+                                .unwrap_or(history.len());
+                            if history.len() > known && known > 0 {
+                                let (timestamp, current) = history[history.len() - 1].clone();
+                                let change = SsidChange {
+                                    bssid: network.bssid.clone(),
+                                    previous: history[history.len() - 2].1.clone(),
+                                    current,
+                                    timestamp,
+                                };
+                                if let Err(e) = window.emit("ssid_changed", change) {
+                                    warn!("Failed to emit SSID change: {}", e);
+                                }
+                            }
+
+                            if network.multi_channel_anomaly
+                                && alerted_bssids.insert(network.bssid.clone())
+                            {
+                                if let Err(e) = window.emit("multi_channel_bssid", network) {
+                                    warn!("Failed to emit multi-channel alert: {}", e);
+                                }
+                            }
+                        }
+
+                        if let Some(completion) = progress.completion {
+                            if let Err(e) = window.emit("wifi_scan_complete", completion) {
+                                warn!("Failed to emit scan completion: {}", e);
+                            }
                             final_networks = progress.networks;
                             break;
                         }
@@ -52,6 +215,12 @@ async fn scan_wifi(window: tauri::Window) -> Result<Vec<WiFiNetwork>, String> {
             // Stop the scanner
             let _ = stop_tx.send(());
 
+            capture
+                .sessions
+                .lock()
+                .unwrap()
+                .record_networks(&mut final_networks);
+
             info!(
                 "WiFi scan completed successfully, found {} networks",
                 final_networks.len()
@@ -59,43 +228,178 @@ async fn scan_wifi(window: tauri::Window) -> Result<Vec<WiFiNetwork>, String> {
             Ok(final_networks)
         }
         Err(e) => {
-            error!("Failed to scan networks: {:?}", e);
-            Err(format!("Failed to scan networks: {:?}", e))
+            error!("Failed to scan networks: {}", e);
+            Err(e)
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Scans `iface_a` and `iface_b` side by side for `duration_secs` to compare
+/// what each adapter hears. An adapter that fails is reported as such
+/// rather than failing the comparison.
+#[tauri::command]
+async fn compare_interfaces(
+    iface_a: String,
+    iface_b: String,
+    duration_secs: u64,
+    app: tauri::AppHandle,
+    scanner_config: tauri::State<'_, Mutex<ScannerConfig>>,
+) -> Result<InterfaceComparison, WifiError> {
+    if iface_a == iface_b {
+        return Err(WifiError::ParseError(
+            "Pick two different interfaces to compare".to_string(),
+        ));
+    }
+    info!(
+        "Comparing {} and {} over {}s",
+        iface_a, iface_b, duration_secs
+    );
+
+    let mut config = scanner_config.lock().unwrap().clone();
+    config.scan_timeout_secs = duration_secs;
+    // Both adapters must listen for the whole comparison
+    config.settle_secs = None;
+    let duration = Duration::from_secs(duration_secs);
+
+    run_blocking(move || {
+        let devices = app.state::<DeviceRegistry>();
+        let scan = |interface: &str| {
+            let _lease = devices.acquire(interface, DeviceUser::Scan)?;
+            interface_compare::scan_for(interface, &config, duration)
+        };
+
+        let (a, b) = thread::scope(|scope| {
+            let a = scope.spawn(|| scan(&iface_a));
+            let b = scan(&iface_b);
+            let a = a.join().unwrap_or_else(|_| {
+                Err(WifiError::CaptureError(format!(
+                    "Scan of {} panicked",
+                    iface_a
+                )))
+            });
+            (a, b)
+        });
+        Ok(InterfaceComparison::new(&iface_a, a, &iface_b, b))
+    })
+    .await
+}
+
+/// Labels a BSSID for surveys; scan results carry the label from then on.
+/// An empty label removes it.
+#[tauri::command]
+fn set_network_label(
+    bssid: String,
+    label: String,
+    color: Option<String>,
+    labels: tauri::State<Mutex<NetworkLabels>>,
+) -> Result<Option<NetworkLabel>, WifiError> {
+    info!("Labelling {} as {:?}", bssid, label);
+    labels.lock().unwrap().set(&bssid, &label, color)
+}
+
+#[tauri::command]
+fn get_network_labels(
+    labels: tauri::State<Mutex<NetworkLabels>>,
+) -> Result<Vec<NetworkLabel>, WifiError> {
+    Ok(labels.lock().unwrap().labels())
+}
+
+/// Streams the signal of one BSSID as "bssid_signal" events, with the
+/// adapter parked on the network's frequency, until `stop_bssid_monitor`.
+#[tauri::command]
+fn monitor_bssid_signal(
+    bssid: String,
+    frequency: u32,
+    window: tauri::Window,
+    devices: tauri::State<'_, DeviceRegistry>,
+    meter: tauri::State<'_, SignalMeter>,
+) -> Result<(), WifiError> {
+    let lease = devices.acquire(WIFI_INTERFACE, DeviceUser::SignalMeter)?;
+    meter.start(lease, WIFI_INTERFACE, &bssid, frequency, move |reading| {
+        if let Err(e) = window.emit("bssid_signal", reading) {
+            warn!("Failed to emit signal reading: {}", e);
+        }
+    })
+}
+
+#[tauri::command]
+fn stop_bssid_monitor(meter: tauri::State<'_, SignalMeter>) -> Result<(), WifiError> {
+    if meter.stop() {
+        Ok(())
+    } else {
+        Err(WifiError::CaptureError(
+            "Signal meter is not running".to_string(),
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 struct ChannelData {
+    band: Band,
     channel: u32,
     occupancy: f32,
+    // Occupancy weighted by beacon_count, so chatty APs count for more
+    beacon_weighted_occupancy: f32,
+    severity: Severity,
 }
 
-#[tauri::command]
-async fn get_channel_data(networks: Vec<WiFiNetwork>) -> Result<Vec<ChannelData>, String> {
-    debug!("Calculating channel data for {} networks", networks.len());
+/// The band a network is on. Networks heard without a radiotap frequency
+/// fall back to their 2.4 GHz DS Parameter Set channel.
+fn network_band(network: &WiFiNetwork) -> Option<Band> {
+    Band::from_frequency(network.frequency).or_else(|| {
+        (1..=14)
+            .contains(&network.channel)
+            .then_some(Band::TwoPointFour)
+    })
+}
+
+/// Per-channel occupancy of the 20 MHz channels of `band`. Occupancy is a
+/// share of the networks on `band` only.
+fn channel_occupancy(
+    networks: &[WiFiNetwork],
+    band: Band,
+    thresholds: &CongestionThresholds,
+) -> Vec<ChannelData> {
+    let channels = band.channels();
     let mut channel_count: HashMap<u32, u32> = HashMap::new();
     let mut channel_signal: HashMap<u32, u32> = HashMap::new();
+    let mut channel_beacons: HashMap<u32, u64> = HashMap::new();
+    let mut channel_beacon_signal: HashMap<u32, u64> = HashMap::new();
 
-    // Initialize data for all 13 channels
-    for channel in 1..=13 {
+    // Initialize data for all channels of the band
+    for &channel in &channels {
         channel_count.insert(channel, 0);
         channel_signal.insert(channel, 0);
+        channel_beacons.insert(channel, 0);
+        channel_beacon_signal.insert(channel, 0);
     }
 
+    // 6 GHz channel numbers overlap the 2.4 GHz ones, so match the band too.
+    // Shares are of this band's networks, so a busy other band doesn't make
+    // this one look clear.
+    let in_band: Vec<&WiFiNetwork> = networks
+        .iter()
+        .filter(|network| network_band(network) == Some(band))
+        .collect();
+
     // Process network data
-    for network in &networks {
-        if network.channel >= 1 && network.channel <= 13 {
+    for network in &in_band {
+        if channels.contains(&network.channel) {
+            let beacons = network.beacon_count as u64;
             *channel_count.entry(network.channel).or_insert(0) += 1;
             *channel_signal.entry(network.channel).or_insert(0) += network.signal_quality;
+            *channel_beacons.entry(network.channel).or_insert(0) += beacons;
+            *channel_beacon_signal.entry(network.channel).or_insert(0) +=
+                beacons * network.signal_quality as u64;
         }
     }
 
-    let total_networks = networks.len() as f32;
+    let total_networks = in_band.len() as f32;
+    let total_beacons: u64 = in_band.iter().map(|n| n.beacon_count as u64).sum();
     let mut channel_data: Vec<ChannelData> = Vec::new();
 
     // Calculate occupancy for all channels
-    for channel in 1..=13 {
+    for channel in channels {
         let count = *channel_count.get(&channel).unwrap_or(&0);
         let signal = *channel_signal.get(&channel).unwrap_or(&0);
         let avg_signal = if count > 0 {
@@ -109,15 +413,679 @@ async fn get_channel_data(networks: Vec<WiFiNetwork>) -> Result<Vec<ChannelData>
             0.0
         };
 
-        channel_data.push(ChannelData { channel, occupancy });
+        let beacons = *channel_beacons.get(&channel).unwrap_or(&0);
+        let beacon_signal = *channel_beacon_signal.get(&channel).unwrap_or(&0);
+        let beacon_weighted_occupancy = if beacons > 0 && total_beacons > 0 {
+            let weighted_signal = beacon_signal as f32 / beacons as f32;
+            (beacons as f32 / total_beacons as f32) * (weighted_signal / 100.0)
+        } else {
+            0.0
+        };
+
+        channel_data.push(ChannelData {
+            band,
+            channel,
+            occupancy,
+            beacon_weighted_occupancy,
+            severity: thresholds.classify(occupancy),
+        });
+    }
+
+    channel_data
+}
+
+/// Channel data along with the thresholds its severities were judged by, so
+/// a legend can show them.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+struct ChannelReport {
+    channels: Vec<ChannelData>,
+    thresholds: CongestionThresholds,
+}
+
+/// Channel data for `band`, 2.4 GHz unless given.
+#[tauri::command]
+async fn get_channel_data(
+    networks: Vec<WiFiNetwork>,
+    band: Option<Band>,
+    congestion: tauri::State<'_, Mutex<CongestionThresholds>>,
+) -> Result<ChannelReport, WifiError> {
+    let band = band.unwrap_or(Band::TwoPointFour);
+    debug!(
+        "Calculating {} channel data for {} networks",
+        band.label(),
+        networks.len()
+    );
+    let thresholds = *congestion.lock().unwrap();
+    let channels = channel_occupancy(&networks, band, &thresholds);
+    info!(
+        "Channel data calculation completed for {} {} channels",
+        channels.len(),
+        band.label()
+    );
+    Ok(ChannelReport {
+        channels,
+        thresholds,
+    })
+}
+
+#[tauri::command]
+fn set_congestion_thresholds(
+    moderate: f32,
+    congested: f32,
+    congestion: tauri::State<Mutex<CongestionThresholds>>,
+) -> Result<CongestionThresholds, WifiError> {
+    let thresholds = CongestionThresholds::new(moderate, congested).ok_or_else(|| {
+        WifiError::ParseError(format!(
+            "Congestion thresholds must satisfy 0 <= moderate < congested <= 1, got {} and {}",
+            moderate, congested
+        ))
+    })?;
+    info!(
+        "Channels are moderate from {} and congested from {} occupancy",
+        moderate, congested
+    );
+    *congestion.lock().unwrap() = thresholds;
+    Ok(thresholds)
+}
+
+/// Occupancy a non-DFS channel may exceed the quietest DFS channel by and
+/// still be recommended over it.
+const DFS_OCCUPANCY_MARGIN: f32 = 0.05;
+
+/// The channel to put an AP on, and why.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ChannelRecommendation {
+    band: Band,
+    channel: u32,
+    frequency: u32,
+    occupancy: f32,
+    severity: Severity,
+    /// Whether the channel needs DFS, so the AP may be forced off by radar
+    dfs: bool,
+    reasons: Vec<String>,
+}
+
+/// Picks the least occupied channel of `band`, taking a non-DFS channel
+/// over a DFS one unless the DFS channel is clearly quieter. DFS channels
+/// cost a minute of listening for radar on every start and can be vacated
+/// at any time.
+fn recommend_channel_for(
+    networks: &[WiFiNetwork],
+    band: Band,
+    thresholds: &CongestionThresholds,
+) -> Option<ChannelRecommendation> {
+    let channels = channel_occupancy(networks, band, thresholds);
+    let quietest = |dfs: bool| {
+        channels
+            .iter()
+            .filter(|data| band.is_dfs(data.channel) == dfs)
+            .min_by(|a, b| a.occupancy.total_cmp(&b.occupancy))
+    };
+    let non_dfs = quietest(false);
+    let dfs = quietest(true);
+
+    let mut reasons = Vec::new();
+    let chosen = match (non_dfs, dfs) {
+        (Some(non_dfs), Some(dfs)) if dfs.occupancy + DFS_OCCUPANCY_MARGIN < non_dfs.occupancy => {
+            reasons.push(format!(
+                "DFS channel {} is clearly less occupied than the best non-DFS channel {} ({:.2} vs {:.2})",
+                dfs.channel, non_dfs.channel, dfs.occupancy, non_dfs.occupancy
+            ));
+            reasons.push(
+                "The AP must check for radar before using it and leave it if radar is detected"
+                    .to_string(),
+            );
+            dfs
+        }
+        (Some(non_dfs), Some(dfs)) => {
+            reasons.push(format!(
+                "Least occupied non-DFS channel ({:.2} occupancy)",
+                non_dfs.occupancy
+            ));
+            if dfs.occupancy < non_dfs.occupancy {
+                reasons.push(format!(
+                    "DFS channel {} is only slightly quieter ({:.2}), not enough to risk radar interruptions",
+                    dfs.channel, dfs.occupancy
+                ));
+            }
+            non_dfs
+        }
+        (Some(only), None) | (None, Some(only)) => {
+            reasons.push(format!(
+                "Least occupied channel in the band ({:.2} occupancy)",
+                only.occupancy
+            ));
+            only
+        }
+        (None, None) => return None,
+    };
+
+    let neighbors = networks
+        .iter()
+        .filter(|n| network_band(n) == Some(band) && n.channel == chosen.channel)
+        .count();
+    reasons.push(match neighbors {
+        0 => "No networks were seen on it".to_string(),
+        1 => "1 network was seen on it".to_string(),
+        count => format!("{} networks were seen on it", count),
+    });
+
+    Some(ChannelRecommendation {
+        band,
+        channel: chosen.channel,
+        frequency: band.channel_frequency(chosen.channel),
+        occupancy: chosen.occupancy,
+        severity: chosen.severity,
+        dfs: band.is_dfs(chosen.channel),
+        reasons,
+    })
+}
+
+/// Recommends a channel in `band`, 5 GHz unless given.
+#[tauri::command]
+async fn recommend_channel(
+    networks: Vec<WiFiNetwork>,
+    band: Option<Band>,
+    congestion: tauri::State<'_, Mutex<CongestionThresholds>>,
+) -> Result<ChannelRecommendation, WifiError> {
+    let band = band.unwrap_or(Band::Five);
+    let thresholds = *congestion.lock().unwrap();
+    let recommendation = recommend_channel_for(&networks, band, &thresholds).ok_or_else(|| {
+        WifiError::ParseError(format!("{} has no channels to recommend", band.label()))
+    })?;
+    info!(
+        "Recommending {} channel {}{}",
+        band.label(),
+        recommendation.channel,
+        if recommendation.dfs { " (DFS)" } else { "" }
+    );
+    Ok(recommendation)
+}
+
+/// Combined signal quality of co-channel and overlapping networks from
+/// which a channel is rated fair, then poor.
+const FAIR_CHANNEL_SIGNAL: u32 = 60;
+const POOR_CHANNEL_SIGNAL: u32 = 150;
+
+/// Networks contending with one channel and how strongly they're heard.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct NeighborLoad {
+    networks: usize,
+    /// Sum of the networks' signal quality (0-100 each)
+    total_signal: u32,
+}
+
+impl NeighborLoad {
+    fn add(&mut self, network: &WiFiNetwork) {
+        self.networks += 1;
+        self.total_signal += network.signal_quality;
+    }
+}
+
+/// How crowded a fixed channel is.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ChannelChoice {
+    band: Band,
+    channel: u32,
+    /// Networks on the same channel, which share airtime with it
+    co_channel: NeighborLoad,
+    /// Networks on partially overlapping 2.4 GHz channels, which interfere
+    /// without deferring to it
+    overlapping: NeighborLoad,
+    verdict: String,
+}
+
+/// Rates `channel` of `band` by the networks whose operating span covers it
+/// and those that only partly overlap it. Bonded networks count on every
+/// 20 MHz channel they span; 20 MHz channels only partly overlap in 2.4 GHz,
+/// where they're 5 MHz apart but 20 MHz wide.
+fn evaluate_channel(networks: &[WiFiNetwork], band: Band, channel: u32) -> ChannelChoice {
+    let chosen = Span::centered(band.channel_frequency(channel), 20);
+    let mut co_channel = NeighborLoad::default();
+    let mut overlapping = NeighborLoad::default();
+    for network in networks.iter().filter(|n| network_band(n) == Some(band)) {
+        let span = Span::centered(network.center_frequency, network.channel_width_mhz);
+        let overlap = span.overlap(&chosen);
+        if overlap >= chosen.width() {
+            co_channel.add(network);
+        } else if overlap > 0 {
+            overlapping.add(network);
+        }
+    }
+
+    let total_signal = co_channel.total_signal + overlapping.total_signal;
+    let verdict = if co_channel.networks == 0 && overlapping.networks == 0 {
+        format!(
+            "Good: no other networks on or overlapping channel {}",
+            channel
+        )
+    } else if total_signal < FAIR_CHANNEL_SIGNAL {
+        format!(
+            "Good: only weak neighbors on or overlapping channel {}",
+            channel
+        )
+    } else if total_signal < POOR_CHANNEL_SIGNAL {
+        format!(
+            "Fair: {} networks share channel {} and {} overlap it",
+            co_channel.networks, channel, overlapping.networks
+        )
+    } else if overlapping.total_signal > co_channel.total_signal {
+        let advice = if band == Band::TwoPointFour && ![1, 6, 11].contains(&channel) {
+            "moving to 1, 6 or 11 would at least let them share airtime"
+        } else {
+            "they're off the usual channel plan, so there's no cleaner channel to move to"
+        };
+        format!(
+            "Poor: strong networks on overlapping channels interfere with channel {}; {}",
+            channel, advice
+        )
+    } else {
+        format!(
+            "Poor: channel {} is crowded by {} strong co-channel networks",
+            channel, co_channel.networks
+        )
+    };
+
+    ChannelChoice {
+        band,
+        channel,
+        co_channel,
+        overlapping,
+        verdict,
+    }
+}
+
+/// Checks how crowded `channel` is, for users who can't freely pick one.
+#[tauri::command]
+async fn evaluate_channel_choice(
+    channel: u32,
+    band: Band,
+    networks: Vec<WiFiNetwork>,
+) -> Result<ChannelChoice, WifiError> {
+    if !band.channels().contains(&channel) {
+        return Err(WifiError::ParseError(format!(
+            "{} is not a {} channel",
+            channel,
+            band.label()
+        )));
+    }
+    let choice = evaluate_channel(&networks, band, channel);
+    info!("{} channel {}: {}", band.label(), channel, choice.verdict);
+    Ok(choice)
+}
+
+/// Occupancy samples kept for the timeline, about 10 minutes of progress
+/// updates at the scanner's 500 ms interval.
+const MAX_OCCUPANCY_SAMPLES: usize = 1200;
+
+/// Channel occupancy sampled at every scan progress update.
+#[derive(Default)]
+struct OccupancyHistory {
+    samples: VecDeque<(u64, Vec<ChannelData>)>,
+}
+
+impl OccupancyHistory {
+    fn record(&mut self, networks: &[WiFiNetwork], thresholds: &CongestionThresholds) {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        if self.samples.len() == MAX_OCCUPANCY_SAMPLES {
+            self.samples.pop_front();
+        }
+        let data = Band::ALL
+            .into_iter()
+            .flat_map(|band| channel_occupancy(networks, band, thresholds))
+            .collect();
+        self.samples.push_back((timestamp_ms, data));
+    }
+
+    /// The samples as one series per channel of every band, aligned with
+    /// `timestamps_ms`.
+    fn timeline(&self) -> OccupancyTimeline {
+        let timestamps_ms = self.samples.iter().map(|(ts, _)| *ts).collect();
+        let channels = Band::ALL
+            .into_iter()
+            .flat_map(|band| {
+                band.channels()
+                    .into_iter()
+                    .map(move |channel| (band, channel))
+            })
+            .map(|(band, channel)| ChannelSeries {
+                band,
+                channel,
+                occupancy: self
+                    .samples
+                    .iter()
+                    .map(|(_, data)| {
+                        data.iter()
+                            .find(|d| d.band == band && d.channel == channel)
+                            .map_or(0.0, |d| d.occupancy)
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        OccupancyTimeline {
+            timestamps_ms,
+            channels,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ChannelSeries {
+    band: Band,
+    channel: u32,
+    occupancy: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct OccupancyTimeline {
+    timestamps_ms: Vec<u64>,
+    channels: Vec<ChannelSeries>,
+}
+
+/// Occupancy of each channel over the latest scan, for a chart over time.
+#[tauri::command]
+fn get_occupancy_timeline(
+    history: tauri::State<Mutex<OccupancyHistory>>,
+) -> Result<OccupancyTimeline, WifiError> {
+    Ok(history.lock().unwrap().timeline())
+}
+
+#[tauri::command]
+fn set_scan_snaplen(
+    snaplen: i32,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    if snaplen < 256 {
+        return Err(WifiError::CaptureError(format!(
+            "Snaplen {} is too small to hold a beacon header",
+            snaplen
+        )));
+    }
+
+    info!("Setting scanner snaplen to {}", snaplen);
+    scanner_config.lock().unwrap().snaplen = snaplen;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_network_retention(
+    retention_secs: u64,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    if !(MIN_RETENTION_SECS..=MAX_RETENTION_SECS).contains(&retention_secs) {
+        return Err(WifiError::ParseError(format!(
+            "Network retention must be between {}s and {}s, got {}s",
+            MIN_RETENTION_SECS, MAX_RETENTION_SECS, retention_secs
+        )));
+    }
+
+    info!(
+        "Keeping networks for {}s after their last beacon",
+        retention_secs
+    );
+    scanner_config.lock().unwrap().retention_secs = retention_secs;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_beacon_throttle(
+    ms: u64,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    info!("Setting per-BSSID beacon throttle to {}ms", ms);
+    scanner_config.lock().unwrap().beacon_throttle_ms = ms;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_adaptive_scan(
+    settle_secs: Option<u64>,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    match settle_secs {
+        Some(secs) => info!("Scans end once no new network appears for {}s", secs),
+        None => info!("Scans run until the timeout"),
+    }
+    scanner_config.lock().unwrap().settle_secs = settle_secs;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_active_probing(
+    enabled: bool,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    info!(
+        "Active probing {} for the next scan",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    scanner_config.lock().unwrap().active_probing = enabled;
+    Ok(())
+}
+
+/// Sets the management frame subtypes the next scans capture, on top of
+/// beacons.
+#[tauri::command]
+fn set_scan_subtypes(
+    subtypes: Vec<MgmtSubtype>,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    let mut config = scanner_config.lock().unwrap();
+    config.subtypes = subtypes;
+    info!("Scan filter: {}", wifi_scanner::scan_filter(&config));
+    Ok(())
+}
+
+/// Sets which frame classes (management, control, data) the next scans
+/// capture.
+#[tauri::command]
+fn set_frame_class_filter(
+    classes: Vec<FrameClass>,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    if classes.is_empty() {
+        return Err(WifiError::CaptureError(
+            "At least one frame class must be captured".to_string(),
+        ));
+    }
+    let mut config = scanner_config.lock().unwrap();
+    config.frame_classes = classes;
+    info!("Scan filter: {}", wifi_scanner::scan_filter(&config));
+    Ok(())
+}
+
+/// RTS/CTS/ACK counts and airtime per channel from the last scan that
+/// captured control frames.
+#[tauri::command]
+fn get_control_frame_stats(
+    control_stats: tauri::State<Mutex<Vec<ControlFrameStats>>>,
+) -> Result<Vec<ControlFrameStats>, WifiError> {
+    Ok(control_stats.lock().unwrap().clone())
+}
+
+/// Collects probe requests during the next scans, for `get_probing_clients`.
+#[tauri::command]
+fn set_probe_request_collection(
+    enabled: bool,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    info!(
+        "Probe request collection {} for the next scan",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    scanner_config.lock().unwrap().probe_requests = enabled;
+    Ok(())
+}
+
+/// Whether the next scans hide the user part of EAP identities, keeping
+/// only the realm.
+#[tauri::command]
+fn set_eap_identity_redaction(
+    enabled: bool,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    info!(
+        "EAP identity redaction {} for the next scan",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    scanner_config.lock().unwrap().redact_eap_identities = enabled;
+    Ok(())
+}
+
+/// Devices heard probing for networks across all scans so far, with the
+/// SSIDs each asked for.
+#[tauri::command]
+fn get_probing_clients(
+    probing: tauri::State<Mutex<ProbingClients>>,
+) -> Result<Vec<ProbingClient>, WifiError> {
+    Ok(probing.lock().unwrap().clients())
+}
+
+#[tauri::command]
+fn clear_probing_clients(probing: tauri::State<Mutex<ProbingClients>>) -> Result<(), WifiError> {
+    probing.lock().unwrap().clear();
+    Ok(())
+}
+
+/// Fails if `interface` can't tune to one of `bands`. When the adapter's
+/// capabilities can't be read, scanning goes ahead on the assumption that
+/// it can.
+fn check_bands_supported(interface: &str, bands: &[Band]) -> Result<(), WifiError> {
+    let capabilities = match interface_capabilities(interface) {
+        Ok(capabilities) => capabilities,
+        Err(e) => {
+            warn!("Can't check which bands {} supports: {}", interface, e);
+            return Ok(());
+        }
+    };
+
+    let unsupported: Vec<&str> = bands
+        .iter()
+        .filter(|band| !capabilities.supports(**band))
+        .map(|band| band.label())
+        .collect();
+    if !unsupported.is_empty() {
+        return Err(WifiError::CaptureError(format!(
+            "{} ({}) can't receive on {}",
+            interface,
+            capabilities.phy,
+            unsupported.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_interface_capabilities(interface: String) -> Result<InterfaceCapabilities, WifiError> {
+    interface_capabilities(&interface)
+}
+
+/// Limits scans to `bands`, or reports every band again with None.
+#[tauri::command]
+fn set_scan_bands(
+    bands: Option<Vec<Band>>,
+    scanner_config: tauri::State<Mutex<ScannerConfig>>,
+) -> Result<(), WifiError> {
+    if let Some(bands) = &bands {
+        check_bands_supported(WIFI_INTERFACE, bands)?;
+        info!("Scanning only {:?}", bands);
+    } else {
+        info!("Scanning all bands");
     }
+    scanner_config.lock().unwrap().bands = bands;
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_band_summary(networks: Vec<WiFiNetwork>) -> Result<Vec<BandSummary>, WifiError> {
+    debug!("Summarizing bands for {} networks", networks.len());
+    Ok(band_summary(&networks))
+}
+
+#[tauri::command]
+async fn audit_network(
+    ssid: String,
+    networks: Vec<WiFiNetwork>,
+) -> Result<NetworkAudit, WifiError> {
+    info!("Auditing deployment of SSID: {}", ssid);
+    Ok(audit_ssid(&ssid, &networks))
+}
+
+#[tauri::command]
+async fn detect_beacon_anomalies(
+    networks: Vec<WiFiNetwork>,
+) -> Result<Vec<BeaconAnomaly>, WifiError> {
+    debug!("Checking {} networks for beacon anomalies", networks.len());
+    Ok(beacon_anomalies(&networks))
+}
+
+#[tauri::command]
+async fn group_physical_aps(
+    networks: Vec<WiFiNetwork>,
+    options: Option<ApGroupingOptions>,
+) -> Result<Vec<PhysicalAp>, WifiError> {
+    let options = options.unwrap_or_default();
+    debug!(
+        "Grouping {} networks into physical APs with {:?}",
+        networks.len(),
+        options
+    );
+    Ok(analysis::group_physical_aps(&networks, &options))
+}
+
+#[tauri::command]
+async fn security_summary(networks: Vec<WiFiNetwork>) -> Result<SecuritySummary, WifiError> {
+    debug!("Summarizing security for {} networks", networks.len());
+    Ok(analysis::security_summary(&networks))
+}
 
-    info!("Channel data calculation completed for all 13 channels");
-    Ok(channel_data)
+/// Networks whose SSID looks like an untouched vendor default.
+#[tauri::command]
+async fn flag_default_networks(
+    networks: Vec<WiFiNetwork>,
+) -> Result<Vec<DefaultNetwork>, WifiError> {
+    debug!("Checking {} networks for default SSIDs", networks.len());
+    Ok(default_ssids::default_networks(&networks))
+}
+
+#[tauri::command]
+async fn check_regulatory_compliance(
+    networks: Vec<WiFiNetwork>,
+) -> Result<Vec<RegulatoryViolation>, WifiError> {
+    debug!(
+        "Checking {} networks against their regulatory domains",
+        networks.len()
+    );
+    Ok(regulatory::check_compliance(&networks))
+}
+
+#[tauri::command]
+async fn interference_matrix(
+    networks: Vec<WiFiNetwork>,
+    band: Band,
+) -> Result<InterferenceMatrix, WifiError> {
+    debug!(
+        "Computing {:?} interference matrix for {} networks",
+        band,
+        networks.len()
+    );
+    Ok(analysis::interference_matrix(&networks, band))
+}
+
+/// The network `interface` is associated with, for highlighting the
+/// user's own channel. None when it isn't connected.
+#[tauri::command]
+fn get_current_connection(interface: String) -> Result<Option<CurrentConnection>, WifiError> {
+    debug!("Querying current connection of {}", interface);
+    connection::current_connection(&interface)
 }
 
 #[tauri::command]
-fn list_devices() -> Result<Vec<String>, String> {
+fn list_devices() -> Result<Vec<String>, WifiError> {
     info!("Listing network devices");
     match Device::list() {
         Ok(devices) => {
@@ -127,13 +1095,13 @@ fn list_devices() -> Result<Vec<String>, String> {
         }
         Err(e) => {
             error!("Failed to list devices: {:?}", e);
-            Err(format!("Failed to list devices: {:?}", e))
+            Err(WifiError::from(e))
         }
     }
 }
 
 #[tauri::command]
-fn get_latest_packets(state: tauri::State<PacketCapture>) -> Result<Vec<PacketInfo>, String> {
+fn get_latest_packets(state: tauri::State<PacketCapture>) -> Result<Vec<PacketInfo>, WifiError> {
     let captured_packets = state.captured_packets.lock().unwrap();
     let mut last_fetch_timestamp = state.last_fetch_timestamp.lock().unwrap();
 
@@ -150,65 +1118,977 @@ fn get_latest_packets(state: tauri::State<PacketCapture>) -> Result<Vec<PacketIn
     Ok(new_packets)
 }
 
+/// Silence after which a capture that was delivering packets is reported
+/// as stalled.
+const STALL_AFTER: Duration = Duration::from_secs(10);
+/// Peak packets per second below which an interface is too quiet for
+/// silence to mean anything.
+const BUSY_PACKETS_PER_SEC: u64 = 5;
+/// Longest a capture runs unless the caller picks a limit, so a frontend
+/// that crashes or never stops it doesn't hold the device forever.
+const DEFAULT_MAX_CAPTURE_SECS: u64 = 12 * 60 * 60;
+/// How often newly seen LAN devices are written to the inventory file.
+const DEVICE_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// Newest buffered packets a resolved hostname is filled into.
+const HOST_BACKFILL_PACKETS: usize = 5000;
+
+/// Starts capturing on `device_name` until stopped, `packet_limit` packets
+/// are captured or `max_duration_secs` pass (12 hours unless given).
 #[tauri::command]
 async fn start_packet_capture(
     device_name: String,
+    packet_limit: Option<u64>,
+    max_duration_secs: Option<u64>,
     state: tauri::State<'_, PacketCapture>,
+    devices: tauri::State<'_, DeviceRegistry>,
     window: tauri::Window,
-) -> Result<(), String> {
+) -> Result<(), WifiError> {
     info!("Starting packet capture on device: {}", device_name);
+    let max_duration = Duration::from_secs(max_duration_secs.unwrap_or(DEFAULT_MAX_CAPTURE_SECS));
+
+    // Held by the capture thread until it exits
+    let lease = devices.acquire(&device_name, DeviceUser::Capture)?;
 
     *state.running.lock().unwrap() = true;
+    *state.paused.lock().unwrap() = false;
     *state.device.lock().unwrap() = Some(device_name.clone());
+    let capture_generation = state.generation.fetch_add(1, Ordering::SeqCst) + 1;
 
     // Clone Arc for state and window to move into the thread
     let running = Arc::clone(&state.running);
+    let paused = Arc::clone(&state.paused);
     let captured_packets = Arc::clone(&state.captured_packets);
+    let rate_packets = Arc::clone(&state.rate_packets);
+    let rate_bytes = Arc::clone(&state.rate_bytes);
+    let packets_per_sec = Arc::clone(&state.packets_per_sec);
+    let bytes_per_sec = Arc::clone(&state.bytes_per_sec);
+    let total_packets = Arc::clone(&state.total_packets);
+    let total_bytes = Arc::clone(&state.total_bytes);
+    let last_packet_time = Arc::clone(&state.last_packet_time);
+    let clock = Arc::clone(&state.clock);
+    let handshakes = Arc::clone(&state.handshakes);
+    let exclude_local = Arc::clone(&state.exclude_local);
+    let capture_raw = Arc::clone(&state.capture_raw);
+    let payload_capture_bytes = Arc::clone(&state.payload_capture_bytes);
+    let parse_depth = Arc::clone(&state.parse_depth);
+    let reverse_dns = Arc::clone(&state.reverse_dns);
+    let local_addresses = Arc::clone(&state.local_addresses);
+    let local_packets = Arc::clone(&state.local_packets);
+    let local_bytes = Arc::clone(&state.local_bytes);
+    let capture_linktype = Arc::clone(&state.linktype);
+    let capture_precision = Arc::clone(&state.precision);
+    let filter = Arc::clone(&state.filter);
+    let pending_filter = Arc::clone(&state.pending_filter);
+    let pending_save = Arc::clone(&state.pending_save);
+    let known_devices = Arc::clone(&state.known_devices);
+    let bindings = Arc::clone(&state.bindings);
+    let sessions = Arc::clone(&state.sessions);
+    let endpoints = Arc::clone(&state.endpoints);
+    let flows = Arc::clone(&state.flows);
+    let parse_failures = Arc::clone(&state.parse_failures);
+    let forwarder = Arc::clone(&state.forwarder);
     let window = window.clone();
 
+    rate_packets.store(0, Ordering::Relaxed);
+    rate_bytes.store(0, Ordering::Relaxed);
+    local_packets.store(0, Ordering::Relaxed);
+    local_bytes.store(0, Ordering::Relaxed);
+    packets_per_sec.store(0, Ordering::Relaxed);
+    bytes_per_sec.store(0, Ordering::Relaxed);
+    total_packets.store(0, Ordering::Relaxed);
+    total_bytes.store(0, Ordering::Relaxed);
+    clock.lock().unwrap().start();
+    *last_packet_time.lock().unwrap() = None;
+    *local_addresses.lock().unwrap() = LocalAddresses::resolve(&device_name);
+    *capture_linktype.lock().unwrap() = None;
+    *capture_precision.lock().unwrap() = None;
+    *filter.lock().unwrap() = None;
+    *pending_filter.lock().unwrap() = None;
+    *pending_save.lock().unwrap() = None;
+    endpoints.lock().unwrap().clear();
+    bindings.lock().unwrap().clear();
+    flows.lock().unwrap().clear();
+    *parse_failures.lock().unwrap() = ParseFailures::default();
+
+    // Backfill hostnames into the buffered packets as lookups complete
+    {
+        let captured_packets = Arc::clone(&captured_packets);
+        let window = window.clone();
+        reverse_dns.start(move |resolved| {
+            // Lookups finish within seconds of the address first showing up,
+            // so only the newest packets can be waiting for this name
+            let mut captured_packets = captured_packets.lock().unwrap();
+            for packet in captured_packets
+                .iter_mut()
+                .rev()
+                .take(HOST_BACKFILL_PACKETS)
+            {
+                if packet.src_ip.as_ref() == Some(&resolved.ip) {
+                    packet.src_host = Some(resolved.host.clone());
+                }
+                if packet.dst_ip.as_ref() == Some(&resolved.ip) {
+                    packet.dst_host = Some(resolved.host.clone());
+                }
+            }
+            drop(captured_packets);
+            if let Err(err) = window.emit("host_resolved", resolved) {
+                warn!("Error emitting resolved host: {}", err);
+            }
+        });
+    }
+
+    // Report packets/bytes per second once a second while capturing, watch
+    // for the capture going silent and save newly seen LAN devices
+    {
+        let running = Arc::clone(&running);
+        let generation = Arc::clone(&state.generation);
+        let paused = Arc::clone(&paused);
+        let rate_packets = Arc::clone(&rate_packets);
+        let rate_bytes = Arc::clone(&rate_bytes);
+        let packets_per_sec = Arc::clone(&packets_per_sec);
+        let bytes_per_sec = Arc::clone(&bytes_per_sec);
+        let last_packet_time = Arc::clone(&last_packet_time);
+        let known_devices = Arc::clone(&known_devices);
+        let window = window.clone();
+        let device = device_name.clone();
+
+        thread::spawn(move || {
+            let mut peak_packets_per_sec = 0;
+            let mut stalled = false;
+            let mut last_flush = Instant::now();
+
+            loop {
+                thread::sleep(Duration::from_secs(1));
+                // A stop and restart during the sleep leaves `running` set,
+                // but the new capture has its own reporter
+                if !*running.lock().unwrap()
+                    || generation.load(Ordering::SeqCst) != capture_generation
+                {
+                    break;
+                }
+
+                if last_flush.elapsed() >= DEVICE_FLUSH_INTERVAL {
+                    DeviceInventory::flush(&known_devices);
+                    last_flush = Instant::now();
+                }
+
+                let rate = CaptureRate {
+                    device: device.clone(),
+                    packets_per_sec: rate_packets.swap(0, Ordering::Relaxed),
+                    bytes_per_sec: rate_bytes.swap(0, Ordering::Relaxed),
+                };
+                peak_packets_per_sec = peak_packets_per_sec.max(rate.packets_per_sec);
+                packets_per_sec.store(rate.packets_per_sec, Ordering::Relaxed);
+                bytes_per_sec.store(rate.bytes_per_sec, Ordering::Relaxed);
+                if let Err(err) = window.emit("capture_rate", rate) {
+                    warn!("Error emitting capture rate event: {}", err);
+                }
+
+                if *paused.lock().unwrap() || peak_packets_per_sec < BUSY_PACKETS_PER_SEC {
+                    continue;
+                }
+                let Some(age) = last_packet_time.lock().unwrap().map(|t| t.elapsed()) else {
+                    continue;
+                };
+                // Report a stall once, and again only after packets resume
+                if age < STALL_AFTER {
+                    stalled = false;
+                } else if !stalled {
+                    stalled = true;
+                    warn!("No packets from {} for {:?}", device, age);
+                    let event = CaptureStalled {
+                        device: device.clone(),
+                        last_packet_age_ms: age.as_millis() as u64,
+                    };
+                    if let Err(err) = window.emit("capture_stalled", event) {
+                        warn!("Error emitting capture stalled event: {}", err);
+                    }
+                }
+            }
+        });
+    }
+
     thread::spawn(move || {
-        let mut cap = match Capture::from_device(device_name.as_str())
-            .unwrap()
-            .immediate_mode(true)
-            .open()
-        {
-            Ok(cap) => cap,
+        let _lease = lease;
+        let (mut cap, precision) = match open_capture_with_retry(&device_name) {
+            Ok(opened) => opened,
             Err(e) => {
-                error!("Error opening device: {:?}", e);
+                error!("Error opening device: {}", e);
+                *running.lock().unwrap() = false;
+                clock.lock().unwrap().freeze();
+                if let Err(err) = window.emit("capture_error", &e) {
+                    warn!("Error emitting capture error event: {}", err);
+                }
                 return;
             }
         };
 
         info!("Packet capture started successfully");
+        let mut reassembler = Ipv4Reassembler::new();
+        let linktype = cap.get_datalink();
+        debug!("Capturing with datalink {:?}", linktype);
+        *capture_linktype.lock().unwrap() = Some(linktype);
+        *capture_precision.lock().unwrap() = Some(precision);
+        let mut packet_count: u64 = 0;
+        let mut savefile: Option<ActiveSave> = None;
+        let started = Instant::now();
 
         while *running.lock().unwrap() {
+            if started.elapsed() >= max_duration {
+                warn!(
+                    "Capture on {} reached its {:?} safety limit without being stopped, stopping",
+                    device_name, max_duration
+                );
+                *running.lock().unwrap() = false;
+                let complete = CaptureComplete {
+                    device: device_name.clone(),
+                    packets: packet_count,
+                };
+                if let Err(err) = window.emit("capture_complete", complete) {
+                    warn!("Error emitting capture complete event: {}", err);
+                }
+                break;
+            }
+
+            let requested_filter = pending_filter.lock().unwrap().take();
+            if let Some(requested) = requested_filter {
+                match apply_filter(&mut cap, &device_name, &requested) {
+                    Ok(()) => {
+                        info!("Capture filter set to '{}'", requested);
+                        *filter.lock().unwrap() = Some(requested);
+                    }
+                    Err(e) => {
+                        error!("Error changing capture filter: {}", e);
+                        *running.lock().unwrap() = false;
+                        if let Err(err) = window.emit("capture_error", &e) {
+                            warn!("Error emitting capture error event: {}", err);
+                        }
+                        break;
+                    }
+                }
+            }
+
+            let requested_save = pending_save.lock().unwrap().take();
+            match requested_save {
+                Some(SaveRequest::Start(path, compress)) => match cap.savefile(&path) {
+                    Ok(file) => {
+                        info!("Saving captured packets to {}", path.display());
+                        if let Some(previous) = savefile.take() {
+                            previous.finish();
+                        }
+                        savefile = Some(ActiveSave::new(file, path, compress));
+                    }
+                    Err(e) => {
+                        let e = WifiError::CaptureError(format!(
+                            "Failed to create {}: {}",
+                            path.display(),
+                            e
+                        ));
+                        error!("{}", e);
+                        if let Err(err) = window.emit("capture_save_failed", &e) {
+                            warn!("Error emitting save failure event: {}", err);
+                        }
+                    }
+                },
+                Some(SaveRequest::Stop) => {
+                    if let Some(save) = savefile.take() {
+                        save.finish();
+                    }
+                }
+                None => {}
+            }
+
             let cap = &mut cap;
+            if *paused.lock().unwrap() {
+                // Keep the handle open but drop what arrives, so resuming
+                // doesn't replay a backlog of stale packets
+                let _ = cap.next_packet();
+                continue;
+            }
+
             match cap.next_packet() {
                 Ok(packet) => {
-                    if let Ok(packet_info) = parse_packet(&packet) {
-                        let cloned_packet_info = packet_info.clone();
-                        let mut packets = captured_packets.lock().unwrap();
-                        packets.push(cloned_packet_info);
-
-                        // Emit the event
-                        if let Err(err) = window.emit("packet", packet_info) {
-                            warn!("Error emitting packet event: {}", err);
+                    if let Some(save) = savefile.as_mut() {
+                        save.write(&packet);
+                    }
+
+                    let mut packet_info = parse_captured_packet(
+                        &packet,
+                        linktype,
+                        precision,
+                        &mut reassembler,
+                        *parse_depth.lock().unwrap(),
+                    );
+                    if let Some(reason) = packet_info.malformed {
+                        parse_failures.lock().unwrap().record(reason);
+                    }
+                    *last_packet_time.lock().unwrap() = Some(Instant::now());
+
+                    if *exclude_local.lock().unwrap()
+                        && local_addresses.lock().unwrap().is_local(&packet_info)
+                    {
+                        // Count our own traffic but keep it out of stats and the list
+                        local_packets.fetch_add(1, Ordering::Relaxed);
+                        local_bytes.fetch_add(packet_info.length as u64, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    rate_packets.fetch_add(1, Ordering::Relaxed);
+                    rate_bytes.fetch_add(packet.data.len() as u64, Ordering::Relaxed);
+                    total_packets.fetch_add(1, Ordering::Relaxed);
+                    total_bytes.fetch_add(packet.data.len() as u64, Ordering::Relaxed);
+
+                    if let Some(message) = packet_info
+                        .eapol
+                        .as_ref()
+                        .and_then(|key| key.handshake_message)
+                    {
+                        handshakes.lock().unwrap().observe(
+                            &packet_info.src_mac,
+                            &packet_info.dst_mac,
+                            message,
+                            packet_info.timestamp,
+                        );
+                    }
+
+                    reverse_dns.annotate(&mut packet_info);
+                    let payload_limit = *payload_capture_bytes.lock().unwrap();
+                    packet_info.truncate_payloads(payload_limit);
+                    if *capture_raw.lock().unwrap() && payload_limit > 0 {
+                        let kept = &packet.data[..packet.data.len().min(payload_limit)];
+                        packet_info.raw_hex = Some(hex::encode(kept));
+                    }
+
+                    let new_device = known_devices.lock().unwrap().observe_packet(&packet_info);
+                    if let Some(device) = new_device {
+                        if let Err(err) = window.emit("new_device", device) {
+                            warn!("Error emitting new device event: {}", err);
+                        }
+                    }
+
+                    for conflict in bindings.lock().unwrap().observe(&packet_info) {
+                        if let Err(err) = window.emit("arp_spoof_alert", conflict) {
+                            warn!("Error emitting ARP spoof alert: {}", err);
+                        }
+                    }
+
+                    sessions.lock().unwrap().record_packet(&mut packet_info);
+                    endpoints.lock().unwrap().observe(&packet_info);
+                    let ts = packet.header.ts;
+                    let arrival_us = ts.tv_sec as u64 * 1_000_000 + ts.tv_usec as u64;
+                    flows.lock().unwrap().observe(&packet_info, arrival_us);
+
+                    if let Some(forwarder) = forwarder.lock().unwrap().as_ref() {
+                        forwarder.send(&packet_info);
+                    }
+
+                    let cloned_packet_info = packet_info.clone();
+                    let mut packets = captured_packets.lock().unwrap();
+                    packets.push(cloned_packet_info);
+
+                    // Emit the event
+                    if let Err(err) = window.emit("packet", packet_info) {
+                        warn!("Error emitting packet event: {}", err);
+                    }
+
+                    packet_count += 1;
+                    if packet_limit.is_some_and(|limit| packet_count >= limit) {
+                        info!("Packet limit of {} reached, stopping capture", packet_count);
+                        *running.lock().unwrap() = false;
+
+                        let complete = CaptureComplete {
+                            device: device_name.clone(),
+                            packets: packet_count,
+                        };
+                        if let Err(err) = window.emit("capture_complete", complete) {
+                            warn!("Error emitting capture complete event: {}", err);
                         }
                     }
                 }
+                // The read timeout only wakes the loop to check for a stop
+                Err(pcap::Error::TimeoutExpired) => {}
                 Err(e) => error!("Error receiving packet: {:?}", e),
             }
         }
+
+        if let Some(save) = savefile.take() {
+            save.finish();
+        }
+        DeviceInventory::flush(&known_devices);
+        // Stops the clock when the capture ends on its own, at a packet limit
+        clock.lock().unwrap().freeze();
     });
 
     Ok(())
 }
 
+/// Validates `filter` against the running capture and hands it to the
+/// capture thread to apply.
+fn request_capture_filter(filter: String, state: &PacketCapture) -> Result<(), WifiError> {
+    if !*state.running.lock().unwrap() {
+        return Err(WifiError::CaptureError("No capture is running".to_string()));
+    }
+    let linktype = state
+        .linktype
+        .lock()
+        .unwrap()
+        .ok_or_else(|| WifiError::CaptureError("Capture is still starting".to_string()))?;
+
+    validate_filter(&filter, linktype)?;
+    info!("Requesting capture filter change to '{}'", filter);
+    *state.pending_filter.lock().unwrap() = Some(filter);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_capture_filter(filter: String, state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    request_capture_filter(filter, &state)
+}
+
+#[tauri::command]
+fn set_protocol_filters(
+    protocols: Vec<String>,
+    state: tauri::State<PacketCapture>,
+) -> Result<String, WifiError> {
+    let filter = protocol_filter(&protocols)?;
+    request_capture_filter(filter.clone(), &state)?;
+    Ok(filter)
+}
+
+#[tauri::command]
+fn get_capture_filter(state: tauri::State<PacketCapture>) -> Result<Option<String>, WifiError> {
+    Ok(state.filter.lock().unwrap().clone())
+}
+
+#[tauri::command]
+fn start_session(
+    name: String,
+    notes: String,
+    location: Option<String>,
+    state: tauri::State<PacketCapture>,
+) -> Result<SessionMeta, WifiError> {
+    state
+        .sessions
+        .lock()
+        .unwrap()
+        .start(&name, &notes, location)
+}
+
+#[tauri::command]
+fn end_session(state: tauri::State<PacketCapture>) -> Result<SessionMeta, WifiError> {
+    state.sessions.lock().unwrap().end()
+}
+
+#[tauri::command]
+fn list_sessions(state: tauri::State<PacketCapture>) -> Result<Vec<SessionMeta>, WifiError> {
+    Ok(state.sessions.lock().unwrap().list())
+}
+
+#[tauri::command]
+fn get_session(id: String, state: tauri::State<PacketCapture>) -> Result<Session, WifiError> {
+    state.sessions.lock().unwrap().get(&id)
+}
+
+#[tauri::command]
+fn get_external_endpoints(
+    state: tauri::State<PacketCapture>,
+) -> Result<Vec<ExternalEndpoint>, WifiError> {
+    Ok(state.endpoints.lock().unwrap().endpoints())
+}
+
+/// Mean inter-arrival time and jitter of one direction of a flow, keyed as
+/// `flows::flow_key` formats it.
+#[tauri::command]
+fn get_flow_jitter(
+    flow_key: String,
+    state: tauri::State<PacketCapture>,
+) -> Result<FlowJitter, WifiError> {
+    state.flows.lock().unwrap().jitter(&flow_key)
+}
+
+#[tauri::command]
+fn get_conversations(state: tauri::State<PacketCapture>) -> Result<Vec<Conversation>, WifiError> {
+    Ok(state.flows.lock().unwrap().conversations())
+}
+
+#[tauri::command]
+fn get_known_devices(state: tauri::State<PacketCapture>) -> Result<Vec<KnownDevice>, WifiError> {
+    Ok(state.known_devices.lock().unwrap().devices())
+}
+
+/// IP/MAC binding conflicts seen during the current capture, oldest first.
+#[tauri::command]
+fn get_binding_conflicts(
+    state: tauri::State<PacketCapture>,
+) -> Result<Vec<BindingConflict>, WifiError> {
+    Ok(state.bindings.lock().unwrap().conflicts())
+}
+
+#[tauri::command]
+fn set_device_trusted(
+    mac: String,
+    trusted: bool,
+    state: tauri::State<PacketCapture>,
+) -> Result<(), WifiError> {
+    info!("Marking LAN device {} trusted: {}", mac, trusted);
+    state
+        .known_devices
+        .lock()
+        .unwrap()
+        .set_trusted(&mac, trusted)?;
+    DeviceInventory::flush(&state.known_devices);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_exclude_local(exclude: bool, state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    info!("Excluding local traffic: {}", exclude);
+    *state.exclude_local.lock().unwrap() = exclude;
+    Ok(())
+}
+
+/// Timestamp precision of the packets from the latest capture.
+#[tauri::command]
+fn get_timestamp_precision(
+    state: tauri::State<PacketCapture>,
+) -> Result<TimestampPrecision, WifiError> {
+    state
+        .precision
+        .lock()
+        .unwrap()
+        .map(TimestampPrecision::from)
+        .ok_or_else(|| WifiError::CaptureError("No capture has opened a device yet".to_string()))
+}
+
+#[tauri::command]
+fn set_capture_raw(enabled: bool, state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    info!("Including raw frame bytes: {}", enabled);
+    *state.capture_raw.lock().unwrap() = enabled;
+    Ok(())
+}
+
+/// Caps the payload text and raw bytes kept per captured packet. 0 keeps
+/// none, for a stats-only capture.
+#[tauri::command]
+fn set_payload_capture_bytes(
+    bytes: usize,
+    state: tauri::State<PacketCapture>,
+) -> Result<(), WifiError> {
+    info!("Keeping up to {} payload bytes per packet", bytes);
+    *state.payload_capture_bytes.lock().unwrap() = bytes;
+    Ok(())
+}
+
+/// Turns reverse DNS of captured public addresses on or off. `server` and
+/// `cache_size` keep their current values unless given.
+#[tauri::command]
+fn set_reverse_dns(
+    enabled: bool,
+    server: Option<String>,
+    cache_size: Option<usize>,
+    state: tauri::State<PacketCapture>,
+) -> Result<ResolverConfig, WifiError> {
+    let mut config = state.reverse_dns.config();
+    config.enabled = enabled;
+    if let Some(server) = server {
+        config.server = reverse_dns::parse_server(&server)?;
+    }
+    if let Some(cache_size) = cache_size {
+        config.cache_size = cache_size;
+    }
+    state.reverse_dns.configure(config.clone())?;
+    info!(
+        "Reverse DNS {} (server {}, {} cached addresses)",
+        if enabled { "enabled" } else { "disabled" },
+        config.server,
+        config.cache_size
+    );
+    Ok(config)
+}
+
+/// Limits how far up the stack captured packets are decoded, trading
+/// detail for throughput on busy links.
+#[tauri::command]
+fn set_parse_depth(depth: ParseDepth, state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    info!("Parsing captured packets to depth {:?}", depth);
+    *state.parse_depth.lock().unwrap() = depth;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_local_traffic(state: tauri::State<PacketCapture>) -> Result<LocalTraffic, WifiError> {
+    Ok(LocalTraffic {
+        addresses: state.local_addresses.lock().unwrap().clone(),
+        excluded: *state.exclude_local.lock().unwrap(),
+        packets: state.local_packets.load(Ordering::Relaxed),
+        bytes: state.local_bytes.load(Ordering::Relaxed),
+    })
+}
+
+#[tauri::command]
+fn get_parse_failures(state: tauri::State<PacketCapture>) -> Result<ParseFailures, WifiError> {
+    Ok(state.parse_failures.lock().unwrap().clone())
+}
+
+#[tauri::command]
+fn set_capture_output(
+    directory: String,
+    template: Option<String>,
+    state: tauri::State<PacketCapture>,
+) -> Result<CaptureOutput, WifiError> {
+    let template = template.unwrap_or_else(|| capture_output::DEFAULT_TEMPLATE.to_string());
+    let output = CaptureOutput::new(directory.into(), template)?;
+    info!(
+        "Saving captures to {} as '{}'",
+        output.directory.display(),
+        output.template
+    );
+    *state.output.lock().unwrap() = output.clone();
+    Ok(output)
+}
+
+#[tauri::command]
+fn get_capture_output(state: tauri::State<PacketCapture>) -> Result<CaptureOutput, WifiError> {
+    Ok(state.output.lock().unwrap().clone())
+}
+
+/// Starts recording the running capture's raw packets to a pcap file named
+/// by the capture output settings. Returns the file's path, which gains a
+/// ".gz" once recording stops when `compress` is set.
+#[tauri::command]
+fn start_saving_capture(
+    compress: bool,
+    state: tauri::State<PacketCapture>,
+) -> Result<String, WifiError> {
+    if !*state.running.lock().unwrap() {
+        return Err(WifiError::CaptureError("No capture is running".to_string()));
+    }
+    let device = state.device.lock().unwrap().clone().unwrap_or_default();
+    let path = state.output.lock().unwrap().next_path(&device)?;
+
+    let resolved = if compress {
+        export::gzip_path(&path)
+    } else {
+        path.clone()
+    };
+    *state.pending_save.lock().unwrap() = Some(SaveRequest::Start(path, compress));
+    Ok(resolved.display().to_string())
+}
+
+#[tauri::command]
+fn stop_saving_capture(state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    *state.pending_save.lock().unwrap() = Some(SaveRequest::Stop);
+    Ok(())
+}
+
+#[tauri::command]
+fn start_forwarding(
+    host: String,
+    port: u16,
+    state: tauri::State<PacketCapture>,
+) -> Result<(), WifiError> {
+    if host.trim().is_empty() {
+        return Err(WifiError::CaptureError(
+            "Collector host is empty".to_string(),
+        ));
+    }
+
+    info!("Starting packet forwarding to {}:{}", host, port);
+    let previous = state
+        .forwarder
+        .lock()
+        .unwrap()
+        .replace(Forwarder::start(host, port));
+    if let Some(previous) = previous {
+        previous.stop();
+    }
+    Ok(())
+}
+
+/// Stops forwarding and returns how many packets the collector missed
+/// because the queue was full.
+#[tauri::command]
+fn stop_forwarding(state: tauri::State<PacketCapture>) -> Result<u64, WifiError> {
+    match state.forwarder.lock().unwrap().take() {
+        Some(forwarder) => Ok(forwarder.stop()),
+        None => Err(WifiError::CaptureError("Not forwarding".to_string())),
+    }
+}
+
+#[tauri::command]
+fn get_handshakes(state: tauri::State<PacketCapture>) -> Result<Vec<HandshakeStatus>, WifiError> {
+    Ok(state.handshakes.lock().unwrap().handshakes())
+}
+
+#[tauri::command]
+async fn capture_n_packets(
+    device: String,
+    count: usize,
+    filter: Option<String>,
+    devices: tauri::State<'_, DeviceRegistry>,
+    capture: tauri::State<'_, PacketCapture>,
+) -> Result<Vec<PacketInfo>, WifiError> {
+    info!("Capturing {} packets on device: {}", count, device);
+    let _lease = devices.acquire(&device, DeviceUser::Capture)?;
+    let timeout = std::time::Duration::from_secs(30);
+    let depth = *capture.parse_depth.lock().unwrap();
+    let payload_limit = *capture.payload_capture_bytes.lock().unwrap();
+    let cap = {
+        let device = device.clone();
+        run_blocking(move || open_stream_capture(&device, filter.as_deref())).await?
+    };
+    capture_packets(&device, cap, count, timeout, depth, payload_limit).await
+}
+
+/// Runs blocking work, like opening a device or reading a file, on the
+/// blocking thread pool so it doesn't stall the async runtime.
+async fn run_blocking<T, F>(work: F) -> Result<T, WifiError>
+where
+    F: FnOnce() -> Result<T, WifiError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(work)
+        .await
+        .map_err(|e| WifiError::CaptureError(format!("Background task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn export_ndjson(
+    path: String,
+    compress: bool,
+    state: tauri::State<'_, PacketCapture>,
+) -> Result<usize, WifiError> {
+    // Snapshot the buffer so the capture thread isn't blocked on disk I/O
+    let packets = state.captured_packets.lock().unwrap().clone();
+    run_blocking(move || {
+        let written = export::export_ndjson(&packets, std::path::Path::new(&path), compress)?;
+        info!("Exported {} packets to {}", written, path);
+        Ok(written)
+    })
+    .await
+}
+
+/// JSON Schemas of event and command payloads, for checking the frontend's
+/// hand-written types against the backend.
+#[tauri::command]
+fn get_event_schemas() -> Result<PayloadSchemas, WifiError> {
+    Ok(schemas::payload_schemas())
+}
+
+#[tauri::command]
+async fn benchmark_parsing(pcap_path: String) -> Result<ParseBenchmark, WifiError> {
+    info!("Benchmarking parser on {}", pcap_path);
+    run_blocking(move || benchmark::benchmark_file(&pcap_path)).await
+}
+
+/// Loads a capture file for replay, paused at its first packet. Playing it
+/// emits "packet" events paced like the original capture and a
+/// "replay_finished" event after the last one.
+#[tauri::command]
+async fn load_replay(
+    path: String,
+    window: tauri::Window,
+    replay: tauri::State<'_, Replay>,
+) -> Result<ReplayInfo, WifiError> {
+    let file = run_blocking(move || ReplayFile::read(&path)).await?;
+    Ok(replay.load(file, replay_emitter(window)))
+}
+
+/// Loads a capture file and starts playing it at `speed` times the original
+/// pace right away. `stop_replay` cancels it.
+#[tauri::command]
+async fn replay_capture(
+    path: String,
+    speed: f64,
+    window: tauri::Window,
+    replay: tauri::State<'_, Replay>,
+) -> Result<ReplayInfo, WifiError> {
+    replay::check_speed(speed)?;
+    let file = run_blocking(move || ReplayFile::read(&path)).await?;
+    let info = replay.load(file, replay_emitter(window));
+    replay.set_speed(speed)?;
+    replay.play()?;
+    Ok(info)
+}
+
+fn replay_emitter(window: tauri::Window) -> impl FnMut(ReplayEvent) + Send + 'static {
+    move |event| {
+        let emitted = match event {
+            ReplayEvent::Packet(packet) => window.emit("packet", packet),
+            ReplayEvent::Finished => window.emit("replay_finished", ()),
+        };
+        if let Err(e) = emitted {
+            warn!("Failed to emit replay event: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+fn play_replay(replay: tauri::State<'_, Replay>) -> Result<(), WifiError> {
+    replay.play()
+}
+
+#[tauri::command]
+fn pause_replay(replay: tauri::State<'_, Replay>) -> Result<(), WifiError> {
+    replay.pause()
+}
+
+/// Sets the playback speed, from 0.5x to 10x the original.
+#[tauri::command]
+fn set_replay_speed(speed: f64, replay: tauri::State<'_, Replay>) -> Result<(), WifiError> {
+    replay.set_speed(speed)
+}
+
+/// Jumps to the first packet captured at or after `timestamp_ms`, in
+/// milliseconds since the epoch. Playback continues from there if it was
+/// playing.
+#[tauri::command]
+fn seek_replay(timestamp_ms: u64, replay: tauri::State<'_, Replay>) -> Result<(), WifiError> {
+    replay.seek(timestamp_ms)
+}
+
+#[tauri::command]
+fn get_replay_status(replay: tauri::State<'_, Replay>) -> Result<ReplayStatus, WifiError> {
+    replay.status()
+}
+
+#[tauri::command]
+fn stop_replay(replay: tauri::State<'_, Replay>) -> Result<(), WifiError> {
+    if replay.stop() {
+        Ok(())
+    } else {
+        Err(WifiError::CaptureError(
+            "No capture is loaded for replay".to_string(),
+        ))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "link_type", content = "packet", rename_all = "snake_case")]
+enum ParsedHexPacket {
+    Ethernet(Box<PacketInfo>),
+    Loopback(Box<PacketInfo>),
+    Radiotap(Box<WiFiFrame>),
+}
+
+#[tauri::command]
+fn parse_hex_packet(hex: String, link_type: String) -> Result<ParsedHexPacket, WifiError> {
+    let data = hex::decode(&hex)?;
+    debug!("Parsing {} byte {} packet from hex", data.len(), link_type);
+
+    match link_type.to_lowercase().as_str() {
+        "ethernet" => parse_packet_bytes(&data)
+            .map(Box::new)
+            .map(ParsedHexPacket::Ethernet),
+        "loopback" => parse_loopback_layers(&data, None, ParseDepth::Application)
+            .map(|packet| Box::new(packet.into()))
+            .map(ParsedHexPacket::Loopback),
+        "radiotap" => RadiotapParser::new(&data)
+            .parse_wifi_frame()
+            .map(Box::new)
+            .map(ParsedHexPacket::Radiotap)
+            .map_err(WifiError::from),
+        other => Err(WifiError::ParseError(format!(
+            "Unsupported link type '{}', expected 'ethernet', 'loopback' or 'radiotap'",
+            other
+        ))),
+    }
+}
+
+/// Guesses whether `data` starts with a radiotap header: version 0 and a
+/// header length that fits the buffer.
+fn looks_like_radiotap(data: &[u8]) -> bool {
+    if data.len() < 8 || data[0] != 0 {
+        return false;
+    }
+    let length = u16::from_le_bytes([data[2], data[3]]) as usize;
+    length >= 8 && length < data.len()
+}
+
+#[tauri::command]
+fn decode_frame(hex: String) -> Result<ParsedHexPacket, WifiError> {
+    let data = hex::decode(&hex)?;
+
+    if looks_like_radiotap(&data) {
+        debug!("Decoding {} byte frame as radiotap", data.len());
+        if let Ok(frame) = RadiotapParser::new(&data).parse_wifi_frame() {
+            return Ok(ParsedHexPacket::Radiotap(Box::new(frame)));
+        }
+    }
+
+    debug!("Decoding {} byte frame as Ethernet", data.len());
+    parse_packet_bytes(&data)
+        .map(Box::new)
+        .map(ParsedHexPacket::Ethernet)
+        .map_err(|e| {
+            WifiError::ParseError(format!("Frame is neither radiotap nor Ethernet: {}", e))
+        })
+}
+
+#[tauri::command]
+fn pause_packet_capture(state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    if !*state.running.lock().unwrap() {
+        return Err(WifiError::CaptureError("No capture is running".to_string()));
+    }
+    info!("Pausing packet capture");
+    *state.paused.lock().unwrap() = true;
+    state.clock.lock().unwrap().freeze();
+    state.packets_per_sec.store(0, Ordering::Relaxed);
+    state.bytes_per_sec.store(0, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+fn resume_packet_capture(state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
+    if !*state.running.lock().unwrap() {
+        return Err(WifiError::CaptureError("No capture is running".to_string()));
+    }
+    info!("Resuming packet capture");
+    // Packets drained while paused don't count, so restart the stall clock
+    *state.last_packet_time.lock().unwrap() = Some(Instant::now());
+    *state.paused.lock().unwrap() = false;
+    state.clock.lock().unwrap().resume();
+    Ok(())
+}
+
+/// Elapsed capture time and packet/byte totals of the current or last
+/// capture. Totals stay as they were when the capture stops.
+#[tauri::command]
+fn get_capture_summary(state: tauri::State<PacketCapture>) -> Result<CaptureSummary, WifiError> {
+    Ok(CaptureSummary {
+        device: state.device.lock().unwrap().clone(),
+        running: *state.running.lock().unwrap(),
+        paused: *state.paused.lock().unwrap(),
+        elapsed_secs: state.clock.lock().unwrap().elapsed().as_secs_f64(),
+        packets: state.total_packets.load(Ordering::Relaxed),
+        bytes: state.total_bytes.load(Ordering::Relaxed),
+        packets_per_sec: state.packets_per_sec.load(Ordering::Relaxed),
+        bytes_per_sec: state.bytes_per_sec.load(Ordering::Relaxed),
+    })
+}
+
+/// Milliseconds since the running capture last parsed a packet, or None
+/// before the first packet.
 #[tauri::command]
-fn stop_packet_capture(state: tauri::State<PacketCapture>) -> Result<(), String> {
+fn get_last_packet_age(state: tauri::State<PacketCapture>) -> Result<Option<u64>, WifiError> {
+    if !*state.running.lock().unwrap() {
+        return Err(WifiError::CaptureError("No capture is running".to_string()));
+    }
+    let age = *state.last_packet_time.lock().unwrap();
+    Ok(age.map(|t| t.elapsed().as_millis() as u64))
+}
+
+#[tauri::command]
+fn stop_packet_capture(state: tauri::State<PacketCapture>) -> Result<(), WifiError> {
     info!("Stopping packet capture");
     *state.running.lock().unwrap() = false;
+    *state.paused.lock().unwrap() = false;
     *state.device.lock().unwrap() = None;
+    state.rate_packets.store(0, Ordering::Relaxed);
+    state.rate_bytes.store(0, Ordering::Relaxed);
+    state.packets_per_sec.store(0, Ordering::Relaxed);
+    state.bytes_per_sec.store(0, Ordering::Relaxed);
+    state.clock.lock().unwrap().freeze();
     info!("Packet capture stopped");
     Ok(())
 }
@@ -219,14 +2099,264 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .manage(PacketCapture::new())
+        .manage(DeviceRegistry::new())
+        .manage(SignalMeter::new())
+        .manage(Replay::new())
+        .manage(Mutex::new(OccupancyHistory::default()))
+        .manage(Mutex::new(CongestionThresholds::default()))
+        .manage(Mutex::new(ScannerConfig::default()))
+        .manage(Mutex::new(ProbingClients::default()))
+        .manage(Mutex::new(Vec::<ControlFrameStats>::new()))
+        .manage(Mutex::new(NetworkLabels::default()))
+        .setup(|app| {
+            let path = app.path().app_config_dir()?.join("known_devices.json");
+            let labels_path = app.path().app_config_dir()?.join("network_labels.json");
+            *app.state::<Mutex<NetworkLabels>>().lock().unwrap() = NetworkLabels::load(labels_path);
+            let sessions_dir = app.path().app_data_dir()?.join("sessions");
+            let capture = app.state::<PacketCapture>();
+            *capture.known_devices.lock().unwrap() = DeviceInventory::load(path);
+            *capture.sessions.lock().unwrap() = SessionManager::new(sessions_dir);
+            capture.output.lock().unwrap().directory = app.path().app_data_dir()?.join("captures");
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_wifi,
+            compare_interfaces,
+            set_network_label,
+            get_network_labels,
+            monitor_bssid_signal,
+            stop_bssid_monitor,
             list_devices,
+            get_current_connection,
             start_packet_capture,
+            get_last_packet_age,
+            get_capture_summary,
             stop_packet_capture,
+            pause_packet_capture,
+            resume_packet_capture,
             get_channel_data,
+            get_occupancy_timeline,
+            set_congestion_thresholds,
+            recommend_channel,
+            evaluate_channel_choice,
             get_latest_packets,
+            capture_n_packets,
+            get_handshakes,
+            set_exclude_local,
+            set_capture_raw,
+            set_payload_capture_bytes,
+            set_parse_depth,
+            set_reverse_dns,
+            get_timestamp_precision,
+            get_local_traffic,
+            get_parse_failures,
+            set_capture_output,
+            get_capture_output,
+            start_saving_capture,
+            stop_saving_capture,
+            start_forwarding,
+            stop_forwarding,
+            get_band_summary,
+            audit_network,
+            interference_matrix,
+            detect_beacon_anomalies,
+            check_regulatory_compliance,
+            security_summary,
+            flag_default_networks,
+            group_physical_aps,
+            set_scan_snaplen,
+            parse_hex_packet,
+            set_network_retention,
+            decode_frame,
+            set_beacon_throttle,
+            set_active_probing,
+            set_probe_request_collection,
+            set_eap_identity_redaction,
+            set_scan_subtypes,
+            set_frame_class_filter,
+            get_control_frame_stats,
+            get_probing_clients,
+            clear_probing_clients,
+            get_interface_capabilities,
+            set_scan_bands,
+            set_adaptive_scan,
+            set_capture_filter,
+            set_protocol_filters,
+            get_capture_filter,
+            get_external_endpoints,
+            get_flow_jitter,
+            get_conversations,
+            get_known_devices,
+            get_binding_conflicts,
+            set_device_trusted,
+            start_session,
+            end_session,
+            list_sessions,
+            get_session,
+            get_event_schemas,
+            benchmark_parsing,
+            load_replay,
+            replay_capture,
+            play_replay,
+            pause_replay,
+            set_replay_speed,
+            seek_replay,
+            get_replay_status,
+            stop_replay,
+            export_ndjson,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiotap::SsidState;
+
+    fn network(bssid: &str, frequency: u32, signal_quality: u32, beacon_count: u32) -> WiFiNetwork {
+        let mut network = WiFiNetwork::new(
+            bssid.to_string(),
+            String::new(),
+            SsidState::Absent,
+            frequency,
+            None,
+        );
+        network.signal_quality = signal_quality;
+        network.beacon_count = beacon_count;
+        network
+    }
+
+    fn channel(data: &[ChannelData], channel: u32) -> &ChannelData {
+        data.iter().find(|c| c.channel == channel).unwrap()
+    }
+
+    #[test]
+    fn equal_beacon_counts_weight_channels_equally() {
+        let networks = [
+            network("00:00:00:00:00:01", 2412, 80, 100),
+            network("00:00:00:00:00:02", 2437, 80, 100),
+        ];
+        let data = channel_occupancy(&networks, Band::TwoPointFour, &Default::default());
+
+        let (ch1, ch6) = (channel(&data, 1), channel(&data, 6));
+        assert_eq!(ch1.occupancy, ch6.occupancy);
+        assert_eq!(ch1.beacon_weighted_occupancy, ch6.beacon_weighted_occupancy);
+        assert_eq!(ch1.beacon_weighted_occupancy, ch1.occupancy);
+    }
+
+    #[test]
+    fn chatty_aps_raise_beacon_weighted_occupancy() {
+        let networks = [
+            network("00:00:00:00:00:01", 2412, 80, 1000),
+            network("00:00:00:00:00:02", 2437, 80, 100),
+        ];
+        let data = channel_occupancy(&networks, Band::TwoPointFour, &Default::default());
+
+        let (ch1, ch6) = (channel(&data, 1), channel(&data, 6));
+        // One network each, so plain occupancy can't tell them apart
+        assert_eq!(ch1.occupancy, ch6.occupancy);
+        assert!(ch1.beacon_weighted_occupancy > ch6.beacon_weighted_occupancy);
+        assert!((ch1.beacon_weighted_occupancy - 0.8 * 1000.0 / 1100.0).abs() < 1e-6);
+        assert_eq!(channel(&data, 11).beacon_weighted_occupancy, 0.0);
+    }
+
+    #[test]
+    fn occupancy_is_a_share_of_the_bands_networks() {
+        let networks = [
+            network("00:00:00:00:00:01", 2412, 80, 100),
+            network("00:00:00:00:00:02", 5180, 80, 100),
+            network("00:00:00:00:00:03", 5200, 80, 100),
+            network("00:00:00:00:00:04", 5220, 80, 100),
+        ];
+        let data = channel_occupancy(&networks, Band::TwoPointFour, &Default::default());
+
+        // The only 2.4 GHz network, so the 5 GHz ones don't dilute it
+        let ch1 = channel(&data, 1);
+        assert!((ch1.occupancy - 0.8).abs() < 1e-6);
+        assert!((ch1.beacon_weighted_occupancy - 0.8).abs() < 1e-6);
+
+        let data = channel_occupancy(&networks, Band::Five, &Default::default());
+        assert!((channel(&data, 36).occupancy - 0.8 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn six_ghz_networks_stay_off_two_point_four_ghz_channels() {
+        let networks = [network("00:00:00:00:00:01", 5955, 80, 100)];
+
+        let data = channel_occupancy(&networks, Band::TwoPointFour, &Default::default());
+        assert_eq!(channel(&data, 1).occupancy, 0.0);
+
+        let data = channel_occupancy(&networks, Band::Six, &Default::default());
+        assert!((channel(&data, 1).occupancy - 0.8).abs() < 1e-6);
+    }
+
+    fn bonded(bssid: &str, frequency: u32, width: u32, center: u32) -> WiFiNetwork {
+        let mut network = network(bssid, frequency, 80, 100);
+        (network.channel_width_mhz, network.center_frequency) = (width, center);
+        network
+    }
+
+    #[test]
+    fn co_channel_networks_crowd_a_channel() {
+        let networks = [
+            network("00:00:00:00:00:01", 2437, 80, 100),
+            network("00:00:00:00:00:02", 2437, 80, 100),
+            // Channel 1 doesn't reach channel 6
+            network("00:00:00:00:00:03", 2412, 80, 100),
+        ];
+        let choice = evaluate_channel(&networks, Band::TwoPointFour, 6);
+
+        assert_eq!(choice.co_channel.networks, 2);
+        assert_eq!(choice.overlapping.networks, 0);
+        assert!(choice
+            .verdict
+            .starts_with("Poor: channel 6 is crowded by 2"));
+    }
+
+    #[test]
+    fn adjacent_channels_overlap_in_two_point_four_ghz() {
+        let networks = [
+            network("00:00:00:00:00:01", 2422, 80, 100),
+            network("00:00:00:00:00:02", 2442, 80, 100),
+        ];
+
+        let choice = evaluate_channel(&networks, Band::TwoPointFour, 5);
+        assert_eq!(choice.co_channel.networks, 0);
+        assert_eq!(choice.overlapping.networks, 2);
+        assert!(choice.verdict.contains("moving to 1, 6 or 11"));
+
+        // Already on the plan, so there's nowhere better to suggest
+        let choice = evaluate_channel(&networks, Band::TwoPointFour, 6);
+        assert_eq!(choice.overlapping.networks, 2);
+        assert!(choice
+            .verdict
+            .starts_with("Poor: strong networks on overlapping"));
+        assert!(!choice.verdict.contains("moving to"));
+    }
+
+    #[test]
+    fn weak_neighbors_leave_a_channel_good() {
+        let networks = [network("00:00:00:00:00:01", 2437, 20, 100)];
+        let choice = evaluate_channel(&networks, Band::TwoPointFour, 6);
+
+        assert_eq!(choice.co_channel.networks, 1);
+        assert!(choice.verdict.starts_with("Good: only weak neighbors"));
+    }
+
+    #[test]
+    fn bonded_networks_count_on_every_channel_they_span() {
+        // Primary channel 36, 80 MHz wide over 36-48
+        let networks = [bonded("00:00:00:00:00:01", 5180, 80, 5210)];
+
+        for channel in [36, 40, 44, 48] {
+            let choice = evaluate_channel(&networks, Band::Five, channel);
+            assert_eq!(choice.co_channel.networks, 1, "channel {}", channel);
+            assert_eq!(choice.overlapping.networks, 0, "channel {}", channel);
+        }
+        let choice = evaluate_channel(&networks, Band::Five, 52);
+        assert_eq!(choice.co_channel.networks, 0);
+        assert_eq!(choice.overlapping.networks, 0);
+        assert!(choice.verdict.starts_with("Good: no other networks"));
+    }
+}
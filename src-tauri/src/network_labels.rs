@@ -0,0 +1,191 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::error::WifiError;
+use crate::wifi_scanner::WiFiNetwork;
+
+const MAX_LABEL_LEN: usize = 128;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NetworkLabel {
+    pub bssid: String,
+    pub label: String,
+    /// CSS hex color, "#RGB" or "#RRGGBB"
+    pub color: Option<String>,
+}
+
+/// Notes surveyors attach to BSSIDs, persisted so they carry over between
+/// scans and restarts.
+#[derive(Default)]
+pub struct NetworkLabels {
+    labels: HashMap<String, NetworkLabel>,
+    path: Option<PathBuf>,
+}
+
+impl NetworkLabels {
+    /// Loads the labels from `path`, starting empty if it doesn't exist or
+    /// can't be read.
+    pub fn load(path: PathBuf) -> Self {
+        let labels = match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<Vec<NetworkLabel>>(&contents) {
+                Ok(labels) => labels.into_iter().map(|l| (l.bssid.clone(), l)).collect(),
+                Err(e) => {
+                    warn!("Ignoring unreadable network labels {:?}: {}", path, e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+
+        info!("Loaded {} network labels", labels.len());
+        NetworkLabels {
+            labels,
+            path: Some(path),
+        }
+    }
+
+    /// Labels `bssid`, replacing any previous label. An empty label removes it.
+    /// If the labels can't be saved the change is undone, so what's shown
+    /// matches what a restart will load.
+    pub fn set(
+        &mut self,
+        bssid: &str,
+        label: &str,
+        color: Option<String>,
+    ) -> Result<Option<NetworkLabel>, WifiError> {
+        let bssid = bssid.to_uppercase();
+        let label = label.trim();
+        if label.is_empty() {
+            let previous = self.labels.remove(&bssid);
+            self.save_or_restore(&bssid, previous)?;
+            return Ok(None);
+        }
+        if label.chars().count() > MAX_LABEL_LEN {
+            return Err(WifiError::ParseError(format!(
+                "Labels are limited to {} characters",
+                MAX_LABEL_LEN
+            )));
+        }
+        if let Some(color) = color.as_deref().filter(|c| !is_hex_color(c)) {
+            return Err(WifiError::ParseError(format!(
+                "Invalid label color {:?}, expected #RGB or #RRGGBB",
+                color
+            )));
+        }
+
+        let entry = NetworkLabel {
+            bssid: bssid.clone(),
+            label: label.to_string(),
+            color,
+        };
+        let previous = self.labels.insert(bssid.clone(), entry.clone());
+        self.save_or_restore(&bssid, previous)?;
+        Ok(Some(entry))
+    }
+
+    /// Fills in the stored label and color of each network.
+    pub fn apply(&self, networks: &mut [WiFiNetwork]) {
+        for network in networks {
+            let label = self.labels.get(&network.bssid);
+            network.label = label.map(|l| l.label.clone());
+            network.label_color = label.and_then(|l| l.color.clone());
+        }
+    }
+
+    pub fn labels(&self) -> Vec<NetworkLabel> {
+        self.labels.values().cloned().collect()
+    }
+
+    /// Saves the labels, putting back `previous` as the label of `bssid` if
+    /// that fails.
+    fn save_or_restore(
+        &mut self,
+        bssid: &str,
+        previous: Option<NetworkLabel>,
+    ) -> Result<(), WifiError> {
+        let result = self.save();
+        if result.is_err() {
+            match previous {
+                Some(previous) => self.labels.insert(bssid.to_string(), previous),
+                None => self.labels.remove(bssid),
+            };
+        }
+        result
+    }
+
+    fn save(&self) -> Result<(), WifiError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        let labels: Vec<&NetworkLabel> = self.labels.values().collect();
+        serde_json::to_string_pretty(&labels)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(path, json).map_err(|e| e.to_string())
+            })
+            .map_err(|e| {
+                warn!("Failed to save network labels to {:?}: {}", path, e);
+                WifiError::CaptureError(format!("Failed to save network labels: {}", e))
+            })
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BSSID: &str = "00:11:22:33:44:55";
+
+    #[test]
+    fn set_and_remove_a_label() {
+        let mut labels = NetworkLabels::default();
+        let label = labels
+            .set(&BSSID.to_lowercase(), " lobby ", Some("#0af".to_string()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(label.bssid, BSSID);
+        assert_eq!(label.label, "lobby");
+
+        assert!(labels.set(BSSID, "", None).unwrap().is_none());
+        assert!(labels.labels().is_empty());
+    }
+
+    #[test]
+    fn failed_save_is_reported_and_undone() {
+        // A file where the labels' directory should be
+        let blocker =
+            std::env::temp_dir().join(format!("wifi-analyzer-labels-{}", std::process::id()));
+        std::fs::write(&blocker, b"").unwrap();
+        let mut labels = NetworkLabels {
+            labels: HashMap::new(),
+            path: Some(blocker.join("labels.json")),
+        };
+
+        let result = labels.set(BSSID, "lobby", None);
+        std::fs::remove_file(&blocker).unwrap();
+
+        assert!(matches!(result, Err(WifiError::CaptureError(_))));
+        assert!(labels.labels().is_empty());
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        let mut labels = NetworkLabels::default();
+        for color in ["0af", "#0afx", "#12345", "#ggg"] {
+            assert!(labels.set(BSSID, "lobby", Some(color.to_string())).is_err());
+        }
+        assert!(labels.labels().is_empty());
+    }
+}
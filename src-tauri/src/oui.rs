@@ -0,0 +1,39 @@
+/// A small built-in table of IEEE OUI assignments for devices commonly seen
+/// on home and lab networks. Not exhaustive; unknown prefixes return None.
+const OUI_VENDORS: &[([u8; 3], &str)] = &[
+    ([0x00, 0x03, 0x93], "Apple"),
+    ([0x00, 0x05, 0x69], "VMware"),
+    ([0x00, 0x0C, 0x29], "VMware"),
+    ([0x00, 0x15, 0x5D], "Microsoft"),
+    ([0x00, 0x17, 0x88], "Philips Lighting"),
+    ([0x00, 0x1A, 0x11], "Google"),
+    ([0x00, 0x50, 0x56], "VMware"),
+    ([0x08, 0x00, 0x27], "Oracle VirtualBox"),
+    ([0x18, 0xB4, 0x30], "Nest Labs"),
+    ([0xB8, 0x27, 0xEB], "Raspberry Pi Foundation"),
+    ([0xDC, 0xA6, 0x32], "Raspberry Pi Trading"),
+    ([0xE4, 0x5F, 0x01], "Raspberry Pi Trading"),
+];
+
+fn parse_prefix(mac: &str) -> Option<[u8; 3]> {
+    let mut prefix = [0u8; 3];
+    let mut octets = mac.split([':', '-']);
+    for byte in prefix.iter_mut() {
+        *byte = u8::from_str_radix(octets.next()?, 16).ok()?;
+    }
+    Some(prefix)
+}
+
+/// Looks up the vendor for a colon- or dash-separated MAC address.
+/// Locally administered (usually randomized) addresses are reported as such.
+pub fn lookup(mac: &str) -> Option<&'static str> {
+    let prefix = parse_prefix(mac)?;
+    if prefix[0] & 0x02 != 0 {
+        return Some("Randomized (locally administered)");
+    }
+
+    OUI_VENDORS
+        .iter()
+        .find(|(oui, _)| *oui == prefix)
+        .map(|(_, vendor)| *vendor)
+}
@@ -1,44 +1,649 @@
+use futures::StreamExt;
+use log::{debug, info, warn};
+use pcap::{Active, Capture, Device, Linktype, PacketCodec, Precision};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::result::Result;
+use std::sync::atomic::AtomicU64;
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::arp_watch::BindingTracker;
+use crate::capture_output::{CaptureOutput, SaveRequest};
+use crate::dhcp::{parse_dhcp, DhcpInfo, DHCP_CLIENT_PORT, DHCP_SERVER_PORT};
+use crate::eapol::{parse_eapol_key, EapolKeyInfo, HandshakeTracker, ETHERTYPE_EAPOL};
+use crate::endpoints::{ip_scope, EndpointTracker, IpScope};
+use crate::error::WifiError;
+use crate::flows::FlowTracker;
+use crate::forwarder::Forwarder;
+use crate::lan_devices::DeviceInventory;
+use crate::radiotap::{frame_kind_name, ActionFrame, AuthFrame, RadiotapError, RadiotapParser};
+use crate::reassembly::{FragmentKey, Ipv4Reassembler};
+use crate::reverse_dns::ReverseDns;
+use crate::sessions::SessionManager;
+use crate::timestamp::{format_rfc3339, TimestampPrecision};
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PacketInfo {
     pub src_mac: String,
     pub dst_mac: String,
     pub src_ip: Option<String>,
     pub dst_ip: Option<String>,
+    pub src_ip_scope: Option<IpScope>,
+    pub dst_ip_scope: Option<IpScope>,
+    /// Hostnames of public addresses from reverse DNS, when enabled and
+    /// already resolved; later lookups arrive as "host_resolved" events
+    pub src_host: Option<String>,
+    pub dst_host: Option<String>,
     pub src_port: Option<u16>,
     pub dst_port: Option<u16>,
     pub protocol: String,
     pub length: usize,
     pub payload: Option<String>,
     pub timestamp: u64,
+    /// Capture time as RFC 3339 UTC at the capture's timestamp precision
+    pub timestamp_iso: String,
+    pub eapol: Option<EapolKeyInfo>,
+    pub dhcp: Option<DhcpInfo>,
+    pub reassembled: bool,
+    pub wifi: Option<WifiFrameInfo>,
+    /// The captured frame as hex, only filled while `capture_raw` is on, and
+    /// cut to the capture's payload byte limit.
+    /// Doubles the size of every frame and goes into each "packet" event and
+    /// the capture buffer, so it is off by default.
+    pub raw_hex: Option<String>,
+    /// Decoded protocol layers, outermost first, for a protocol tree view
+    pub layers: Vec<Layer>,
+    /// Id of the session that was active when the packet was captured
+    pub session_id: Option<String>,
+    /// Set when the frame couldn't be fully decoded. Frames that fail before
+    /// any layer is decoded only carry this, the length and the timestamps.
+    pub malformed: Option<MalformedReason>,
+}
+
+/// Why a captured frame couldn't be fully decoded.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MalformedReason {
+    /// Shorter than the link-layer header
+    TooShort,
+    /// An ethertype or loopback address family the parser doesn't know
+    UnknownEthertype,
+    /// A header that claims more bytes than the frame holds
+    TruncatedHeader,
+    /// A radiotap header with an unsupported version
+    UnsupportedHeader,
+}
+
+impl MalformedReason {
+    fn label(self) -> &'static str {
+        match self {
+            MalformedReason::TooShort => "too short",
+            MalformedReason::UnknownEthertype => "unknown ethertype",
+            MalformedReason::TruncatedHeader => "truncated header",
+            MalformedReason::UnsupportedHeader => "unsupported header",
+        }
+    }
+}
+
+impl From<&RadiotapError> for MalformedReason {
+    fn from(err: &RadiotapError) -> Self {
+        match err {
+            RadiotapError::TooShort(_) => MalformedReason::TooShort,
+            RadiotapError::UnsupportedVersion(_) => MalformedReason::UnsupportedHeader,
+            RadiotapError::BadLength { .. } | RadiotapError::Truncated { .. } => {
+                MalformedReason::TruncatedHeader
+            }
+        }
+    }
+}
+
+/// Frames the current capture couldn't fully decode, by reason.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ParseFailures {
+    pub too_short: u64,
+    pub unknown_ethertype: u64,
+    pub truncated_header: u64,
+    pub unsupported_header: u64,
+}
+
+impl ParseFailures {
+    pub fn record(&mut self, reason: MalformedReason) {
+        let counter = match reason {
+            MalformedReason::TooShort => &mut self.too_short,
+            MalformedReason::UnknownEthertype => &mut self.unknown_ethertype,
+            MalformedReason::TruncatedHeader => &mut self.truncated_header,
+            MalformedReason::UnsupportedHeader => &mut self.unsupported_header,
+        };
+        *counter += 1;
+    }
+}
+
+/// 802.11 details for packets captured on a radiotap (monitor mode) interface.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct WifiFrameInfo {
+    pub frame_type: u8,
+    pub frame_subtype: u8,
+    pub bssid: String,
+    pub signal_dbm: Option<i8>,
+    pub channel_freq: Option<u16>,
+    /// Radiotap TSFT: the receiving radio's MAC clock in microseconds
+    pub tsft: Option<u64>,
+    pub action: Option<ActionFrame>,
+    pub auth: Option<AuthFrame>,
+    /// Network name from the SSID element of beacons and probes, when visible
+    pub ssid: Option<String>,
+}
+
+/// One decoded protocol layer of a packet.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(tag = "layer", rename_all = "snake_case")]
+pub enum Layer {
+    Ethernet {
+        src_mac: String,
+        dst_mac: String,
+        ethertype: u16,
+    },
+    /// DLT_NULL / DLT_LOOP pseudo-header
+    Loopback {
+        family: u32,
+    },
+    Vlan {
+        id: u16,
+        priority: u8,
+        ethertype: u16,
+    },
+    Arp {
+        operation: u16,
+        sender_mac: String,
+        sender_ip: String,
+        target_ip: String,
+    },
+    Ipv4 {
+        src_ip: String,
+        dst_ip: String,
+        protocol: u8,
+        /// A fragment whose datagram couldn't be (or isn't yet) reassembled
+        fragment: bool,
+    },
+    Ipv6 {
+        src_ip: String,
+        dst_ip: String,
+        next_header: u8,
+    },
+    Tcp {
+        src_port: u16,
+        dst_port: u16,
+    },
+    Udp {
+        src_port: u16,
+        dst_port: u16,
+    },
+    Sctp {
+        src_port: u16,
+        dst_port: u16,
+        chunks: Vec<String>,
+    },
+    Quic {
+        version: String,
+    },
+    Dhcp(DhcpInfo),
+    Dns {
+        id: u16,
+        response: bool,
+        query: Option<String>,
+        /// Addresses from A/AAAA answer records
+        answers: Vec<String>,
+    },
+    Http {
+        payload: String,
+        /// Host header of a request, without surrounding whitespace
+        host: Option<String>,
+    },
+    Eapol(EapolKeyInfo),
+    Ieee80211(WifiFrameInfo),
+    /// LLC/SNAP encapsulation of an 802.11 data frame
+    LlcSnap {
+        ethertype: u16,
+    },
+    Pppoe {
+        /// Discovery packet (PADI/PADO/PADR/PADS/PADT) or "Session"
+        stage: String,
+        session_id: u16,
+        /// Protocol carried by a session packet's PPP frame
+        ppp_protocol: Option<String>,
+    },
+}
+
+impl Layer {
+    fn mark_fragment(&mut self) {
+        if let Layer::Ipv4 { fragment, .. } = self {
+            *fragment = true;
+        }
+    }
+}
+
+/// A packet decoded into its protocol layers, outermost first.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParsedPacket {
+    pub layers: Vec<Layer>,
+    pub length: usize,
+    pub timestamp: u64,
+    pub timestamp_iso: String,
+    pub reassembled: bool,
+    /// Why decoding stopped before the innermost layer, if it did
+    pub malformed: Option<MalformedReason>,
+}
+
+fn ethertype_label(ethertype: u16) -> String {
+    match ethertype {
+        0x0800 => "IPv4".to_string(),
+        0x86DD => "IPv6".to_string(),
+        ETHERTYPE_ARP => "ARP".to_string(),
+        ETHERTYPE_EAPOL => "EAPOL".to_string(),
+        ETHERTYPE_PPPOE_DISCOVERY => "PPPoE Discovery".to_string(),
+        ETHERTYPE_PPPOE_SESSION => "PPPoE Session".to_string(),
+        _ => format!("Unknown (0x{:04X})", ethertype),
+    }
+}
+
+impl From<ParsedPacket> for PacketInfo {
+    /// Flattens the layers into the summary fields. Each recognized layer
+    /// overrides the protocol label of the layers below it.
+    fn from(packet: ParsedPacket) -> Self {
+        let mut info = PacketInfo {
+            src_mac: String::new(),
+            dst_mac: String::new(),
+            src_ip: None,
+            dst_ip: None,
+            src_ip_scope: None,
+            dst_ip_scope: None,
+            src_host: None,
+            dst_host: None,
+            src_port: None,
+            dst_port: None,
+            protocol: String::new(),
+            length: packet.length,
+            payload: None,
+            timestamp: packet.timestamp,
+            timestamp_iso: packet.timestamp_iso,
+            eapol: None,
+            dhcp: None,
+            reassembled: packet.reassembled,
+            wifi: None,
+            raw_hex: None,
+            layers: Vec::new(),
+            session_id: None,
+            malformed: packet.malformed,
+        };
+
+        for layer in &packet.layers {
+            match layer {
+                Layer::Ethernet {
+                    src_mac,
+                    dst_mac,
+                    ethertype,
+                } => {
+                    info.src_mac = src_mac.clone();
+                    info.dst_mac = dst_mac.clone();
+                    info.protocol = ethertype_label(*ethertype);
+                }
+                Layer::Loopback { family } => info.protocol = format!("Loopback (AF {})", family),
+                Layer::Vlan { ethertype, .. } => info.protocol = ethertype_label(*ethertype),
+                Layer::Arp {
+                    operation,
+                    sender_ip,
+                    target_ip,
+                    ..
+                } => {
+                    info.src_ip = Some(sender_ip.clone());
+                    info.dst_ip = Some(target_ip.clone());
+                    info.protocol = match operation {
+                        1 => "ARP (request)".to_string(),
+                        2 => "ARP (reply)".to_string(),
+                        op => format!("ARP ({})", op),
+                    };
+                }
+                Layer::Ipv4 {
+                    src_ip,
+                    dst_ip,
+                    protocol,
+                    fragment,
+                } => {
+                    info.src_ip = Some(src_ip.clone());
+                    info.dst_ip = Some(dst_ip.clone());
+                    info.protocol = if *fragment {
+                        format!("IPv4 ({}) fragment", protocol)
+                    } else {
+                        format!("IPv4 ({})", protocol)
+                    };
+                }
+                Layer::Ipv6 {
+                    src_ip,
+                    dst_ip,
+                    next_header,
+                } => {
+                    info.src_ip = Some(src_ip.clone());
+                    info.dst_ip = Some(dst_ip.clone());
+                    info.protocol = format!("IPv6 ({})", next_header);
+                }
+                Layer::Tcp { src_port, dst_port } | Layer::Udp { src_port, dst_port } => {
+                    info.src_port = Some(*src_port);
+                    info.dst_port = Some(*dst_port);
+                }
+                Layer::Sctp {
+                    src_port,
+                    dst_port,
+                    chunks,
+                } => {
+                    info.src_port = Some(*src_port);
+                    info.dst_port = Some(*dst_port);
+                    info.protocol = format!("SCTP [{}]", chunks.join(", "));
+                }
+                Layer::Quic { version } => info.protocol = format!("QUIC ({})", version),
+                Layer::Dhcp(dhcp) => {
+                    info.protocol = format!("DHCP ({})", dhcp.message_name());
+                    info.dhcp = Some(dhcp.clone());
+                }
+                Layer::Dns { response, .. } => {
+                    info.protocol = if *response {
+                        "DNS (response)".to_string()
+                    } else {
+                        "DNS (query)".to_string()
+                    };
+                }
+                Layer::Http { payload, .. } => info.payload = Some(payload.clone()),
+                Layer::Eapol(key) => {
+                    info.protocol = match key.handshake_message {
+                        Some(message) => format!("EAPOL (M{})", message),
+                        None => "EAPOL".to_string(),
+                    };
+                    info.eapol = Some(key.clone());
+                }
+                Layer::Ieee80211(wifi) => info.wifi = Some(wifi.clone()),
+                Layer::LlcSnap { ethertype } => info.protocol = ethertype_label(*ethertype),
+                Layer::Pppoe {
+                    stage,
+                    ppp_protocol,
+                    ..
+                } => {
+                    info.protocol = match ppp_protocol {
+                        Some(protocol) => format!("PPPoE > PPP ({})", protocol),
+                        None => format!("PPPoE ({})", stage),
+                    };
+                }
+            }
+        }
+
+        // Name the tunnel in front of the protocol it carried
+        let tunneled = packet
+            .layers
+            .iter()
+            .any(|layer| matches!(layer, Layer::Pppoe { .. }));
+        if tunneled && !info.protocol.starts_with("PPPoE") {
+            info.protocol = format!("PPPoE > {}", info.protocol);
+        }
+
+        let scope = |ip: &Option<String>| {
+            ip.as_deref()
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+                .map(|ip| ip_scope(&ip))
+        };
+        info.src_ip_scope = scope(&info.src_ip);
+        info.dst_ip_scope = scope(&info.dst_ip);
+
+        info.layers = packet.layers;
+        info
+    }
 }
 
 pub struct PacketCapture {
     pub running: Arc<Mutex<bool>>,
+    // Bumped by every start, so threads of an earlier capture that missed
+    // the stop can tell they're stale
+    pub generation: Arc<AtomicU64>,
+    // While set, the capture thread drains packets without recording them
+    pub paused: Arc<Mutex<bool>>,
     pub device: Arc<Mutex<Option<String>>>,
     pub captured_packets: Arc<Mutex<Vec<PacketInfo>>>,
     pub last_fetch_timestamp: Arc<Mutex<u64>>,
+    // Per-second counters drained by the rate reporter, kept outside the
+    // packet buffer lock so reporting never contends with capture.
+    pub rate_packets: Arc<AtomicU64>,
+    pub rate_bytes: Arc<AtomicU64>,
+    // Rates from the reporter's last tick, for `get_capture_summary`
+    pub packets_per_sec: Arc<AtomicU64>,
+    pub bytes_per_sec: Arc<AtomicU64>,
+    // Running totals since the capture started
+    pub total_packets: Arc<AtomicU64>,
+    pub total_bytes: Arc<AtomicU64>,
+    pub clock: Arc<Mutex<CaptureClock>>,
+    // When the capture thread last parsed a packet, watched for stalls
+    pub last_packet_time: Arc<Mutex<Option<Instant>>>,
+    pub handshakes: Arc<Mutex<HandshakeTracker>>,
+    pub exclude_local: Arc<Mutex<bool>>,
+    pub capture_raw: Arc<Mutex<bool>>,
+    // Payload and raw bytes kept per packet; 0 keeps neither
+    pub payload_capture_bytes: Arc<Mutex<usize>>,
+    // Read per packet, so a change applies to the running capture
+    pub parse_depth: Arc<Mutex<ParseDepth>>,
+    pub reverse_dns: Arc<ReverseDns>,
+    pub local_addresses: Arc<Mutex<LocalAddresses>>,
+    pub local_packets: Arc<AtomicU64>,
+    pub local_bytes: Arc<AtomicU64>,
+    // Set by the capture thread once the device is open, used to validate
+    // filter expressions against the right link type.
+    pub linktype: Arc<Mutex<Option<Linktype>>>,
+    // Timestamp precision of the open capture handle, likewise
+    pub precision: Arc<Mutex<Option<Precision>>>,
+    pub filter: Arc<Mutex<Option<String>>>,
+    // Filter requested by `set_capture_filter`, applied by the capture thread
+    pub pending_filter: Arc<Mutex<Option<String>>>,
+    pub known_devices: Arc<Mutex<DeviceInventory>>,
+    pub bindings: Arc<Mutex<BindingTracker>>,
+    pub sessions: Arc<Mutex<SessionManager>>,
+    pub endpoints: Arc<Mutex<EndpointTracker>>,
+    pub flows: Arc<Mutex<FlowTracker>>,
+    pub parse_failures: Arc<Mutex<ParseFailures>>,
+    // Remote collector captured packets are streamed to, if any
+    pub forwarder: Arc<Mutex<Option<Forwarder>>>,
+    pub output: Arc<Mutex<CaptureOutput>>,
+    // Savefile change requested by the save commands, applied by the capture thread
+    pub pending_save: Arc<Mutex<Option<SaveRequest>>>,
+}
+
+/// Time spent capturing, which stands still while paused or stopped.
+#[derive(Debug, Default)]
+pub struct CaptureClock {
+    running_since: Option<Instant>,
+    banked: Duration,
+}
+
+impl CaptureClock {
+    pub fn start(&mut self) {
+        self.banked = Duration::ZERO;
+        self.running_since = Some(Instant::now());
+    }
+
+    /// Stops the clock, keeping the time counted so far.
+    pub fn freeze(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.banked += since.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.running_since.is_none() {
+            self.running_since = Some(Instant::now());
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.banked
+            + self
+                .running_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// At-a-glance totals for the status bar.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureSummary {
+    pub device: Option<String>,
+    pub running: bool,
+    pub paused: bool,
+    pub elapsed_secs: f64,
+    pub packets: u64,
+    pub bytes: u64,
+    /// Rates over the last full second, zero while paused or stopped
+    pub packets_per_sec: u64,
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CaptureRate {
+    pub device: String,
+    pub packets_per_sec: u64,
+    pub bytes_per_sec: u64,
+}
+
+/// Payload of the "capture_stalled" event, sent when an interface that was
+/// delivering packets goes silent.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CaptureStalled {
+    pub device: String,
+    pub last_packet_age_ms: u64,
+}
+
+/// Payload of the "capture_complete" event, sent when a packet limit is hit.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct CaptureComplete {
+    pub device: String,
+    pub packets: u64,
 }
 
 impl PacketCapture {
     pub fn new() -> Self {
         PacketCapture {
             running: Arc::new(Mutex::new(false)),
+            generation: Arc::new(AtomicU64::new(0)),
+            paused: Arc::new(Mutex::new(false)),
             device: Arc::new(Mutex::new(None)),
             captured_packets: Arc::new(Mutex::new(Vec::new())),
             last_fetch_timestamp: Arc::new(Mutex::new(0)),
+            rate_packets: Arc::new(AtomicU64::new(0)),
+            rate_bytes: Arc::new(AtomicU64::new(0)),
+            packets_per_sec: Arc::new(AtomicU64::new(0)),
+            bytes_per_sec: Arc::new(AtomicU64::new(0)),
+            total_packets: Arc::new(AtomicU64::new(0)),
+            total_bytes: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(Mutex::new(CaptureClock::default())),
+            last_packet_time: Arc::new(Mutex::new(None)),
+            handshakes: Arc::new(Mutex::new(HandshakeTracker::new())),
+            exclude_local: Arc::new(Mutex::new(false)),
+            capture_raw: Arc::new(Mutex::new(false)),
+            payload_capture_bytes: Arc::new(Mutex::new(DEFAULT_PAYLOAD_CAPTURE_BYTES)),
+            parse_depth: Arc::new(Mutex::new(ParseDepth::default())),
+            reverse_dns: Arc::new(ReverseDns::default()),
+            local_addresses: Arc::new(Mutex::new(LocalAddresses::default())),
+            local_packets: Arc::new(AtomicU64::new(0)),
+            local_bytes: Arc::new(AtomicU64::new(0)),
+            linktype: Arc::new(Mutex::new(None)),
+            precision: Arc::new(Mutex::new(None)),
+            filter: Arc::new(Mutex::new(None)),
+            pending_filter: Arc::new(Mutex::new(None)),
+            known_devices: Arc::new(Mutex::new(DeviceInventory::default())),
+            bindings: Arc::new(Mutex::new(BindingTracker::new())),
+            sessions: Arc::new(Mutex::new(SessionManager::default())),
+            endpoints: Arc::new(Mutex::new(EndpointTracker::new())),
+            flows: Arc::new(Mutex::new(FlowTracker::new())),
+            parse_failures: Arc::new(Mutex::new(ParseFailures::default())),
+            forwarder: Arc::new(Mutex::new(None)),
+            output: Arc::new(Mutex::new(CaptureOutput::default())),
+            pending_save: Arc::new(Mutex::new(None)),
         }
     }
 }
 
+/// MAC and IP addresses belonging to the capture interface itself.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LocalAddresses {
+    pub mac: Option<String>,
+    pub ips: Vec<String>,
+}
+
+impl LocalAddresses {
+    /// Resolves the addresses of `device`. IPs come from pcap's device list,
+    /// the MAC from sysfs since pcap doesn't report link-layer addresses.
+    pub fn resolve(device: &str) -> Self {
+        let ips = match Device::list() {
+            Ok(devices) => devices
+                .into_iter()
+                .filter(|d| d.name == device)
+                .flat_map(|d| d.addresses)
+                .map(|a| a.addr.to_string())
+                .collect(),
+            Err(e) => {
+                warn!("Failed to list devices for local address lookup: {}", e);
+                Vec::new()
+            }
+        };
+
+        let mac = std::fs::read_to_string(format!("/sys/class/net/{}/address", device))
+            .ok()
+            .map(|mac| mac.trim().to_uppercase())
+            .filter(|mac| !mac.is_empty());
+
+        debug!(
+            "Local addresses for {}: mac={:?}, ips={:?}",
+            device, mac, ips
+        );
+        LocalAddresses { mac, ips }
+    }
+
+    /// Whether the packet was sent from or to this host.
+    pub fn is_local(&self, packet: &PacketInfo) -> bool {
+        let mac_matches = self
+            .mac
+            .as_ref()
+            .is_some_and(|mac| *mac == packet.src_mac || *mac == packet.dst_mac);
+        let ip_matches = [&packet.src_ip, &packet.dst_ip]
+            .into_iter()
+            .flatten()
+            .any(|ip| self.ips.contains(ip));
+
+        mac_matches || ip_matches
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalTraffic {
+    pub addresses: LocalAddresses,
+    pub excluded: bool,
+    pub packets: u64,
+    pub bytes: u64,
+}
+
 // Protocol numbers
 const IP_PROTO_TCP: u8 = 6;
 const IP_PROTO_UDP: u8 = 17;
+const IP_PROTO_SCTP: u8 = 132;
+
+const ETHERTYPE_ARP: u16 = 0x0806;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88A8;
+const ETHERTYPE_PPPOE_DISCOVERY: u16 = 0x8863;
+const ETHERTYPE_PPPOE_SESSION: u16 = 0x8864;
+
+const PPPOE_CODE_SESSION: u8 = 0x00;
+const PPP_PROTO_IPV4: u16 = 0x0021;
+const PPP_PROTO_IPV6: u16 = 0x0057;
+
+// Loopback (DLT_NULL/DLT_LOOP) address families. AF_INET6 differs between
+// Linux, NetBSD/OpenBSD, FreeBSD and macOS.
+const AF_INET: u32 = 2;
+const AF_INET6: [u32; 4] = [10, 24, 28, 30];
 
 // Ethernet frame parsing
 fn parse_mac_address(bytes: &[u8]) -> String {
@@ -49,11 +654,83 @@ fn parse_mac_address(bytes: &[u8]) -> String {
         .join(":")
 }
 
+// ARP parsing (Ethernet/IPv4 only)
+struct ArpPacket {
+    operation: u16,
+    sender_mac: String,
+    sender_ip: Ipv4Addr,
+    target_ip: Ipv4Addr,
+}
+
+fn parse_arp(data: &[u8]) -> Option<ArpPacket> {
+    // htype (2) + ptype (2) + hlen (1) + plen (1) + oper (2)
+    // + sender MAC/IP (10) + target MAC/IP (10)
+    if data.len() < 28 || data[4] != 6 || data[5] != 4 {
+        return None;
+    }
+
+    Some(ArpPacket {
+        operation: u16::from_be_bytes([data[6], data[7]]),
+        sender_mac: parse_mac_address(&data[8..14]),
+        sender_ip: Ipv4Addr::new(data[14], data[15], data[16], data[17]),
+        target_ip: Ipv4Addr::new(data[24], data[25], data[26], data[27]),
+    })
+}
+
+// PPPoE parsing (RFC 2516)
+struct PppoeHeader {
+    code: u8,
+    session_id: u16,
+    length: u16,
+}
+
+fn parse_pppoe(data: &[u8]) -> Option<PppoeHeader> {
+    // ver/type (1) + code (1) + session id (2) + length (2); only version 1
+    // type 1 is defined
+    if data.len() < 6 || data[0] != 0x11 {
+        return None;
+    }
+    Some(PppoeHeader {
+        code: data[1],
+        session_id: u16::from_be_bytes([data[2], data[3]]),
+        length: u16::from_be_bytes([data[4], data[5]]),
+    })
+}
+
+/// Discovery stage name for a PPPoE code, or "Session" for session data.
+fn pppoe_stage(code: u8) -> String {
+    match code {
+        PPPOE_CODE_SESSION => "Session".to_string(),
+        0x09 => "PADI".to_string(),
+        0x07 => "PADO".to_string(),
+        0x19 => "PADR".to_string(),
+        0x65 => "PADS".to_string(),
+        0xA7 => "PADT".to_string(),
+        code => format!("0x{:02X}", code),
+    }
+}
+
+fn ppp_protocol_name(protocol: u16) -> String {
+    match protocol {
+        PPP_PROTO_IPV4 => "IPv4".to_string(),
+        PPP_PROTO_IPV6 => "IPv6".to_string(),
+        0xC021 => "LCP".to_string(),
+        0xC023 => "PAP".to_string(),
+        0xC223 => "CHAP".to_string(),
+        0x8021 => "IPCP".to_string(),
+        0x8057 => "IPV6CP".to_string(),
+        p => format!("0x{:04X}", p),
+    }
+}
+
 // IPv4 header parsing
 struct Ipv4Header {
     version: u8,
     ihl: u8,
     total_length: u16,
+    identification: u16,
+    more_fragments: bool,
+    fragment_offset: u16, // In 8-byte units
     protocol: u8,
     src_addr: Ipv4Addr,
     dst_addr: Ipv4Addr,
@@ -73,6 +750,10 @@ fn parse_ipv4_header(data: &[u8]) -> Option<(Ipv4Header, usize)> {
     }
 
     let total_length = u16::from_be_bytes([data[2], data[3]]);
+    let identification = u16::from_be_bytes([data[4], data[5]]);
+    let flags_fragment = u16::from_be_bytes([data[6], data[7]]);
+    let more_fragments = flags_fragment & 0x2000 != 0;
+    let fragment_offset = flags_fragment & 0x1FFF;
     let protocol = data[9];
 
     let src_addr = Ipv4Addr::new(data[12], data[13], data[14], data[15]);
@@ -83,6 +764,9 @@ fn parse_ipv4_header(data: &[u8]) -> Option<(Ipv4Header, usize)> {
             version,
             ihl,
             total_length,
+            identification,
+            more_fragments,
+            fragment_offset,
             protocol,
             src_addr,
             dst_addr,
@@ -91,6 +775,12 @@ fn parse_ipv4_header(data: &[u8]) -> Option<(Ipv4Header, usize)> {
     ))
 }
 
+impl Ipv4Header {
+    fn is_fragment(&self) -> bool {
+        self.more_fragments || self.fragment_offset != 0
+    }
+}
+
 // IPv6 header parsing
 struct Ipv6Header {
     version: u8,
@@ -177,123 +867,1165 @@ fn parse_udp_header(data: &[u8]) -> Option<(UdpHeader, usize)> {
     Some((UdpHeader { src_port, dst_port }, 8))
 }
 
-pub fn parse_packet(packet: &pcap::Packet) -> Result<PacketInfo, Box<dyn std::error::Error>> {
-    let data = packet.data;
+// SCTP common header and chunk parsing
+struct SctpHeader {
+    src_port: u16,
+    dst_port: u16,
+    chunk_types: Vec<u8>,
+}
 
-    // Ensure we have at least an Ethernet header (14 bytes)
-    if data.len() < 14 {
-        return Err("Packet too short for Ethernet header".into());
+fn parse_sctp_header(data: &[u8]) -> Option<SctpHeader> {
+    if data.len() < 12 {
+        return None;
     }
 
-    // Parse Ethernet header
-    let dst_mac = parse_mac_address(&data[0..6]);
-    let src_mac = parse_mac_address(&data[6..12]);
-    let ethertype = u16::from_be_bytes([data[12], data[13]]);
+    let src_port = u16::from_be_bytes([data[0], data[1]]);
+    let dst_port = u16::from_be_bytes([data[2], data[3]]);
+
+    // Chunks follow the 12-byte common header, each padded to 4 bytes
+    let mut chunk_types = Vec::new();
+    let mut offset = 12;
+    while offset + 4 <= data.len() {
+        let chunk_length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        if chunk_length < 4 {
+            break;
+        }
+        chunk_types.push(data[offset]);
+        offset += (chunk_length + 3) & !3;
+    }
+
+    Some(SctpHeader {
+        src_port,
+        dst_port,
+        chunk_types,
+    })
+}
+
+fn sctp_chunk_name(chunk_type: u8) -> String {
+    match chunk_type {
+        0 => "DATA".to_string(),
+        1 => "INIT".to_string(),
+        2 => "INIT_ACK".to_string(),
+        3 => "SACK".to_string(),
+        4 => "HEARTBEAT".to_string(),
+        5 => "HEARTBEAT_ACK".to_string(),
+        6 => "ABORT".to_string(),
+        7 => "SHUTDOWN".to_string(),
+        8 => "SHUTDOWN_ACK".to_string(),
+        9 => "ERROR".to_string(),
+        10 => "COOKIE_ECHO".to_string(),
+        11 => "COOKIE_ACK".to_string(),
+        14 => "SHUTDOWN_COMPLETE".to_string(),
+        other => format!("{}", other),
+    }
+}
+
+// QUIC detection, based on the invariant header forms (RFC 8999).
+// Returns the version name for the protocol label.
+fn detect_quic(payload: &[u8]) -> Option<String> {
+    let first = *payload.first()?;
+
+    // The fixed bit is set in every QUIC v1/v2 packet
+    if first & 0x40 == 0 {
+        return None;
+    }
+
+    if first & 0x80 == 0 {
+        return Some("short header".to_string());
+    }
+
+    if payload.len() < 5 {
+        return None;
+    }
+    let version = u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]);
+    let version_name = match version {
+        0x0000_0000 => "version negotiation".to_string(),
+        0x0000_0001 => "v1".to_string(),
+        0x6b33_43cf => "v2".to_string(),
+        v if v >> 8 == 0x00ff_0000 => format!("draft-{}", v & 0xFF),
+        v => format!("0x{:08x}", v),
+    };
+    Some(version_name)
+}
+
+// Skips a possibly compressed DNS name, returning the offset after it
+fn skip_dns_name(data: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let len = *data.get(offset)?;
+        match len {
+            0 => return Some(offset + 1),
+            // A compression pointer ends the name
+            l if l & 0xC0 == 0xC0 => return Some(offset + 2),
+            l => offset += 1 + l as usize,
+        }
+    }
+}
+
+// DNS header, first question and A/AAAA answers (RFC 1035)
+fn parse_dns(data: &[u8]) -> Option<Layer> {
+    if data.len() < 12 {
+        return None;
+    }
+
+    let id = u16::from_be_bytes([data[0], data[1]]);
+    let flags = u16::from_be_bytes([data[2], data[3]]);
+    let question_count = u16::from_be_bytes([data[4], data[5]]);
+    let answer_count = u16::from_be_bytes([data[6], data[7]]);
+
+    // Questions rarely use compression, so only plain labels are followed
+    let mut query = None;
+    if question_count > 0 {
+        let mut labels = Vec::new();
+        let mut offset = 12;
+        while let Some(&len) = data.get(offset) {
+            if len == 0 || len & 0xC0 != 0 {
+                break;
+            }
+            let label = data.get(offset + 1..offset + 1 + len as usize)?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            offset += 1 + len as usize;
+        }
+        query = (!labels.is_empty()).then(|| labels.join("."));
+    }
+
+    let mut answers = Vec::new();
+    let mut offset = Some(12);
+    for _ in 0..question_count {
+        // Name, then type (2) and class (2)
+        offset = offset.and_then(|o| skip_dns_name(data, o)).map(|o| o + 4);
+    }
+    for _ in 0..answer_count {
+        let Some(name_end) = offset.and_then(|o| skip_dns_name(data, o)) else {
+            break;
+        };
+        // Type (2), class (2), TTL (4), data length (2)
+        let Some(fixed) = data.get(name_end..name_end + 10) else {
+            break;
+        };
+        let record_type = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let rdata_start = name_end + 10;
+        let rdata_end = rdata_start + u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        let Some(rdata) = data.get(rdata_start..rdata_end) else {
+            break;
+        };
+
+        match (record_type, rdata.len()) {
+            (1, 4) => {
+                answers.push(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]).to_string())
+            }
+            (28, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(rdata);
+                answers.push(Ipv6Addr::from(octets).to_string());
+            }
+            _ => {}
+        }
+        offset = Some(rdata_end);
+    }
+
+    Some(Layer::Dns {
+        id,
+        response: flags & 0x8000 != 0,
+        query,
+        answers,
+    })
+}
+
+// Longest DNS name plus ":port"
+const MAX_HTTP_HOST_LEN: usize = 253 + 6;
+
+/// Finds the Host header in the header block of an HTTP request. Header
+/// names match case-insensitively, and lines the segment cut off are
+/// ignored, since their value may be incomplete. Requests without one
+/// (HTTP/1.0) give None.
+fn http_host(data: &[u8]) -> Option<String> {
+    // Every complete line but the last, which the segment may have cut short;
+    // the request line is skipped
+    let mut lines: Vec<&[u8]> = data.split(|&b| b == b'\n').collect();
+    lines.pop();
+
+    lines
+        .into_iter()
+        .skip(1)
+        .take_while(|line| !line.is_empty() && *line != b"\r")
+        .find_map(|line| {
+            let colon = line.iter().position(|&b| b == b':')?;
+            let (name, value) = (&line[..colon], &line[colon + 1..]);
+            if !name.trim_ascii().eq_ignore_ascii_case(b"host") {
+                return None;
+            }
+
+            let host = String::from_utf8_lossy(value.trim_ascii());
+            let valid = !host.is_empty()
+                && host.len() <= MAX_HTTP_HOST_LEN
+                && !host.chars().any(char::is_control);
+            valid.then(|| host.into_owned())
+        })
+}
+
+/// How far up the protocol stack packets are decoded. Shallower depths
+/// skip the costlier parsers to keep up with busy links.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "snake_case")]
+// L4 is only chosen by the frontend through Deserialize, which dead code
+// analysis doesn't count as constructing it
+#[allow(dead_code)]
+pub enum ParseDepth {
+    /// Link layer only: Ethernet, VLAN, loopback and 802.11 headers
+    L2,
+    /// Up to the transport header, without HTTP, DNS, DHCP or QUIC
+    L4,
+    #[default]
+    Application,
+}
+
+/// Appends the transport layer and, at `ParseDepth::Application`, anything
+/// recognized on top of it.
+fn parse_transport(protocol: u8, data: &[u8], depth: ParseDepth, layers: &mut Vec<Layer>) {
+    let application = depth >= ParseDepth::Application;
+    match protocol {
+        IP_PROTO_TCP => {
+            if let Some((tcp_header, tcp_header_len)) = parse_tcp_header(data) {
+                layers.push(Layer::Tcp {
+                    src_port: tcp_header.src_port,
+                    dst_port: tcp_header.dst_port,
+                });
+
+                // Extract HTTP payload if port 80
+                if application && tcp_header.dst_port == 80 {
+                    // Lossy, so stray bytes in one header don't hide the rest
+                    let body = &data[tcp_header_len..];
+                    layers.push(Layer::Http {
+                        payload: String::from_utf8_lossy(body).into_owned(),
+                        host: http_host(body),
+                    });
+                }
+            }
+        }
+        IP_PROTO_UDP => {
+            if let Some((udp_header, udp_header_len)) = parse_udp_header(data) {
+                layers.push(Layer::Udp {
+                    src_port: udp_header.src_port,
+                    dst_port: udp_header.dst_port,
+                });
+
+                let payload = &data[udp_header_len..];
+                let ports = [udp_header.src_port, udp_header.dst_port];
+                let application = if !application {
+                    None
+                } else if ports.contains(&443) {
+                    detect_quic(payload).map(|version| Layer::Quic { version })
+                } else if ports.contains(&DHCP_SERVER_PORT) && ports.contains(&DHCP_CLIENT_PORT) {
+                    parse_dhcp(payload).map(Layer::Dhcp)
+                } else if ports.contains(&53) {
+                    parse_dns(payload)
+                } else {
+                    None
+                };
+                layers.extend(application);
+            }
+        }
+        IP_PROTO_SCTP => {
+            if let Some(sctp_header) = parse_sctp_header(data) {
+                layers.push(Layer::Sctp {
+                    src_port: sctp_header.src_port,
+                    dst_port: sctp_header.dst_port,
+                    chunks: sctp_header
+                        .chunk_types
+                        .iter()
+                        .map(|t| sctp_chunk_name(*t))
+                        .collect(),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parses a captured Ethernet frame. IPv4 fragments are fed through
+/// `reassembler` so the transport header is parsed from the full datagram.
+pub fn parse_packet(
+    packet: &pcap::Packet,
+    reassembler: &mut Ipv4Reassembler,
+    depth: ParseDepth,
+) -> Result<PacketInfo, WifiError> {
+    parse_ethernet_layers(packet.data, Some(reassembler), depth).map(PacketInfo::from)
+}
+
+/// Parses a single Ethernet frame from raw bytes, without fragment reassembly.
+pub fn parse_packet_bytes(data: &[u8]) -> Result<PacketInfo, WifiError> {
+    parse_ethernet_layers(data, None, ParseDepth::Application).map(PacketInfo::from)
+}
+
+/// Appends the layers of a network-layer payload identified by `ethertype`.
+/// Returns whether an IPv4 datagram was reassembled from fragments, or why
+/// the network layer couldn't be decoded.
+fn parse_network_layers(
+    ethertype: u16,
+    data: &[u8],
+    reassembler: Option<&mut Ipv4Reassembler>,
+    depth: ParseDepth,
+    layers: &mut Vec<Layer>,
+) -> Result<bool, MalformedReason> {
+    let mut offset = 0;
+    let mut reassembled = false;
+    if depth == ParseDepth::L2 {
+        return Ok(reassembled);
+    }
 
-    let mut offset = 14;
-    let mut protocol = String::new();
-    let mut src_ip = None;
-    let mut dst_ip = None;
-    let mut src_port = None;
-    let mut dst_port = None;
-    let mut payload = None;
-
-    // Parse IP header
     match ethertype {
         0x0800 => {
             // IPv4
             if let Some((ip_header, ip_header_len)) = parse_ipv4_header(&data[offset..]) {
-                src_ip = Some(ip_header.src_addr.to_string());
-                dst_ip = Some(ip_header.dst_addr.to_string());
-                protocol = format!("IPv4 ({})", ip_header.protocol);
+                // Bound the payload by the IP total length so Ethernet padding
+                // isn't mistaken for data. Offloaded captures may report 0.
+                let ip_end = if ip_header.total_length as usize >= ip_header_len {
+                    (offset + ip_header.total_length as usize).min(data.len())
+                } else {
+                    data.len()
+                };
                 offset += ip_header_len;
+                let ip_payload = &data[offset..ip_end.max(offset)];
 
-                // Parse TCP/UDP
-                match ip_header.protocol {
-                    IP_PROTO_TCP => {
-                        if let Some((tcp_header, tcp_header_len)) =
-                            parse_tcp_header(&data[offset..])
-                        {
-                            src_port = Some(tcp_header.src_port);
-                            dst_port = Some(tcp_header.dst_port);
-                            offset += tcp_header_len;
-
-                            // Extract HTTP payload if port 80
-                            if tcp_header.dst_port == 80 {
-                                payload = String::from_utf8(data[offset..].to_vec()).ok();
-                            }
-                        }
-                    }
-                    IP_PROTO_UDP => {
-                        if let Some((udp_header, udp_header_len)) =
-                            parse_udp_header(&data[offset..])
-                        {
-                            src_port = Some(udp_header.src_port);
-                            dst_port = Some(udp_header.dst_port);
-                            offset += udp_header_len;
+                let mut ipv4 = Layer::Ipv4 {
+                    src_ip: ip_header.src_addr.to_string(),
+                    dst_ip: ip_header.dst_addr.to_string(),
+                    protocol: ip_header.protocol,
+                    fragment: false,
+                };
+                let mut transport = Vec::new();
+
+                if !ip_header.is_fragment() {
+                    parse_transport(ip_header.protocol, ip_payload, depth, &mut transport);
+                } else if let Some(reassembler) = reassembler {
+                    let key = FragmentKey {
+                        src: ip_header.src_addr,
+                        dst: ip_header.dst_addr,
+                        id: ip_header.identification,
+                        protocol: ip_header.protocol,
+                    };
+                    match reassembler.add_fragment(
+                        key,
+                        ip_header.fragment_offset as usize * 8,
+                        ip_header.more_fragments,
+                        ip_payload,
+                    ) {
+                        Some(datagram) => {
+                            parse_transport(ip_header.protocol, &datagram, depth, &mut transport);
+                            reassembled = true;
                         }
+                        None => ipv4.mark_fragment(),
                     }
-                    _ => {}
+                } else if ip_header.fragment_offset == 0 {
+                    // Without reassembly only the first fragment has a transport header
+                    parse_transport(ip_header.protocol, ip_payload, depth, &mut transport);
+                } else {
+                    ipv4.mark_fragment();
                 }
+
+                layers.push(ipv4);
+                layers.append(&mut transport);
+            } else {
+                return Err(MalformedReason::TruncatedHeader);
             }
         }
         0x86DD => {
             // IPv6
             if let Some((ip_header, ip_header_len)) = parse_ipv6_header(&data[offset..]) {
-                src_ip = Some(ip_header.src_addr.to_string());
-                dst_ip = Some(ip_header.dst_addr.to_string());
-                protocol = format!("IPv6 ({})", ip_header.next_header);
+                layers.push(Layer::Ipv6 {
+                    src_ip: ip_header.src_addr.to_string(),
+                    dst_ip: ip_header.dst_addr.to_string(),
+                    next_header: ip_header.next_header,
+                });
                 offset += ip_header_len;
 
-                // Parse TCP/UDP
-                match ip_header.next_header {
-                    IP_PROTO_TCP => {
-                        if let Some((tcp_header, tcp_header_len)) =
-                            parse_tcp_header(&data[offset..])
-                        {
-                            src_port = Some(tcp_header.src_port);
-                            dst_port = Some(tcp_header.dst_port);
-                            offset += tcp_header_len;
-
-                            // Extract HTTP payload if port 80
-                            if tcp_header.dst_port == 80 {
-                                payload = String::from_utf8(data[offset..].to_vec()).ok();
-                            }
-                        }
-                    }
-                    IP_PROTO_UDP => {
-                        if let Some((udp_header, udp_header_len)) =
-                            parse_udp_header(&data[offset..])
-                        {
-                            src_port = Some(udp_header.src_port);
-                            dst_port = Some(udp_header.dst_port);
-                            offset += udp_header_len;
-                        }
-                    }
-                    _ => {}
-                }
+                parse_transport(ip_header.next_header, &data[offset..], depth, layers);
+            } else {
+                return Err(MalformedReason::TruncatedHeader);
+            }
+        }
+        ETHERTYPE_ARP => {
+            if let Some(arp) = parse_arp(&data[offset..]) {
+                layers.push(Layer::Arp {
+                    operation: arp.operation,
+                    sender_mac: arp.sender_mac,
+                    sender_ip: arp.sender_ip.to_string(),
+                    target_ip: arp.target_ip.to_string(),
+                });
+            }
+        }
+        ETHERTYPE_EAPOL => {
+            if let Some(key) = parse_eapol_key(&data[offset..]) {
+                layers.push(Layer::Eapol(key));
             }
         }
-        _ => {
-            protocol = format!("Unknown (0x{:04X})", ethertype);
+        ETHERTYPE_PPPOE_DISCOVERY | ETHERTYPE_PPPOE_SESSION => {
+            let pppoe = parse_pppoe(data).ok_or(MalformedReason::TruncatedHeader)?;
+            // The PPPoE length excludes its own header and bounds the payload
+            // against Ethernet padding
+            let payload_end = (6 + pppoe.length as usize).min(data.len());
+            let payload = &data[6..payload_end];
+
+            let ppp_protocol = (ethertype == ETHERTYPE_PPPOE_SESSION && payload.len() >= 2)
+                .then(|| u16::from_be_bytes([payload[0], payload[1]]));
+            layers.push(Layer::Pppoe {
+                stage: pppoe_stage(pppoe.code),
+                session_id: pppoe.session_id,
+                ppp_protocol: ppp_protocol.map(ppp_protocol_name),
+            });
+
+            // Only IP continues; LCP, authentication and NCP messages end here
+            match ppp_protocol {
+                Some(PPP_PROTO_IPV4) => {
+                    return parse_network_layers(0x0800, &payload[2..], reassembler, depth, layers)
+                }
+                Some(PPP_PROTO_IPV6) => {
+                    return parse_network_layers(0x86DD, &payload[2..], reassembler, depth, layers)
+                }
+                _ => {}
+            }
         }
+        _ => return Err(MalformedReason::UnknownEthertype),
     }
 
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .expect("Time went backwards")
-        .as_secs();
+    Ok(reassembled)
+}
+
+/// Decodes an Ethernet frame into its protocol layers, outermost first.
+pub fn parse_ethernet_layers(
+    data: &[u8],
+    reassembler: Option<&mut Ipv4Reassembler>,
+    depth: ParseDepth,
+) -> Result<ParsedPacket, WifiError> {
+    // Ensure we have at least an Ethernet header (14 bytes)
+    if data.len() < 14 {
+        return Err(WifiError::ParseError(
+            "Packet too short for Ethernet header".to_string(),
+        ));
+    }
+
+    let mut ethertype = u16::from_be_bytes([data[12], data[13]]);
+    let mut layers = vec![Layer::Ethernet {
+        src_mac: parse_mac_address(&data[6..12]),
+        dst_mac: parse_mac_address(&data[0..6]),
+        ethertype,
+    }];
+    let mut offset = 14;
+
+    // 802.1Q / 802.1ad tags, possibly stacked
+    while matches!(ethertype, ETHERTYPE_VLAN | ETHERTYPE_QINQ) && offset + 4 <= data.len() {
+        let tci = u16::from_be_bytes([data[offset], data[offset + 1]]);
+        ethertype = u16::from_be_bytes([data[offset + 2], data[offset + 3]]);
+        layers.push(Layer::Vlan {
+            id: tci & 0x0FFF,
+            priority: (tci >> 13) as u8,
+            ethertype,
+        });
+        offset += 4;
+    }
+
+    let network = parse_network_layers(ethertype, &data[offset..], reassembler, depth, &mut layers);
+    let (reassembled, malformed) = match network {
+        Ok(reassembled) => (reassembled, None),
+        Err(reason) => (false, Some(reason)),
+    };
+
+    let (timestamp, timestamp_iso) = now_timestamps();
+
+    Ok(ParsedPacket {
+        layers,
+        length: data.len(),
+        timestamp,
+        timestamp_iso,
+        reassembled,
+        malformed,
+    })
+}
+
+/// Decodes a loopback capture frame: a 4-byte address family (host byte
+/// order for DLT_NULL, network order for DLT_LOOP) followed by an IP packet.
+pub fn parse_loopback_layers(
+    data: &[u8],
+    reassembler: Option<&mut Ipv4Reassembler>,
+    depth: ParseDepth,
+) -> Result<ParsedPacket, WifiError> {
+    if data.len() < 4 {
+        return Err(WifiError::ParseError(
+            "Packet too short for loopback header".to_string(),
+        ));
+    }
+
+    // The capturing host's byte order is unknown, but families are small
+    // numbers, so only one reading is plausible
+    let header = [data[0], data[1], data[2], data[3]];
+    let little_endian = u32::from_le_bytes(header);
+    let family = if little_endian <= 0xFF {
+        little_endian
+    } else {
+        u32::from_be_bytes(header)
+    };
+
+    let mut layers = vec![Layer::Loopback { family }];
+    let network = match family {
+        AF_INET => parse_network_layers(0x0800, &data[4..], reassembler, depth, &mut layers),
+        f if AF_INET6.contains(&f) => {
+            parse_network_layers(0x86DD, &data[4..], reassembler, depth, &mut layers)
+        }
+        _ => Err(MalformedReason::UnknownEthertype),
+    };
+    let (reassembled, malformed) = match network {
+        Ok(reassembled) => (reassembled, None),
+        Err(reason) => (false, Some(reason)),
+    };
+
+    let (timestamp, timestamp_iso) = now_timestamps();
+
+    Ok(ParsedPacket {
+        layers,
+        length: data.len(),
+        timestamp,
+        timestamp_iso,
+        reassembled,
+        malformed,
+    })
+}
+
+/// Parses a radiotap-encapsulated 802.11 frame into a WiFi-flavored
+/// `PacketInfo`, with the transmitter/receiver as the source/destination.
+/// Unencrypted data frames are decoded on through their LLC/SNAP header with
+/// the same network-layer parsing as Ethernet.
+pub fn parse_radiotap_packet(
+    data: &[u8],
+    reassembler: Option<&mut Ipv4Reassembler>,
+    depth: ParseDepth,
+) -> Result<PacketInfo, RadiotapError> {
+    let frame = RadiotapParser::new(data).parse_wifi_frame()?;
+
+    let frame_type = (frame.frame_control & 0x000C) >> 2;
+    let frame_subtype = (frame.frame_control & 0x00F0) >> 4;
+
+    let (timestamp, timestamp_iso) = now_timestamps();
+
+    let wifi = WifiFrameInfo {
+        frame_type: frame_type as u8,
+        frame_subtype: frame_subtype as u8,
+        bssid: parse_mac_address(&frame.addr3),
+        signal_dbm: frame.radiotap.antenna_signal,
+        channel_freq: frame.radiotap.channel_freq,
+        tsft: frame.radiotap.mac_timestamp,
+        action: frame.action.clone(),
+        auth: frame.auth,
+        ssid: frame.ssid.clone(),
+    };
+
+    let kind = frame_kind_name(frame_type, frame_subtype);
+    let protocol = match (&frame.action, &frame.auth) {
+        (Some(action), _) => format!("802.11 {} ({})", kind, action.describe()),
+        (_, Some(auth)) => format!(
+            "802.11 {} ({}, seq {})",
+            kind, auth.algorithm, auth.sequence
+        ),
+        _ => format!("802.11 {}", kind),
+    };
+
+    if let Some(llc) = frame.llc_snap {
+        let mut layers = vec![
+            Layer::Ieee80211(wifi),
+            Layer::LlcSnap {
+                ethertype: llc.ethertype,
+            },
+        ];
+        let payload = &data[llc.payload_start..llc.payload_end];
+        let network = parse_network_layers(llc.ethertype, payload, reassembler, depth, &mut layers);
+        let (reassembled, malformed) = match network {
+            Ok(reassembled) => (reassembled, None),
+            Err(reason) => (false, Some(reason)),
+        };
+
+        let mut info = PacketInfo::from(ParsedPacket {
+            layers,
+            length: data.len(),
+            timestamp,
+            timestamp_iso,
+            reassembled,
+            malformed,
+        });
+        info.src_mac = parse_mac_address(&frame.addr2);
+        info.dst_mac = parse_mac_address(&frame.addr1);
+        return Ok(info);
+    }
 
     Ok(PacketInfo {
-        src_mac,
-        dst_mac,
-        src_ip,
-        dst_ip,
-        src_port,
-        dst_port,
+        src_mac: parse_mac_address(&frame.addr2),
+        dst_mac: parse_mac_address(&frame.addr1),
+        src_ip: None,
+        dst_ip: None,
+        src_ip_scope: None,
+        dst_ip_scope: None,
+        src_host: None,
+        dst_host: None,
+        src_port: None,
+        dst_port: None,
         protocol,
         length: data.len(),
-        payload,
+        payload: None,
         timestamp,
+        timestamp_iso,
+        eapol: None,
+        dhcp: None,
+        reassembled: false,
+        wifi: Some(wifi.clone()),
+        raw_hex: None,
+        layers: vec![Layer::Ieee80211(wifi)],
+        session_id: None,
+        malformed: None,
     })
 }
+
+impl PacketInfo {
+    /// A placeholder for a frame that failed before any layer was decoded,
+    /// so the drop still shows up in the packet list.
+    fn malformed(reason: MalformedReason, length: usize) -> Self {
+        let (timestamp, timestamp_iso) = now_timestamps();
+        PacketInfo {
+            src_mac: String::new(),
+            dst_mac: String::new(),
+            src_ip: None,
+            dst_ip: None,
+            src_ip_scope: None,
+            dst_ip_scope: None,
+            src_host: None,
+            dst_host: None,
+            src_port: None,
+            dst_port: None,
+            protocol: format!("Malformed ({})", reason.label()),
+            length,
+            payload: None,
+            timestamp,
+            timestamp_iso,
+            eapol: None,
+            dhcp: None,
+            reassembled: false,
+            wifi: None,
+            raw_hex: None,
+            layers: Vec::new(),
+            session_id: None,
+            malformed: Some(reason),
+        }
+    }
+
+    /// Cuts the decoded payload text down to `limit` bytes, dropping it
+    /// entirely at 0. Headers already parsed out of it, like the HTTP Host,
+    /// are kept.
+    pub fn truncate_payloads(&mut self, limit: usize) {
+        if limit == 0 {
+            self.payload = None;
+        } else if let Some(payload) = &mut self.payload {
+            truncate_utf8(payload, limit);
+        }
+        for layer in &mut self.layers {
+            if let Layer::Http { payload, .. } = layer {
+                truncate_utf8(payload, limit);
+            }
+        }
+    }
+}
+
+/// Truncates `text` to at most `limit` bytes without splitting a character.
+fn truncate_utf8(text: &mut String, limit: usize) {
+    if text.len() <= limit {
+        return;
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+/// Timestamps for packets that don't come with a capture header.
+fn now_timestamps() -> (u64, String) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards");
+    let iso = format_rfc3339(
+        now.as_secs() as i64,
+        now.subsec_micros(),
+        TimestampPrecision::Micro,
+    );
+    (now.as_secs(), iso)
+}
+
+/// Dispatches a captured packet to the parser matching the capture's datalink,
+/// stamping it with the pcap header's capture time. Frames that can't be
+/// decoded come back as placeholders with `malformed` set instead of failing.
+pub fn parse_captured_packet(
+    packet: &pcap::Packet,
+    linktype: Linktype,
+    precision: Precision,
+    reassembler: &mut Ipv4Reassembler,
+    depth: ParseDepth,
+) -> PacketInfo {
+    // Ethernet and loopback parsing only fail on frames shorter than their header
+    let parsed = match linktype {
+        Linktype::IEEE802_11_RADIOTAP => {
+            parse_radiotap_packet(packet.data, Some(reassembler), depth)
+                .map_err(|e| MalformedReason::from(&e))
+        }
+        Linktype::NULL | Linktype::LOOP => {
+            parse_loopback_layers(packet.data, Some(reassembler), depth)
+                .map(PacketInfo::from)
+                .map_err(|_| MalformedReason::TooShort)
+        }
+        _ => parse_packet(packet, reassembler, depth).map_err(|_| MalformedReason::TooShort),
+    };
+    let mut info = parsed.unwrap_or_else(|reason| PacketInfo::malformed(reason, packet.data.len()));
+
+    // tv_usec holds nanoseconds when the capture uses nanosecond precision
+    let ts = packet.header.ts;
+    info.timestamp = ts.tv_sec as u64;
+    info.timestamp_iso = format_rfc3339(ts.tv_sec, ts.tv_usec as u32, precision.into());
+    info
+}
+
+/// Payload bytes kept per captured packet unless configured otherwise.
+pub const DEFAULT_PAYLOAD_CAPTURE_BYTES: usize = 256;
+
+/// Timestamp precision requested for live captures. Microseconds are
+/// supported everywhere; libpcap silently keeps micro if nano is unavailable,
+/// which would make nanosecond timestamps wrong.
+pub const CAPTURE_PRECISION: Precision = Precision::Micro;
+
+const OPEN_ATTEMPTS: u32 = 5;
+const OPEN_BACKOFF: Duration = Duration::from_millis(200);
+/// Longest a read waits for a packet, so the capture loop notices a stop
+/// request on a quiet interface or a driver that never wakes the read.
+const READ_TIMEOUT_MS: i32 = 500;
+
+/// Opens `device` for live capture, retrying with a linear backoff.
+///
+/// Starting a capture right after stopping one often races with the previous
+/// handle's teardown, so a busy device is retried a few times before giving up.
+/// Other failures, like a missing device or no permission, are returned
+/// straight away. Returns the handle with the timestamp precision it was
+/// activated with.
+pub fn open_capture_with_retry(device: &str) -> Result<(Capture<Active>, Precision), WifiError> {
+    let mut attempt = 1;
+    loop {
+        let opened = Capture::from_device(device).and_then(|cap| {
+            cap.immediate_mode(true)
+                .precision(CAPTURE_PRECISION)
+                .timeout(READ_TIMEOUT_MS)
+                .open()
+        });
+        match opened {
+            Ok(cap) => return Ok((cap, CAPTURE_PRECISION)),
+            Err(e) if attempt < OPEN_ATTEMPTS && is_transient_open_error(&e) => {
+                let backoff = OPEN_BACKOFF * attempt;
+                warn!(
+                    "Failed to open {} (attempt {}/{}): {}. Retrying in {:?}",
+                    device, attempt, OPEN_ATTEMPTS, e, backoff
+                );
+                std::thread::sleep(backoff);
+                attempt += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Whether a failed open may succeed if retried: the device is still held
+/// by another handle or briefly unavailable.
+fn is_transient_open_error(e: &pcap::Error) -> bool {
+    let msg = e.to_string().to_lowercase();
+    [
+        "busy",
+        "temporarily unavailable",
+        "try again",
+        "interrupted",
+    ]
+    .iter()
+    .any(|transient| msg.contains(transient))
+}
+
+/// Friendly protocol names accepted by `protocol_filter`, with their BPF.
+const PROTOCOL_FILTERS: &[(&str, &str)] = &[
+    ("TCP", "tcp"),
+    ("UDP", "udp"),
+    ("DNS", "port 53"),
+    ("HTTP", "tcp port 80"),
+    ("ARP", "arp"),
+    ("ICMP", "icmp or icmp6"),
+];
+
+/// Builds a BPF expression matching any of `protocols`, named as in
+/// `PROTOCOL_FILTERS` (case-insensitive). No protocols matches everything.
+pub fn protocol_filter(protocols: &[String]) -> Result<String, WifiError> {
+    let clauses = protocols
+        .iter()
+        .map(|name| {
+            PROTOCOL_FILTERS
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(name))
+                .map(|(_, bpf)| format!("({})", bpf))
+                .ok_or_else(|| {
+                    let valid: Vec<&str> = PROTOCOL_FILTERS.iter().map(|(n, _)| *n).collect();
+                    WifiError::ParseError(format!(
+                        "Unknown protocol '{}', expected one of {}",
+                        name,
+                        valid.join(", ")
+                    ))
+                })
+        })
+        .collect::<Result<Vec<String>, WifiError>>()?;
+    Ok(clauses.join(" or "))
+}
+
+/// Checks that `filter` compiles for captures of the given link type.
+pub fn validate_filter(filter: &str, linktype: Linktype) -> Result<(), WifiError> {
+    Capture::dead(linktype)?
+        .compile(filter, true)
+        .map(|_| ())
+        .map_err(|e| WifiError::ParseError(format!("Invalid filter '{}': {}", filter, e)))
+}
+
+/// Applies `filter` to a running capture. If the live change is rejected,
+/// the capture is reopened with the filter so the caller keeps capturing.
+pub fn apply_filter(
+    cap: &mut Capture<Active>,
+    device: &str,
+    filter: &str,
+) -> Result<(), WifiError> {
+    let Err(e) = cap.filter(filter, true) else {
+        return Ok(());
+    };
+
+    warn!(
+        "Live filter change on {} failed ({}), restarting capture",
+        device, e
+    );
+    let (mut restarted, _) = open_capture_with_retry(device)?;
+    restarted
+        .filter(filter, true)
+        .map_err(|e| WifiError::CaptureError(format!("Failed to set filter: {}", e)))?;
+    *cap = restarted;
+    Ok(())
+}
+
+/// Parses packets as an async `PacketStream` yields them.
+struct PacketInfoCodec {
+    linktype: Linktype,
+    reassembler: Ipv4Reassembler,
+    depth: ParseDepth,
+    payload_limit: usize,
+}
+
+impl PacketCodec for PacketInfoCodec {
+    type Item = PacketInfo;
+
+    fn decode(&mut self, packet: pcap::Packet<'_>) -> Self::Item {
+        let mut info = parse_captured_packet(
+            &packet,
+            self.linktype,
+            CAPTURE_PRECISION,
+            &mut self.reassembler,
+            self.depth,
+        );
+        info.truncate_payloads(self.payload_limit);
+        info
+    }
+}
+
+/// Opens `device` for `capture_packets` with `filter` applied. Activating
+/// the device blocks, so call this off the async runtime.
+pub fn open_stream_capture(
+    device: &str,
+    filter: Option<&str>,
+) -> Result<Capture<Active>, WifiError> {
+    let mut cap = Capture::from_device(device)?
+        .immediate_mode(true)
+        .precision(CAPTURE_PRECISION)
+        .open()?
+        .setnonblock()?;
+
+    if let Some(filter) = filter {
+        debug!("Setting pcap filter: {}", filter);
+        cap.filter(filter, true)
+            .map_err(|e| WifiError::CaptureError(format!("Failed to set filter: {}", e)))?;
+    }
+    Ok(cap)
+}
+
+/// Captures up to `count` packets from `device`, opened with
+/// `open_stream_capture`, and returns them directly. Like a running capture,
+/// packets are decoded to `depth` and their payloads cut to `payload_limit`
+/// bytes.
+///
+/// Stops as soon as `count` packets have been parsed or `timeout` elapses,
+/// whichever comes first. Malformed frames count, flagged as such. The
+/// capture is non-blocking and driven by the async runtime, so dropping the
+/// future cancels it and closes the device. Must run inside a tokio runtime.
+pub async fn capture_packets(
+    device: &str,
+    cap: Capture<Active>,
+    count: usize,
+    timeout: Duration,
+    depth: ParseDepth,
+    payload_limit: usize,
+) -> Result<Vec<PacketInfo>, WifiError> {
+    let codec = PacketInfoCodec {
+        linktype: cap.get_datalink(),
+        reassembler: Ipv4Reassembler::new(),
+        depth,
+        payload_limit,
+    };
+    let mut stream = cap.stream(codec)?;
+    let mut packets = Vec::with_capacity(count);
+
+    let collect = async {
+        while packets.len() < count {
+            match stream.next().await {
+                Some(Ok(packet_info)) => packets.push(packet_info),
+                Some(Err(e)) => return Err(WifiError::from(e)),
+                None => break,
+            }
+        }
+        Ok(())
+    };
+    match tokio::time::timeout(timeout, collect).await {
+        Ok(result) => result?,
+        Err(_) => debug!("Capture on {} timed out after {:?}", device, timeout),
+    }
+
+    info!(
+        "Captured {} of {} requested packets on {}",
+        packets.len(),
+        count,
+        device
+    );
+    Ok(packets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 20-byte IPv4 header from 192.168.1.2 to 10.0.0.1 followed by `payload`.
+    fn ipv4_packet(protocol: u8, payload: &[u8]) -> Vec<u8> {
+        let total_len = (20 + payload.len()) as u16;
+        let mut packet = vec![0x45, 0];
+        packet.extend_from_slice(&total_len.to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0x40, 0, 64, protocol, 0, 0]);
+        packet.extend_from_slice(&[192, 168, 1, 2, 10, 0, 0, 1]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn tcp_segment(src_port: u16, dst_port: u16) -> Vec<u8> {
+        let mut segment = Vec::new();
+        segment.extend_from_slice(&src_port.to_be_bytes());
+        segment.extend_from_slice(&dst_port.to_be_bytes());
+        segment.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xFF, 0xFF, 0, 0, 0, 0]);
+        segment
+    }
+
+    fn assert_ipv4_tcp(layers: &[Layer], src_port: u16, dst_port: u16) {
+        assert!(layers.iter().any(|layer| matches!(
+            layer,
+            Layer::Ipv4 { src_ip, dst_ip, protocol: 6, fragment: false }
+                if src_ip == "192.168.1.2" && dst_ip == "10.0.0.1"
+        )));
+        assert!(layers.iter().any(|layer| matches!(
+            layer,
+            Layer::Tcp { src_port: s, dst_port: d } if *s == src_port && *d == dst_port
+        )));
+    }
+
+    #[test]
+    fn dlt_null_ipv4_in_host_byte_order() {
+        let mut frame = AF_INET.to_le_bytes().to_vec();
+        frame.extend(ipv4_packet(6, &tcp_segment(51000, 443)));
+
+        let parsed = parse_loopback_layers(&frame, None, ParseDepth::Application).unwrap();
+
+        assert!(matches!(
+            parsed.layers[0],
+            Layer::Loopback { family: AF_INET }
+        ));
+        assert_ipv4_tcp(&parsed.layers, 51000, 443);
+        assert_eq!(parsed.malformed, None);
+        assert_eq!(parsed.length, frame.len());
+    }
+
+    #[test]
+    fn dlt_loop_ipv4_in_network_byte_order() {
+        let mut frame = AF_INET.to_be_bytes().to_vec();
+        frame.extend(ipv4_packet(6, &tcp_segment(8080, 40000)));
+
+        let parsed = parse_loopback_layers(&frame, None, ParseDepth::Application).unwrap();
+
+        assert!(matches!(
+            parsed.layers[0],
+            Layer::Loopback { family: AF_INET }
+        ));
+        assert_ipv4_tcp(&parsed.layers, 8080, 40000);
+    }
+
+    #[test]
+    fn loopback_header_alone_is_too_short() {
+        assert!(parse_loopback_layers(&[2, 0, 0], None, ParseDepth::Application).is_err());
+    }
+
+    #[test]
+    fn unknown_loopback_family_is_malformed() {
+        let mut frame = 99u32.to_le_bytes().to_vec();
+        frame.extend(ipv4_packet(6, &tcp_segment(1, 2)));
+
+        let parsed = parse_loopback_layers(&frame, None, ParseDepth::Application).unwrap();
+
+        assert_eq!(parsed.layers.len(), 1);
+        assert_eq!(parsed.malformed, Some(MalformedReason::UnknownEthertype));
+    }
+
+    #[test]
+    fn pppoe_session_carrying_ipv4_tcp() {
+        let ip = ipv4_packet(6, &tcp_segment(49152, 80));
+        let mut frame = vec![0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        frame.extend_from_slice(&[0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB]);
+        frame.extend_from_slice(&ETHERTYPE_PPPOE_SESSION.to_be_bytes());
+        // Version/type, session code, session id, then the PPP protocol and
+        // IP packet the length covers
+        frame.extend_from_slice(&[0x11, PPPOE_CODE_SESSION, 0x12, 0x34]);
+        frame.extend_from_slice(&(2 + ip.len() as u16).to_be_bytes());
+        frame.extend_from_slice(&PPP_PROTO_IPV4.to_be_bytes());
+        frame.extend(ip);
+        // Ethernet padding past the PPPoE length is ignored
+        frame.extend_from_slice(&[0; 4]);
+
+        let info = parse_packet_bytes(&frame).unwrap();
+
+        assert!(info.layers.iter().any(|layer| matches!(
+            layer,
+            Layer::Pppoe { session_id: 0x1234, ppp_protocol: Some(p), .. } if p == "IPv4"
+        )));
+        assert_ipv4_tcp(&info.layers, 49152, 80);
+        assert_eq!(info.protocol, "PPPoE > IPv4 (6)");
+        assert_eq!(info.src_port, Some(49152));
+        assert_eq!(info.dst_port, Some(80));
+        assert_eq!(info.src_ip.as_deref(), Some("192.168.1.2"));
+        assert_eq!(info.malformed, None);
+    }
+
+    /// A radiotap header without fields, then a beacon for `ssid`.
+    fn radiotap_beacon(ssid: &str) -> Vec<u8> {
+        let mut frame = vec![0, 0, 8, 0, 0, 0, 0, 0];
+        frame.extend_from_slice(&[0x80, 0x00, 0, 0]);
+        frame.extend_from_slice(&[0xFF; 6]);
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&[0; 8]);
+        frame.extend_from_slice(&[100, 0, 0x01, 0x00]);
+        frame.extend_from_slice(&[0, ssid.len() as u8]);
+        frame.extend_from_slice(ssid.as_bytes());
+        frame
+    }
+
+    #[test]
+    fn payload_limit_leaves_the_ssid_alone() {
+        let data = radiotap_beacon("home network");
+        let mut info = parse_radiotap_packet(&data, None, ParseDepth::Application).unwrap();
+
+        info.truncate_payloads(0);
+
+        assert_eq!(info.payload, None);
+        let wifi = info.wifi.unwrap();
+        assert_eq!(wifi.ssid.as_deref(), Some("home network"));
+        assert!(matches!(
+            &info.layers[0],
+            Layer::Ieee80211(layer) if layer.ssid.as_deref() == Some("home network")
+        ));
+    }
+
+    #[test]
+    fn payload_limit_cuts_http_payloads_on_a_character_boundary() {
+        let http = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n\xC3\xA9";
+        let mut segment = tcp_segment(50000, 80);
+        segment.extend_from_slice(http);
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+        frame.extend(ipv4_packet(6, &segment));
+        let mut info = parse_packet_bytes(&frame).unwrap();
+
+        info.truncate_payloads(http.len() - 1);
+        assert_eq!(info.payload.as_deref().map(str::len), Some(http.len() - 2));
+
+        info.truncate_payloads(0);
+        assert_eq!(info.payload, None);
+    }
+
+    /// An Ethernet frame carrying a UDP DNS query from port 53000 to 53.
+    fn dns_query_frame() -> Vec<u8> {
+        let mut dns = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        dns.extend_from_slice(b"\x07example\x03com\x00");
+        dns.extend_from_slice(&[0, 1, 0, 1]);
+        let mut udp = Vec::new();
+        udp.extend_from_slice(&53000u16.to_be_bytes());
+        udp.extend_from_slice(&53u16.to_be_bytes());
+        udp.extend_from_slice(&(8 + dns.len() as u16).to_be_bytes());
+        udp.extend_from_slice(&[0, 0]);
+        udp.extend(dns);
+
+        let mut frame = vec![0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+        frame.extend_from_slice(&[0x66, 0x77, 0x88, 0x99, 0xAA, 0xBB]);
+        frame.extend_from_slice(&0x0800u16.to_be_bytes());
+        frame.extend(ipv4_packet(17, &udp));
+        frame
+    }
+
+    fn parse_at(depth: ParseDepth) -> PacketInfo {
+        parse_ethernet_layers(&dns_query_frame(), None, depth)
+            .map(PacketInfo::from)
+            .unwrap()
+    }
+
+    #[test]
+    fn l2_depth_stops_at_the_link_layer() {
+        let info = parse_at(ParseDepth::L2);
+
+        assert_eq!(info.layers.len(), 1);
+        assert_eq!(info.src_mac, "66:77:88:99:AA:BB");
+        assert_eq!(info.protocol, "IPv4");
+        assert_eq!(info.src_ip, None);
+        assert_eq!(info.src_port, None);
+        assert_eq!(info.malformed, None);
+    }
+
+    #[test]
+    fn l4_depth_stops_at_the_transport_header() {
+        let info = parse_at(ParseDepth::L4);
+
+        assert!(matches!(
+            info.layers.last(),
+            Some(Layer::Udp {
+                src_port: 53000,
+                dst_port: 53
+            })
+        ));
+        assert_eq!(info.src_ip.as_deref(), Some("192.168.1.2"));
+        assert_eq!(info.dst_port, Some(53));
+        assert_eq!(info.protocol, "IPv4 (17)");
+    }
+
+    #[test]
+    fn application_depth_decodes_the_payload_protocol() {
+        let info = parse_at(ParseDepth::Application);
+
+        assert!(matches!(
+            info.layers.last(),
+            Some(Layer::Dns { id: 0x1234, response: false, query: Some(q), .. }) if q == "example.com"
+        ));
+        assert_eq!(info.dst_port, Some(53));
+        assert_eq!(info.protocol, "DNS (query)");
+    }
+}
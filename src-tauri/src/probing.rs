@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::oui;
+
+// Randomized MACs rotate, so a long session can see a great many "clients"
+const MAX_PROBING_CLIENTS: usize = 2048;
+const MAX_SSIDS_PER_CLIENT: usize = 64;
+
+/// One probe request heard during a scan.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProbeRequest {
+    pub client: String,
+    /// None for a wildcard probe, which asks every AP to answer
+    pub ssid: Option<String>,
+    pub timestamp: u64,
+}
+
+/// A device looking for networks, with the SSIDs it asked for by name.
+/// These usually come from the device's saved network list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProbingClient {
+    pub mac: String,
+    pub vendor: Option<String>,
+    /// Sorted, without duplicates
+    pub ssids: Vec<String>,
+    pub wildcard_probes: u64,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+/// Probe requests merged per client across scans.
+#[derive(Default)]
+pub struct ProbingClients {
+    clients: HashMap<String, ProbingClient>,
+}
+
+impl ProbingClients {
+    pub fn record(&mut self, probe: &ProbeRequest) {
+        if self.clients.len() >= MAX_PROBING_CLIENTS && !self.clients.contains_key(&probe.client) {
+            return;
+        }
+
+        let client = self
+            .clients
+            .entry(probe.client.clone())
+            .or_insert_with(|| ProbingClient {
+                mac: probe.client.clone(),
+                vendor: oui::lookup(&probe.client).map(str::to_string),
+                ssids: Vec::new(),
+                wildcard_probes: 0,
+                first_seen: probe.timestamp,
+                last_seen: probe.timestamp,
+            });
+        client.last_seen = client.last_seen.max(probe.timestamp);
+
+        match &probe.ssid {
+            None => client.wildcard_probes += 1,
+            Some(ssid) => {
+                if let Err(index) = client.ssids.binary_search(ssid) {
+                    if client.ssids.len() < MAX_SSIDS_PER_CLIENT {
+                        client.ssids.insert(index, ssid.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Clients, most recently heard first.
+    pub fn clients(&self) -> Vec<ProbingClient> {
+        let mut clients: Vec<ProbingClient> = self.clients.values().cloned().collect();
+        clients.sort_by_key(|client| std::cmp::Reverse(client.last_seen));
+        clients
+    }
+
+    pub fn clear(&mut self) {
+        self.clients.clear();
+    }
+}
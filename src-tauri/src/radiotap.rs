@@ -1,6 +1,75 @@
 use byteorder::{ByteOrder, LittleEndian};
-use log::debug;
+use log::{debug, warn};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::WifiError;
+
+// Radiotap flags bit: the frame includes a trailing 4-byte FCS
+const FLAG_FCS_AT_END: u8 = 0x10;
+
+// Real beacons carry a few dozen elements; more than this is malformed or hostile
+const MAX_INFORMATION_ELEMENTS: usize = 255;
+
+/// Why a radiotap-encapsulated frame couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RadiotapError {
+    /// Fewer bytes than the fixed 8-byte radiotap header
+    TooShort(usize),
+    UnsupportedVersion(u8),
+    /// The declared header length leaves no room for an 802.11 frame
+    BadLength {
+        declared: u16,
+        available: usize,
+    },
+    /// The buffer ended before `field` could be read, usually because the
+    /// capture was cut short by the snaplen
+    Truncated {
+        field: &'static str,
+        offset: usize,
+    },
+}
+
+impl RadiotapError {
+    /// Whether the buffer is a radiotap frame that was merely cut short,
+    /// as opposed to not looking like radiotap at all.
+    pub fn is_truncation(&self) -> bool {
+        matches!(self, RadiotapError::Truncated { .. })
+    }
+}
+
+impl fmt::Display for RadiotapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RadiotapError::TooShort(len) => {
+                write!(f, "Buffer too small for radiotap header ({} bytes)", len)
+            }
+            RadiotapError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported radiotap version: {}", version)
+            }
+            RadiotapError::BadLength {
+                declared,
+                available,
+            } => write!(
+                f,
+                "Invalid radiotap length {} for a {} byte buffer",
+                declared, available
+            ),
+            RadiotapError::Truncated { field, offset } => {
+                write!(f, "Buffer ends before {} at offset {}", field, offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RadiotapError {}
+
+impl From<RadiotapError> for WifiError {
+    fn from(err: RadiotapError) -> Self {
+        WifiError::ParseError(err.to_string())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RadiotapData {
@@ -15,6 +84,61 @@ pub struct RadiotapData {
     pub channel_flags: Option<u16>,
     pub antenna_signal: Option<i8>,
     pub antenna: Option<u8>,
+    /// (antenna index, dBm) for each antenna reported by MIMO adapters
+    pub antenna_signals: Vec<(u8, i8)>,
+    /// 802.11ax PPDU details from the HE field
+    pub he: Option<HeInfo>,
+}
+
+/// The parts of the radiotap HE field the adapter marked as known.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HeInfo {
+    pub mcs: Option<u8>,
+    /// Channel width, or None for an RU allocation narrower than 20 MHz
+    pub bandwidth_mhz: Option<u16>,
+    pub guard_interval_ns: Option<u16>,
+}
+
+// Known bits in the HE field's data1/data2 words
+const HE_DATA1_MCS_KNOWN: u16 = 0x0020;
+const HE_DATA1_BW_KNOWN: u16 = 0x4000;
+const HE_DATA2_GI_KNOWN: u16 = 0x0002;
+
+impl HeInfo {
+    /// Decodes the six little-endian data words of the HE field.
+    fn from_data(data: [u16; 6]) -> Self {
+        let [data1, data2, data3, _, data5, _] = data;
+
+        let mcs = (data1 & HE_DATA1_MCS_KNOWN != 0).then_some(((data3 >> 8) & 0x0F) as u8);
+        let bandwidth_mhz = if data1 & HE_DATA1_BW_KNOWN != 0 {
+            match data5 & 0x000F {
+                0 => Some(20),
+                1 => Some(40),
+                2 => Some(80),
+                3 => Some(160),
+                // 4 and up are RU allocations of a fraction of 20 MHz
+                _ => None,
+            }
+        } else {
+            None
+        };
+        let guard_interval_ns = if data2 & HE_DATA2_GI_KNOWN != 0 {
+            match (data5 >> 4) & 0x03 {
+                0 => Some(800),
+                1 => Some(1600),
+                2 => Some(3200),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        HeInfo {
+            mcs,
+            bandwidth_mhz,
+            guard_interval_ns,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -26,29 +150,351 @@ pub struct WiFiFrame {
     pub addr2: [u8; 6],
     pub addr3: [u8; 6],
     pub seq_ctrl: u16,
+    pub retry: bool,
+    pub addr4: Option<[u8; 6]>,
+    pub qos_control: Option<u16>,
+    /// Name from the SSID element, when it carries a visible one
     pub ssid: Option<String>,
+    pub ssid_state: SsidState,
     pub channel: Option<u8>,
     pub rates: Vec<u8>,
+    /// The transmitter's TSF timer in µs from the fixed parameters. Unlike
+    /// the radiotap TSFT, which is the receiving adapter's clock, this counts
+    /// from when the AP started its BSS.
+    pub tsf_timestamp: Option<u64>,
+    /// Beacon interval in TUs (1024 µs) from the fixed parameters
+    pub beacon_interval: Option<u16>,
+    /// Capability information from the beacon/probe response fixed parameters
+    pub capability_info: Option<u16>,
+    pub information_elements: Vec<InformationElement>,
+    /// Category and action code of an unprotected Action frame
+    pub action: Option<ActionFrame>,
+    /// Fixed fields of an unprotected Authentication frame
+    pub auth: Option<AuthFrame>,
+    /// Encapsulation header of an unprotected, non-aggregated data frame
+    pub llc_snap: Option<LlcSnap>,
+}
+
+/// How a beacon or probe response presented its SSID element. Hidden APs
+/// either blank the element (zero length) or pad it with null bytes to the
+/// real name's length; an absent element is malformed or deliberately evasive.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+#[serde(tag = "state", content = "ssid", rename_all = "snake_case")]
+pub enum SsidState {
+    Broadcast(String),
+    NullHidden,
+    ZeroLength,
+    Absent,
+}
+
+impl SsidState {
+    fn from_element(data: Option<&[u8]>) -> Self {
+        match data {
+            None => SsidState::Absent,
+            Some([]) => SsidState::ZeroLength,
+            Some(data) if data.iter().all(|&b| b == 0) => SsidState::NullHidden,
+            Some(data) => SsidState::Broadcast(String::from_utf8_lossy(data).to_string()),
+        }
+    }
+}
+
+/// The category and action code that open an Action frame body.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ActionFrame {
+    pub category: u8,
+    pub action: u8,
+    pub btm_request: Option<BtmRequest>,
+}
+
+/// Fixed fields of a BSS Transition Management request (802.11v). APs send
+/// these to steer clients elsewhere; with disassociation imminent set the
+/// client is about to be kicked off, which is also how forced roaming
+/// attacks push clients toward a rogue AP.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct BtmRequest {
+    pub candidate_list: bool,
+    pub disassociation_imminent: bool,
+    pub bss_termination_included: bool,
+    pub ess_disassociation_imminent: bool,
+    /// Beacon intervals until the AP disassociates the client, 0 if unspecified
+    pub disassociation_timer: u16,
 }
 
+const CATEGORY_SPECTRUM_MANAGEMENT: u8 = 0;
+const CATEGORY_RADIO_MEASUREMENT: u8 = 5;
+const CATEGORY_SA_QUERY: u8 = 8;
+const CATEGORY_WNM: u8 = 10;
+const WNM_BTM_REQUEST: u8 = 7;
+
+impl ActionFrame {
+    fn parse(body: &[u8]) -> Option<Self> {
+        let (&category, rest) = body.split_first()?;
+        let (&action, fields) = rest.split_first()?;
+
+        // Dialog token (1), request mode (1), disassociation timer (2)
+        let btm_request = match (category, action, fields) {
+            (CATEGORY_WNM, WNM_BTM_REQUEST, [_, mode, timer_lo, timer_hi, ..]) => {
+                Some(BtmRequest {
+                    candidate_list: mode & 0x01 != 0,
+                    disassociation_imminent: mode & 0x04 != 0,
+                    bss_termination_included: mode & 0x08 != 0,
+                    ess_disassociation_imminent: mode & 0x10 != 0,
+                    disassociation_timer: u16::from_le_bytes([*timer_lo, *timer_hi]),
+                })
+            }
+            _ => None,
+        };
+
+        Some(ActionFrame {
+            category,
+            action,
+            btm_request,
+        })
+    }
+
+    pub fn category_name(&self) -> &'static str {
+        match self.category {
+            CATEGORY_SPECTRUM_MANAGEMENT => "Spectrum Management",
+            1 => "QoS",
+            3 => "Block Ack",
+            4 => "Public",
+            CATEGORY_RADIO_MEASUREMENT => "Radio Measurement",
+            6 => "Fast BSS Transition",
+            7 => "HT",
+            CATEGORY_SA_QUERY => "SA Query",
+            9 => "Protected Dual of Public",
+            CATEGORY_WNM => "WNM",
+            12 => "TDLS",
+            13 => "Mesh",
+            15 => "Self-protected",
+            21 => "VHT",
+            30 => "HE",
+            126 | 127 => "Vendor-specific",
+            _ => "Unknown",
+        }
+    }
+
+    /// Name of the action, for the ones worth telling apart.
+    pub fn action_name(&self) -> Option<&'static str> {
+        let name = match (self.category, self.action) {
+            (CATEGORY_SPECTRUM_MANAGEMENT, 0) => "Measurement Request",
+            (CATEGORY_SPECTRUM_MANAGEMENT, 1) => "Measurement Report",
+            (CATEGORY_SPECTRUM_MANAGEMENT, 4) => "Channel Switch Announcement",
+            (CATEGORY_RADIO_MEASUREMENT, 0) => "Radio Measurement Request",
+            (CATEGORY_RADIO_MEASUREMENT, 1) => "Radio Measurement Report",
+            (CATEGORY_RADIO_MEASUREMENT, 4) => "Neighbor Report Request",
+            (CATEGORY_RADIO_MEASUREMENT, 5) => "Neighbor Report Response",
+            (CATEGORY_SA_QUERY, 0) => "SA Query Request",
+            (CATEGORY_SA_QUERY, 1) => "SA Query Response",
+            (CATEGORY_WNM, 6) => "BSS Transition Management Query",
+            (CATEGORY_WNM, WNM_BTM_REQUEST) => "BSS Transition Management Request",
+            (CATEGORY_WNM, 8) => "BSS Transition Management Response",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// "Category: action", falling back to the action code.
+    pub fn describe(&self) -> String {
+        match self.action_name() {
+            Some(name) => format!("{}: {}", self.category_name(), name),
+            None => format!("{} ({})", self.category_name(), self.action),
+        }
+    }
+}
+
+/// Authentication algorithm number from an Authentication frame.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthAlgorithm {
+    OpenSystem,
+    SharedKey,
+    FastTransition,
+    /// WPA3-Personal
+    Sae,
+    Fils,
+    Other(u16),
+}
+
+impl From<u16> for AuthAlgorithm {
+    fn from(number: u16) -> Self {
+        match number {
+            0 => AuthAlgorithm::OpenSystem,
+            1 => AuthAlgorithm::SharedKey,
+            2 => AuthAlgorithm::FastTransition,
+            3 => AuthAlgorithm::Sae,
+            4..=6 => AuthAlgorithm::Fils,
+            n => AuthAlgorithm::Other(n),
+        }
+    }
+}
+
+impl fmt::Display for AuthAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthAlgorithm::OpenSystem => write!(f, "Open System"),
+            AuthAlgorithm::SharedKey => write!(f, "Shared Key"),
+            AuthAlgorithm::FastTransition => write!(f, "Fast Transition"),
+            AuthAlgorithm::Sae => write!(f, "SAE"),
+            AuthAlgorithm::Fils => write!(f, "FILS"),
+            AuthAlgorithm::Other(n) => write!(f, "algorithm {}", n),
+        }
+    }
+}
+
+/// The fixed fields that open an Authentication frame body.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+pub struct AuthFrame {
+    pub algorithm: AuthAlgorithm,
+    /// Transaction sequence number; SAE uses 1 for commit and 2 for confirm
+    pub sequence: u16,
+    /// 802.11 status code, 0 for success
+    pub status: u16,
+}
+
+impl AuthFrame {
+    fn parse(body: &[u8]) -> Option<Self> {
+        if body.len() < 6 {
+            return None;
+        }
+        Some(AuthFrame {
+            algorithm: AuthAlgorithm::from(LittleEndian::read_u16(body)),
+            sequence: LittleEndian::read_u16(&body[2..]),
+            status: LittleEndian::read_u16(&body[4..]),
+        })
+    }
+}
+
+// RFC 1042 encapsulation, and 802.1H bridge tunnel for the few ethertypes
+// (IPX, AppleTalk AARP) that need to survive translation back to Ethernet
+const LLC_SNAP_RFC1042: [u8; 6] = [0xAA, 0xAA, 0x03, 0x00, 0x00, 0x00];
+const LLC_SNAP_BRIDGE_TUNNEL: [u8; 6] = [0xAA, 0xAA, 0x03, 0x00, 0x00, 0xF8];
+
+/// The LLC/SNAP header opening an 802.11 data frame body, which carries the
+/// ethertype a wired frame would have in its Ethernet header.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct LlcSnap {
+    pub ethertype: u16,
+    /// Byte range of the network-layer payload in the captured data
+    pub payload_start: usize,
+    pub payload_end: usize,
+}
+
+impl LlcSnap {
+    fn parse(data: &[u8], body_start: usize, body_end: usize) -> Option<Self> {
+        let body = data.get(body_start..body_end)?;
+        if body.len() < 8 || !(body[..6] == LLC_SNAP_RFC1042 || body[..6] == LLC_SNAP_BRIDGE_TUNNEL)
+        {
+            return None;
+        }
+        Some(LlcSnap {
+            ethertype: u16::from_be_bytes([body[6], body[7]]),
+            payload_start: body_start + 8,
+            payload_end: body_end,
+        })
+    }
+}
+
+/// A raw tagged parameter from a management frame body.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InformationElement {
+    pub id: u8,
+    pub data: Vec<u8>,
+}
+
+/// Present-word bits that steer parsing rather than announce a field. The
+/// field bits are laid out by `field_layout`.
 #[repr(u32)]
 #[derive(Debug)]
 pub enum RadiotapPresent {
-    TSFT = 1 << 0,
-    Flags = 1 << 1,
-    Rate = 1 << 2,
-    Channel = 1 << 3,
-    FHSS = 1 << 4,
-    AntennaSignal = 1 << 5,
-    AntennaNoise = 1 << 6,
-    LockQuality = 1 << 7,
-    TxAttenuation = 1 << 8,
-    DbTxAttenuation = 1 << 9,
-    DbmTxPower = 1 << 10,
-    Antenna = 1 << 11,
-    DbAntennaSignal = 1 << 12,
-    DbAntennaNoise = 1 << 13,
-    RxFlags = 1 << 14,
+    RadiotapNamespace = 1 << 29,
+    VendorNamespace = 1 << 30,
+    Ext = 1 << 31,
+}
+
+/// (alignment, size) in bytes of each radiotap namespace field, by bit.
+fn field_layout(bit: u32) -> Option<(usize, usize)> {
+    match bit {
+        0 => Some((8, 8)),        // TSFT
+        1 | 2 => Some((1, 1)),    // Flags, Rate
+        3 => Some((2, 4)),        // Channel
+        4 => Some((1, 2)),        // FHSS
+        5 | 6 => Some((1, 1)),    // Antenna signal/noise (dBm)
+        7..=9 => Some((2, 2)),    // Lock quality, TX attenuation
+        10..=13 => Some((1, 1)),  // TX power, antenna, antenna signal/noise (dB)
+        14 | 15 => Some((2, 2)),  // RX/TX flags
+        16 | 17 => Some((1, 1)),  // RTS/data retries
+        18 => Some((4, 8)),       // XChannel
+        19 => Some((1, 3)),       // MCS
+        20 => Some((4, 8)),       // A-MPDU status
+        21 => Some((2, 12)),      // VHT
+        22 => Some((8, 12)),      // Timestamp
+        23 | 24 => Some((2, 12)), // HE, HE-MU
+        25 => Some((2, 6)),       // HE-MU other user
+        26 => Some((1, 1)),       // 0-length PSDU
+        27 => Some((2, 4)),       // L-SIG
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Namespace {
+    Radiotap,
+    Vendor { header_read: bool },
+}
+
+/// Human-readable name of an 802.11 frame type/subtype combination.
+pub fn frame_kind_name(frame_type: u16, frame_subtype: u16) -> &'static str {
+    match (frame_type, frame_subtype) {
+        (0, 0) => "Association Request",
+        (0, 1) => "Association Response",
+        (0, 2) => "Reassociation Request",
+        (0, 3) => "Reassociation Response",
+        (0, 4) => "Probe Request",
+        (0, 5) => "Probe Response",
+        (0, 8) => "Beacon",
+        (0, 9) => "ATIM",
+        (0, 10) => "Disassociation",
+        (0, 11) => "Authentication",
+        (0, 12) => "Deauthentication",
+        (0, 13) => "Action",
+        (0, 14) => "Action No Ack",
+        (0, _) => "Management",
+        (1, 8) => "Block Ack Request",
+        (1, 9) => "Block Ack",
+        (1, 10) => "PS-Poll",
+        (1, 11) => "RTS",
+        (1, 12) => "CTS",
+        (1, 13) => "ACK",
+        (1, 14) => "CF-End",
+        (1, _) => "Control",
+        (2, 4) => "Null",
+        (2, 12) => "QoS Null",
+        (2, s) if s & 0x08 != 0 => "QoS Data",
+        (2, _) => "Data",
+        _ => "Extension",
+    }
+}
+
+/// WMM access category, derived from the traffic identifier (TID) in the
+/// QoS Control field.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AccessCategory {
+    Background,
+    BestEffort,
+    Video,
+    Voice,
+}
+
+impl AccessCategory {
+    pub fn from_qos_control(qos_control: u16) -> Self {
+        match qos_control & 0x000F {
+            1 | 2 => AccessCategory::Background,
+            4 | 5 => AccessCategory::Video,
+            6 | 7 => AccessCategory::Voice,
+            _ => AccessCategory::BestEffort,
+        }
+    }
 }
 
 pub struct RadiotapParser<'a> {
@@ -61,9 +507,9 @@ impl<'a> RadiotapParser<'a> {
         Self { data, offset: 0 }
     }
 
-    pub fn parse_radiotap_header(&mut self) -> Result<RadiotapData, String> {
+    pub fn parse_radiotap_header(&mut self) -> Result<RadiotapData, RadiotapError> {
         if self.data.len() < 8 {
-            return Err("Buffer too small for radiotap header".to_string());
+            return Err(RadiotapError::TooShort(self.data.len()));
         }
 
         debug!(
@@ -74,12 +520,7 @@ impl<'a> RadiotapParser<'a> {
         let version = self.data[0];
         debug!("Radiotap version byte: {:02X}", version);
         if version != 0 {
-            return Err(format!(
-                "Unsupported radiotap version: {} (hex: {:02X}). First 8 bytes: {:02X?}",
-                version,
-                version,
-                &self.data[..8.min(self.data.len())]
-            ));
+            return Err(RadiotapError::UnsupportedVersion(version));
         }
 
         let pad = self.data[1];
@@ -90,7 +531,13 @@ impl<'a> RadiotapParser<'a> {
             version, pad, length, present_flags
         );
 
+        // Further present words follow while the Ext bit is set
         self.offset = 8;
+        let mut present_words = vec![present_flags];
+        while present_words[present_words.len() - 1] & (RadiotapPresent::Ext as u32) != 0 {
+            present_words.push(self.read_u32("extended present word")?);
+        }
+
         let mut radiotap = RadiotapData {
             version,
             pad,
@@ -103,122 +550,291 @@ impl<'a> RadiotapParser<'a> {
             channel_flags: None,
             antenna_signal: None,
             antenna: None,
+            antenna_signals: Vec::new(),
+            he: None,
         };
 
-        // Parse present flags with safe error handling
-        if present_flags & (RadiotapPresent::TSFT as u32) != 0 {
-            radiotap.mac_timestamp = self.read_u64().ok();
-        }
-        if present_flags & (RadiotapPresent::Flags as u32) != 0 {
-            radiotap.flags = self.read_u8().ok();
+        let header_end = (length as usize).min(self.data.len());
+        let mut namespace = Namespace::Radiotap;
+
+        for (index, word) in present_words.iter().enumerate() {
+            match namespace {
+                Namespace::Radiotap => {
+                    if !self.parse_present_word(*word, index == 0, header_end, &mut radiotap) {
+                        // An unknown field hides the offsets of everything after it
+                        break;
+                    }
+                }
+                Namespace::Vendor { header_read } => {
+                    if !header_read {
+                        self.skip_vendor_namespace(header_end);
+                    }
+                }
+            }
+
+            // Bits 29/30 select the namespace of the next present word
+            if word & (RadiotapPresent::RadiotapNamespace as u32) != 0 {
+                namespace = Namespace::Radiotap;
+            } else if word & (RadiotapPresent::VendorNamespace as u32) != 0 {
+                namespace = match namespace {
+                    Namespace::Vendor { .. } if word & (RadiotapPresent::Ext as u32) != 0 => {
+                        Namespace::Vendor { header_read: true }
+                    }
+                    _ => Namespace::Vendor { header_read: false },
+                };
+            }
         }
-        if present_flags & (RadiotapPresent::Rate as u32) != 0 {
-            radiotap.rate = self.read_u8().ok();
+
+        Ok(radiotap)
+    }
+
+    /// Parses the fields announced by one radiotap-namespace present word.
+    /// The first word carries the frame-wide fields; later words (one per
+    /// antenna on MIMO adapters) only contribute antenna/signal pairs.
+    ///
+    /// Returns false when an unknown field makes further parsing unsafe.
+    fn parse_present_word(
+        &mut self,
+        word: u32,
+        primary: bool,
+        header_end: usize,
+        radiotap: &mut RadiotapData,
+    ) -> bool {
+        let mut antenna_signal = None;
+        let mut antenna = None;
+
+        for bit in 0..29 {
+            if word & (1 << bit) == 0 {
+                continue;
+            }
+
+            let Some((align, size)) = field_layout(bit) else {
+                debug!("Unknown radiotap field {}, skipping remaining fields", bit);
+                return false;
+            };
+
+            // Field alignment is relative to the start of the radiotap header
+            self.offset = self.offset.next_multiple_of(align);
+            if self.offset + size > header_end {
+                debug!("Radiotap field {} runs past the header", bit);
+                return false;
+            }
+
+            match bit {
+                0 if primary => radiotap.mac_timestamp = self.read_u64("TSFT").ok(),
+                1 if primary => radiotap.flags = self.read_u8("flags").ok(),
+                2 if primary => radiotap.rate = self.read_u8("rate").ok(),
+                3 if primary => {
+                    radiotap.channel_freq = self.read_u16("channel frequency").ok();
+                    radiotap.channel_flags = self.read_u16("channel flags").ok();
+                }
+                5 => antenna_signal = self.read_i8("antenna signal").ok(),
+                11 => antenna = self.read_u8("antenna").ok(),
+                23 if primary => {
+                    let mut data = [0u16; 6];
+                    for word in data.iter_mut() {
+                        *word = self.read_u16("HE data").unwrap_or(0);
+                    }
+                    radiotap.he = Some(HeInfo::from_data(data));
+                }
+                _ => self.offset += size,
+            }
         }
-        if present_flags & (RadiotapPresent::Channel as u32) != 0 {
-            radiotap.channel_freq = self.read_u16().ok();
-            radiotap.channel_flags = self.read_u16().ok();
+
+        if primary {
+            radiotap.antenna_signal = antenna_signal;
+            radiotap.antenna = antenna;
         }
-        if present_flags & (RadiotapPresent::AntennaSignal as u32) != 0 {
-            radiotap.antenna_signal = self.read_i8().ok();
+        if let (Some(antenna), Some(signal)) = (antenna, antenna_signal) {
+            radiotap.antenna_signals.push((antenna, signal));
         }
-        if present_flags & (RadiotapPresent::Antenna as u32) != 0 {
-            radiotap.antenna = self.read_u8().ok();
+
+        true
+    }
+
+    /// Skips a vendor namespace's data using the skip length in its header.
+    fn skip_vendor_namespace(&mut self, header_end: usize) {
+        self.offset = self.offset.next_multiple_of(2);
+        if self.offset + 6 > header_end {
+            self.offset = header_end;
+            return;
         }
 
-        Ok(radiotap)
+        // OUI (3), sub-namespace (1), skip length (2)
+        let skip_length = LittleEndian::read_u16(&self.data[self.offset + 4..]) as usize;
+        self.offset = (self.offset + 6 + skip_length).min(header_end);
     }
 
-    pub fn parse_wifi_frame(&mut self) -> Result<WiFiFrame, String> {
+    pub fn parse_wifi_frame(&mut self) -> Result<WiFiFrame, RadiotapError> {
+        self.parse_frame(true)
+    }
+
+    /// Parses the radiotap and 802.11 headers only, skipping the tagged
+    /// parameters of management frames. Much cheaper on IE-heavy beacons.
+    pub fn parse_frame_header(&mut self) -> Result<WiFiFrame, RadiotapError> {
+        self.parse_frame(false)
+    }
+
+    fn parse_frame(&mut self, parse_elements: bool) -> Result<WiFiFrame, RadiotapError> {
         let radiotap = self.parse_radiotap_header()?;
 
         // Move offset to start of 802.11 frame
         self.offset = radiotap.length as usize;
         if self.offset >= self.data.len() {
-            return Err("Invalid radiotap length".to_string());
+            return Err(RadiotapError::BadLength {
+                declared: radiotap.length,
+                available: self.data.len(),
+            });
         }
 
         // Parse 802.11 frame header with safe error handling
-        let frame_control = self
-            .read_u16()
-            .map_err(|e| format!("Failed to read frame control: {}", e))?;
-        let duration = self
-            .read_u16()
-            .map_err(|e| format!("Failed to read duration: {}", e))?;
+        let frame_control = self.read_u16("frame control")?;
+        let duration = self.read_u16("duration")?;
 
         // Safe address reading
-        let addr1 = self
-            .read_mac_address()
-            .map_err(|e| format!("Failed to read addr1: {}", e))?;
-        let addr2 = self
-            .read_mac_address()
-            .map_err(|e| format!("Failed to read addr2: {}", e))?;
-        let addr3 = self
-            .read_mac_address()
-            .map_err(|e| format!("Failed to read addr3: {}", e))?;
-
-        let seq_ctrl = self
-            .read_u16()
-            .map_err(|e| format!("Failed to read sequence control: {}", e))?;
+        let addr1 = self.read_mac_address("addr1")?;
+        let addr2 = self.read_mac_address("addr2")?;
+        let addr3 = self.read_mac_address("addr3")?;
+
+        let seq_ctrl = self.read_u16("sequence control")?;
 
         let frame_type = (frame_control & 0x000C) >> 2;
         let frame_subtype = (frame_control & 0x00F0) >> 4;
+        let retry = frame_control & 0x0800 != 0;
+
+        let mut addr4 = None;
+        let mut qos_control = None;
+
+        if frame_type == 2 {
+            // Address 4 is only present when both ToDS and FromDS are set
+            if frame_control & 0x0300 == 0x0300 {
+                addr4 = Some(self.read_mac_address("addr4")?);
+            }
+            // QoS data subtypes have bit 3 of the subtype set
+            if frame_subtype & 0x08 != 0 {
+                qos_control = Some(self.read_u16("QoS control")?);
+                // +HTC: the Order bit on a QoS data frame adds an HT Control field
+                if frame_control & 0x8000 != 0 {
+                    self.read_u32("HT control")?;
+                }
+            }
+        }
 
-        let mut ssid = None;
+        let mut ssid_element = None;
         let mut channel = None;
         let mut rates = Vec::new();
+        let mut tsf_timestamp = None;
+        let mut beacon_interval = None;
+        let mut capability_info = None;
+        let mut information_elements = Vec::new();
 
-        if frame_type == 0 && (frame_subtype == 8 || frame_subtype == 5) {
+        // Elements must end before the FCS when radiotap says one is present
+        let fcs_len = match radiotap.flags {
+            Some(flags) if flags & FLAG_FCS_AT_END != 0 => 4,
+            _ => 0,
+        };
+        let frame_end = self.data.len().saturating_sub(fcs_len);
+
+        // Beacons, probe requests and probe responses carry tagged elements
+        let has_elements = frame_type == 0 && matches!(frame_subtype, 4 | 5 | 8);
+        // Probe requests have no fixed parameters before their elements
+        let fixed_len = if frame_subtype == 4 { 0 } else { 12 };
+        if parse_elements && has_elements {
             // Skip fixed parameters safely
-            if self.offset + 12 <= self.data.len() {
-                self.offset += 12;
-
-                // Parse tagged parameters
-                while self.offset + 2 <= self.data.len() {
-                    let tag_number = match self.read_u8() {
-                        Ok(n) => n,
-                        Err(_) => break,
-                    };
-                    let tag_length = match self.read_u8() {
-                        Ok(n) => n as usize,
-                        Err(_) => break,
-                    };
-
-                    if self.offset + tag_length > self.data.len() {
+            if self.offset + fixed_len <= frame_end {
+                if fixed_len > 0 {
+                    // Timestamp (8) + beacon interval (2) + capability info (2)
+                    tsf_timestamp = Some(LittleEndian::read_u64(&self.data[self.offset..]));
+                    beacon_interval = Some(LittleEndian::read_u16(&self.data[self.offset + 8..]));
+                    capability_info = Some(LittleEndian::read_u16(&self.data[self.offset + 10..]));
+                    self.offset += fixed_len;
+                }
+
+                // Parse tagged parameters, stopping at the first one that doesn't fit
+                while self.offset + 2 <= frame_end {
+                    if information_elements.len() >= MAX_INFORMATION_ELEMENTS {
+                        warn!(
+                            "Stopped after {} information elements from {:02X?}, {} bytes left unparsed",
+                            MAX_INFORMATION_ELEMENTS,
+                            addr2,
+                            frame_end - self.offset
+                        );
+                        break;
+                    }
+
+                    let tag_number = self.data[self.offset];
+                    let tag_length = self.data[self.offset + 1] as usize;
+                    let body_start = self.offset + 2;
+                    let body_end = body_start + tag_length;
+                    if body_end > frame_end {
+                        debug!(
+                            "Truncated element {} ({} bytes declared, {} available)",
+                            tag_number,
+                            tag_length,
+                            frame_end - body_start
+                        );
                         break;
                     }
+                    let body = &self.data[body_start..body_end];
+
+                    information_elements.push(InformationElement {
+                        id: tag_number,
+                        data: body.to_vec(),
+                    });
 
                     match tag_number {
-                        0 => {
-                            // SSID
-                            if tag_length > 0 {
-                                ssid = String::from_utf8_lossy(
-                                    &self.data[self.offset..self.offset + tag_length],
-                                )
-                                .to_string()
-                                .into();
-                            }
-                        }
-                        3 => {
-                            // Channel
-                            if tag_length > 0 {
-                                channel = Some(self.data[self.offset]);
-                            }
-                        }
+                        // SSID; only the first element names the network
+                        0 if ssid_element.is_none() => ssid_element = Some(body),
+                        // Channel
+                        3 if tag_length > 0 => channel = Some(body[0]),
                         1 | 50 => {
                             // Supported rates
-                            rates.extend_from_slice(
-                                &self.data[self.offset..self.offset + tag_length],
-                            );
+                            rates.extend_from_slice(body);
                         }
                         _ => {}
                     }
 
-                    self.offset += tag_length;
+                    self.offset = body_end;
                 }
             }
         }
 
+        // Protected (802.11w) action frames have an encrypted body
+        let mut action = None;
+        if frame_type == 0
+            && (frame_subtype == 13 || frame_subtype == 14)
+            && frame_control & 0x4000 == 0
+        {
+            action = self
+                .data
+                .get(self.offset..frame_end)
+                .and_then(ActionFrame::parse);
+        }
+
+        // The third Shared Key frame is WEP-encrypted
+        let mut auth = None;
+        if frame_type == 0 && frame_subtype == 11 && frame_control & 0x4000 == 0 {
+            auth = self
+                .data
+                .get(self.offset..frame_end)
+                .and_then(AuthFrame::parse);
+        }
+
+        // Null data subtypes have no body, encrypted bodies can't be read, and
+        // an A-MSDU (QoS control bit 7) holds subframes rather than one packet
+        let mut llc_snap = None;
+        let has_body = frame_subtype & 0x04 == 0;
+        let aggregated = qos_control.is_some_and(|qos| qos & 0x0080 != 0);
+        if frame_type == 2 && has_body && !aggregated && frame_control & 0x4000 == 0 {
+            llc_snap = LlcSnap::parse(self.data, self.offset, frame_end);
+        }
+
+        let ssid_state = SsidState::from_element(ssid_element);
+        let ssid = match &ssid_state {
+            SsidState::Broadcast(name) => Some(name.clone()),
+            _ => None,
+        };
+
         Ok(WiFiFrame {
             radiotap,
             frame_control,
@@ -227,15 +843,33 @@ impl<'a> RadiotapParser<'a> {
             addr2,
             addr3,
             seq_ctrl,
+            retry,
+            addr4,
+            qos_control,
             ssid,
+            ssid_state,
             channel,
             rates,
+            tsf_timestamp,
+            beacon_interval,
+            capability_info,
+            information_elements,
+            action,
+            auth,
+            llc_snap,
         })
     }
 
-    fn read_mac_address(&mut self) -> Result<[u8; 6], String> {
+    fn truncated(&self, field: &'static str) -> RadiotapError {
+        RadiotapError::Truncated {
+            field,
+            offset: self.offset,
+        }
+    }
+
+    fn read_mac_address(&mut self, field: &'static str) -> Result<[u8; 6], RadiotapError> {
         if self.offset + 6 > self.data.len() {
-            return Err("Buffer overflow reading MAC address".to_string());
+            return Err(self.truncated(field));
         }
         let mut addr = [0u8; 6];
         addr.copy_from_slice(&self.data[self.offset..self.offset + 6]);
@@ -243,34 +877,134 @@ impl<'a> RadiotapParser<'a> {
         Ok(addr)
     }
 
-    fn read_u8(&mut self) -> Result<u8, String> {
+    fn read_u8(&mut self, field: &'static str) -> Result<u8, RadiotapError> {
         if self.offset >= self.data.len() {
-            return Err("Buffer overflow reading u8".to_string());
+            return Err(self.truncated(field));
         }
         let value = self.data[self.offset];
         self.offset += 1;
         Ok(value)
     }
 
-    fn read_i8(&mut self) -> Result<i8, String> {
-        self.read_u8().map(|v| v as i8)
+    fn read_i8(&mut self, field: &'static str) -> Result<i8, RadiotapError> {
+        self.read_u8(field).map(|v| v as i8)
     }
 
-    fn read_u16(&mut self) -> Result<u16, String> {
+    fn read_u16(&mut self, field: &'static str) -> Result<u16, RadiotapError> {
         if self.offset + 2 > self.data.len() {
-            return Err("Buffer overflow reading u16".to_string());
+            return Err(self.truncated(field));
         }
         let value = LittleEndian::read_u16(&self.data[self.offset..]);
         self.offset += 2;
         Ok(value)
     }
 
-    fn read_u64(&mut self) -> Result<u64, String> {
+    fn read_u32(&mut self, field: &'static str) -> Result<u32, RadiotapError> {
+        if self.offset + 4 > self.data.len() {
+            return Err(self.truncated(field));
+        }
+        let value = LittleEndian::read_u32(&self.data[self.offset..]);
+        self.offset += 4;
+        Ok(value)
+    }
+
+    fn read_u64(&mut self, field: &'static str) -> Result<u64, RadiotapError> {
         if self.offset + 8 > self.data.len() {
-            return Err("Buffer overflow reading u64".to_string());
+            return Err(self.truncated(field));
         }
         let value = LittleEndian::read_u64(&self.data[self.offset..]);
         self.offset += 8;
         Ok(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A radiotap header carrying only the flags field, then a beacon for
+    /// "home" on channel 6, then `trailer`.
+    fn beacon(flags: u8, trailer: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0, 0, 9, 0, 0x02, 0, 0, 0, flags];
+        // Frame control (beacon), duration, addresses, sequence control
+        frame.extend_from_slice(&[0x80, 0x00, 0, 0]);
+        frame.extend_from_slice(&[0xFF; 6]);
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&[0, 0]);
+        // Timestamp, beacon interval (100 TU), capability info
+        frame.extend_from_slice(&[0; 8]);
+        frame.extend_from_slice(&[100, 0, 0x11, 0x04]);
+        frame.extend_from_slice(&[0, 4, b'h', b'o', b'm', b'e']);
+        frame.extend_from_slice(&[3, 1, 6]);
+        frame.extend_from_slice(trailer);
+        frame
+    }
+
+    // An FCS that happens to read as a two-byte vendor element
+    const FCS: [u8; 4] = [0xDD, 0x02, 0xAA, 0xBB];
+
+    #[test]
+    fn trailing_fcs_is_not_parsed_as_an_element() {
+        let data = beacon(FLAG_FCS_AT_END, &FCS);
+        let frame = RadiotapParser::new(&data).parse_wifi_frame().unwrap();
+
+        assert_eq!(frame.ssid.as_deref(), Some("home"));
+        assert_eq!(frame.ssid_state, SsidState::Broadcast("home".to_string()));
+        assert_eq!(frame.channel, Some(6));
+        assert_eq!(frame.beacon_interval, Some(100));
+        let ids: Vec<u8> = frame.information_elements.iter().map(|ie| ie.id).collect();
+        assert_eq!(ids, [0, 3]);
+    }
+
+    #[test]
+    fn without_the_fcs_flag_trailing_bytes_are_elements() {
+        let data = beacon(0, &FCS);
+        let frame = RadiotapParser::new(&data).parse_wifi_frame().unwrap();
+
+        let ids: Vec<u8> = frame.information_elements.iter().map(|ie| ie.id).collect();
+        assert_eq!(ids, [0, 3, 0xDD]);
+    }
+
+    /// RSN element body: CCMP group and pairwise ciphers, PSK and SAE AKMs,
+    /// and MFPC set in the capabilities.
+    const RSN_BODY: [u8; 24] = [
+        1, 0, 0x00, 0x0F, 0xAC, 4, 1, 0, 0x00, 0x0F, 0xAC, 4, 2, 0, 0x00, 0x0F, 0xAC, 2, 0x00,
+        0x0F, 0xAC, 8, 0x80, 0x00,
+    ];
+
+    fn assert_rsn_intact(frame: &WiFiFrame) {
+        let rsn = frame
+            .information_elements
+            .iter()
+            .find(|ie| ie.id == 48)
+            .expect("RSN element");
+        assert_eq!(rsn.data, RSN_BODY);
+
+        let security =
+            crate::security::classify(frame.capability_info, &frame.information_elements);
+        assert_eq!(security.label, "WPA2/WPA3 Transition");
+        assert_eq!(security.pmf, crate::security::PmfStatus::Capable);
+    }
+
+    #[test]
+    fn rsn_element_before_the_fcs_parses_intact() {
+        let mut trailer = vec![48, RSN_BODY.len() as u8];
+        trailer.extend_from_slice(&RSN_BODY);
+        trailer.extend_from_slice(&FCS);
+
+        let with_flag = beacon(FLAG_FCS_AT_END, &trailer);
+        let frame = RadiotapParser::new(&with_flag).parse_wifi_frame().unwrap();
+        let ids: Vec<u8> = frame.information_elements.iter().map(|ie| ie.id).collect();
+        assert_eq!(ids, [0, 3, 48]);
+        assert_rsn_intact(&frame);
+
+        let without_flag = beacon(0, &trailer);
+        let frame = RadiotapParser::new(&without_flag)
+            .parse_wifi_frame()
+            .unwrap();
+        let ids: Vec<u8> = frame.information_elements.iter().map(|ie| ie.id).collect();
+        assert_eq!(ids, [0, 3, 48, 0xDD]);
+        assert_rsn_intact(&frame);
+    }
+}
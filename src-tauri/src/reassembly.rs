@@ -0,0 +1,239 @@
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+// Bounds that keep a flood of never-completed fragments from growing memory
+const MAX_PENDING_DATAGRAMS: usize = 64;
+const MAX_DATAGRAM_SIZE: usize = 65535;
+const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Identifies the datagram a fragment belongs to (RFC 791).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FragmentKey {
+    pub src: Ipv4Addr,
+    pub dst: Ipv4Addr,
+    pub id: u16,
+    pub protocol: u8,
+}
+
+struct PendingDatagram {
+    // (byte offset, fragment payload), kept sorted by offset
+    fragments: Vec<(usize, Vec<u8>)>,
+    total_len: Option<usize>,
+    created: Instant,
+}
+
+impl PendingDatagram {
+    fn new() -> Self {
+        PendingDatagram {
+            fragments: Vec::new(),
+            total_len: None,
+            created: Instant::now(),
+        }
+    }
+
+    /// Adds a fragment, returning false if it conflicts with what we already
+    /// have (overlapping data, a second "last" fragment, data past the end
+    /// of the datagram, or oversize).
+    fn insert(&mut self, offset: usize, payload: &[u8], last: bool) -> bool {
+        let end = offset + payload.len();
+        if end > MAX_DATAGRAM_SIZE {
+            return false;
+        }
+
+        if last {
+            if self.total_len.is_some_and(|len| len != end) {
+                return false;
+            }
+            if self.fragments.iter().any(|(o, p)| o + p.len() > end) {
+                return false;
+            }
+            self.total_len = Some(end);
+        } else if self.total_len.is_some_and(|len| end > len) {
+            return false;
+        }
+
+        for (existing_offset, existing) in &self.fragments {
+            let existing_end = existing_offset + existing.len();
+            if offset < existing_end && *existing_offset < end {
+                // Exact retransmissions are harmless, anything else is suspect
+                return *existing_offset == offset && existing.as_slice() == payload;
+            }
+        }
+
+        let index = self
+            .fragments
+            .iter()
+            .position(|(o, _)| *o > offset)
+            .unwrap_or(self.fragments.len());
+        self.fragments.insert(index, (offset, payload.to_vec()));
+        true
+    }
+
+    fn assemble(&self) -> Option<Vec<u8>> {
+        let total_len = self.total_len?;
+        let mut datagram = Vec::with_capacity(total_len);
+
+        for (offset, payload) in &self.fragments {
+            if *offset != datagram.len() {
+                // Still missing a fragment
+                return None;
+            }
+            datagram.extend_from_slice(payload);
+        }
+
+        (datagram.len() == total_len).then_some(datagram)
+    }
+}
+
+/// Reassembles fragmented IPv4 datagrams.
+#[derive(Default)]
+pub struct Ipv4Reassembler {
+    pending: HashMap<FragmentKey, PendingDatagram>,
+}
+
+impl Ipv4Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one fragment's payload (the bytes after the IP header).
+    /// `offset` is in bytes, `more_fragments` is the MF flag.
+    ///
+    /// Returns the full transport payload once every fragment has arrived.
+    pub fn add_fragment(
+        &mut self,
+        key: FragmentKey,
+        offset: usize,
+        more_fragments: bool,
+        payload: &[u8],
+    ) -> Option<Vec<u8>> {
+        self.expire();
+
+        if !self.pending.contains_key(&key) && self.pending.len() >= MAX_PENDING_DATAGRAMS {
+            warn!(
+                "Dropping fragment of {:?}: {} datagrams already pending",
+                key, MAX_PENDING_DATAGRAMS
+            );
+            return None;
+        }
+
+        let datagram = self.pending.entry(key).or_insert_with(PendingDatagram::new);
+        if !datagram.insert(offset, payload, !more_fragments) {
+            warn!("Discarding datagram {:?} with inconsistent fragments", key);
+            self.pending.remove(&key);
+            return None;
+        }
+
+        let assembled = datagram.assemble();
+        if assembled.is_some() {
+            debug!("Reassembled datagram {:?}", key);
+            self.pending.remove(&key);
+        }
+        assembled
+    }
+
+    fn expire(&mut self) {
+        self.pending
+            .retain(|_, datagram| datagram.created.elapsed() < FRAGMENT_TIMEOUT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: FragmentKey = FragmentKey {
+        src: Ipv4Addr::new(10, 0, 0, 2),
+        dst: Ipv4Addr::new(10, 0, 0, 9),
+        id: 0x1234,
+        protocol: 17,
+    };
+
+    /// 24 bytes split into three 8-byte fragments.
+    fn datagram() -> Vec<u8> {
+        (0..24).collect()
+    }
+
+    fn fragment(index: usize) -> (usize, bool, Vec<u8>) {
+        let offset = index * 8;
+        (offset, index < 2, datagram()[offset..offset + 8].to_vec())
+    }
+
+    fn feed(reassembler: &mut Ipv4Reassembler, indices: &[usize]) -> Option<Vec<u8>> {
+        let mut assembled = None;
+        for &index in indices {
+            let (offset, more, payload) = fragment(index);
+            assembled = reassembler.add_fragment(KEY, offset, more, &payload);
+        }
+        assembled
+    }
+
+    #[test]
+    fn in_order_fragments_reassemble() {
+        let mut reassembler = Ipv4Reassembler::new();
+        assert_eq!(feed(&mut reassembler, &[0, 1]), None);
+        assert_eq!(feed(&mut reassembler, &[2]), Some(datagram()));
+        assert!(reassembler.pending.is_empty());
+    }
+
+    #[test]
+    fn out_of_order_fragments_reassemble() {
+        let mut reassembler = Ipv4Reassembler::new();
+        assert_eq!(feed(&mut reassembler, &[2, 0, 1]), Some(datagram()));
+    }
+
+    #[test]
+    fn exact_retransmission_is_harmless() {
+        let mut reassembler = Ipv4Reassembler::new();
+        assert_eq!(feed(&mut reassembler, &[0, 0, 2, 1]), Some(datagram()));
+    }
+
+    #[test]
+    fn overlapping_fragment_drops_the_datagram() {
+        let mut reassembler = Ipv4Reassembler::new();
+        feed(&mut reassembler, &[0]);
+        assert_eq!(reassembler.add_fragment(KEY, 4, true, &[0xFF; 8]), None);
+        assert!(reassembler.pending.is_empty());
+    }
+
+    #[test]
+    fn missing_fragment_holds_the_datagram() {
+        let mut reassembler = Ipv4Reassembler::new();
+        assert_eq!(feed(&mut reassembler, &[0, 2]), None);
+        assert_eq!(reassembler.pending.len(), 1);
+    }
+
+    #[test]
+    fn fragment_past_the_known_end_drops_the_datagram() {
+        let mut reassembler = Ipv4Reassembler::new();
+        feed(&mut reassembler, &[2]);
+        assert_eq!(reassembler.add_fragment(KEY, 24, true, &[0; 8]), None);
+        assert!(reassembler.pending.is_empty());
+    }
+
+    #[test]
+    fn last_fragment_before_received_data_drops_the_datagram() {
+        let mut reassembler = Ipv4Reassembler::new();
+        feed(&mut reassembler, &[1]);
+        // Says the datagram ends at 8, but bytes up to 16 already arrived
+        let (offset, _, payload) = fragment(0);
+        assert_eq!(reassembler.add_fragment(KEY, offset, false, &payload), None);
+        assert!(reassembler.pending.is_empty());
+    }
+
+    #[test]
+    fn stale_datagrams_expire() {
+        let mut reassembler = Ipv4Reassembler::new();
+        feed(&mut reassembler, &[0]);
+        let Some(created) = Instant::now().checked_sub(FRAGMENT_TIMEOUT) else {
+            return;
+        };
+        reassembler.pending.get_mut(&KEY).unwrap().created = created;
+
+        // The rest arrives too late to complete the expired datagram
+        assert_eq!(feed(&mut reassembler, &[1, 2]), None);
+        assert_eq!(reassembler.pending.len(), 1);
+    }
+}
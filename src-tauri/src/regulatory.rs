@@ -0,0 +1,226 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::radiotap::InformationElement;
+use crate::spectrum::Band;
+use crate::wifi_scanner::WiFiNetwork;
+
+const IE_COUNTRY: u8 = 7;
+// First-channel values from 201 up start an operating extension triplet
+const OPERATING_EXTENSION_ID: u8 = 201;
+
+/// Regulatory information from a beacon's Country element.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq, Eq)]
+pub struct CountryInfo {
+    /// ISO 3166 alpha-2 code, e.g. "US"
+    pub code: String,
+    /// 20 MHz channels covered by the element's subband triplets
+    pub channels: Vec<u32>,
+}
+
+/// Parses the Country element (ID 7): a 3-byte country string followed by
+/// (first channel, number of channels, max power) triplets.
+pub fn parse_country(elements: &[InformationElement]) -> Option<CountryInfo> {
+    let element = elements.iter().find(|ie| ie.id == IE_COUNTRY)?;
+    if element.data.len() < 3 {
+        return None;
+    }
+
+    let code: String = element.data[..2]
+        .iter()
+        .map(|&b| b as char)
+        .collect::<String>()
+        .to_uppercase();
+    if !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut channels = Vec::new();
+    for triplet in element.data[3..].chunks_exact(3) {
+        let (first, count) = (triplet[0], triplet[1]);
+        if first >= OPERATING_EXTENSION_ID || first == 0 {
+            continue;
+        }
+        // Channels are consecutive in 2.4 GHz and 20 MHz (4 numbers) apart above it
+        let step = if first <= 14 { 1 } else { 4 };
+        channels.extend((0..count as u32).map(|i| first as u32 + i * step));
+    }
+    channels.sort_unstable();
+    channels.dedup();
+
+    Some(CountryInfo { code, channels })
+}
+
+fn range(first: u32, last: u32, step: usize) -> impl Iterator<Item = u32> {
+    (first..=last).step_by(step)
+}
+
+/// 20 MHz channels permitted for access points in `country` on `band`, or
+/// None when the country or band isn't in the built-in table. Covers the
+/// common domains only, without DFS or indoor-only distinctions.
+pub fn allowed_channels(country: &str, band: Band) -> Option<Vec<u32>> {
+    const EU: &[&str] = &[
+        "AT", "BE", "CH", "CZ", "DE", "DK", "ES", "FI", "FR", "GB", "GR", "IE", "IT", "NL", "NO",
+        "PL", "PT", "RO", "SE",
+    ];
+
+    let channels: Vec<u32> = match (country, band) {
+        ("US" | "CA", Band::TwoPointFour) => range(1, 11, 1).collect(),
+        ("JP", Band::TwoPointFour) => range(1, 14, 1).collect(),
+        (c, Band::TwoPointFour) if EU.contains(&c) || matches!(c, "AU" | "CN" | "NZ") => {
+            range(1, 13, 1).collect()
+        }
+        ("US" | "CA", Band::Five) => range(36, 64, 4)
+            .chain(range(100, 144, 4))
+            .chain(range(149, 165, 4))
+            .collect(),
+        ("JP", Band::Five) => range(36, 64, 4).chain(range(100, 144, 4)).collect(),
+        ("CN", Band::Five) => range(36, 64, 4).chain(range(149, 165, 4)).collect(),
+        ("AU" | "NZ", Band::Five) => range(36, 64, 4)
+            .chain(range(100, 116, 4))
+            .chain(range(132, 144, 4))
+            .chain(range(149, 165, 4))
+            .collect(),
+        (c, Band::Five) if EU.contains(&c) => range(36, 64, 4).chain(range(100, 140, 4)).collect(),
+        _ => return None,
+    };
+    Some(channels)
+}
+
+/// An AP operating on a channel its advertised regulatory domain doesn't allow.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegulatoryViolation {
+    pub bssid: String,
+    pub ssid: String,
+    pub country: String,
+    pub band: Band,
+    pub channel: u32,
+    /// Channels the country permits on this band
+    pub expected_channels: Vec<u32>,
+    /// Channels the AP's Country element claims are allowed
+    pub advertised_channels: Vec<u32>,
+}
+
+/// Flags networks whose channel is outside the allowed set for the country
+/// code in their beacons. Networks without a Country element, or in a
+/// country or band without a table, are skipped.
+pub fn check_compliance(networks: &[WiFiNetwork]) -> Vec<RegulatoryViolation> {
+    networks
+        .iter()
+        .filter_map(|network| {
+            let country = network.country.as_ref()?;
+            let band = Band::from_frequency(network.frequency)?;
+            let expected_channels = allowed_channels(&country.code, band)?;
+            if expected_channels.contains(&network.channel) {
+                return None;
+            }
+
+            Some(RegulatoryViolation {
+                bssid: network.bssid.clone(),
+                ssid: network.ssid.clone(),
+                country: country.code.clone(),
+                band,
+                channel: network.channel,
+                expected_channels,
+                advertised_channels: country.channels.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiotap::SsidState;
+
+    fn country_element(data: &[u8]) -> InformationElement {
+        InformationElement {
+            id: IE_COUNTRY,
+            data: data.to_vec(),
+        }
+    }
+
+    fn network(code: &str, frequency: u32, channel: u8) -> WiFiNetwork {
+        let mut network = WiFiNetwork::new(
+            "00:11:22:33:44:55".to_string(),
+            "office".to_string(),
+            SsidState::Broadcast("office".to_string()),
+            frequency,
+            Some(channel),
+        );
+        network.country = Some(CountryInfo {
+            code: code.to_string(),
+            channels: Vec::new(),
+        });
+        network
+    }
+
+    #[test]
+    fn us_stops_at_channel_11() {
+        let channels = allowed_channels("US", Band::TwoPointFour).unwrap();
+        assert_eq!(channels, (1..=11).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn europe_allows_13_and_japan_14() {
+        let de = allowed_channels("DE", Band::TwoPointFour).unwrap();
+        assert_eq!(de.last(), Some(&13));
+        let jp = allowed_channels("JP", Band::TwoPointFour).unwrap();
+        assert_eq!(jp.last(), Some(&14));
+    }
+
+    #[test]
+    fn five_ghz_subbands_differ_by_country() {
+        let us = allowed_channels("US", Band::Five).unwrap();
+        assert!(us.contains(&144) && us.contains(&165));
+        let de = allowed_channels("DE", Band::Five).unwrap();
+        assert!(de.contains(&140) && !de.contains(&144) && !de.contains(&149));
+        let au = allowed_channels("AU", Band::Five).unwrap();
+        assert!(!au.contains(&120) && au.contains(&132));
+    }
+
+    #[test]
+    fn unknown_countries_and_bands_have_no_table() {
+        assert_eq!(allowed_channels("ZZ", Band::TwoPointFour), None);
+        assert_eq!(allowed_channels("US", Band::Six), None);
+    }
+
+    #[test]
+    fn parse_country_triplets() {
+        // "us " with 2.4 GHz channels 1-11 and 5 GHz channels 36-48, then an
+        // operating extension triplet
+        let info =
+            parse_country(&[country_element(b"us \x01\x0b\x1e\x24\x04\x17\xc9\x01\x00")]).unwrap();
+        assert_eq!(info.code, "US");
+        assert_eq!(
+            info.channels,
+            (1..=11).chain([36, 40, 44, 48]).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn parse_country_rejects_bad_codes() {
+        assert_eq!(parse_country(&[country_element(b"u")]), None);
+        assert_eq!(parse_country(&[country_element(b"1A ")]), None);
+        assert_eq!(parse_country(&[]), None);
+    }
+
+    #[test]
+    fn channels_outside_the_country_table_are_flagged() {
+        let networks = [
+            network("US", 2467, 12),
+            network("DE", 2467, 12),
+            network("JP", 2484, 14),
+            network("US", 2484, 14),
+            // No table, so nothing to check against
+            network("ZZ", 2467, 12),
+        ];
+
+        let violations = check_compliance(&networks);
+        let flagged: Vec<(&str, u32)> = violations
+            .iter()
+            .map(|v| (v.country.as_str(), v.channel))
+            .collect();
+        assert_eq!(flagged, [("US", 12), ("US", 14)]);
+    }
+}
@@ -0,0 +1,361 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::WifiError;
+use crate::export::open_capture_file;
+use crate::packet_sniffer::{parse_captured_packet, PacketInfo, ParseDepth, CAPTURE_PRECISION};
+use crate::reassembly::Ipv4Reassembler;
+
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 10.0;
+// Replays are held in memory so seeking doesn't have to re-read the file
+const MAX_REPLAY_PACKETS: usize = 500_000;
+// Longest the replay thread sleeps before checking for pause, seek or stop
+const CONTROL_POLL: Duration = Duration::from_millis(50);
+
+/// A loaded capture file, as reported to the frontend.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplayInfo {
+    pub path: String,
+    pub packets: usize,
+    /// First and last packet, in milliseconds since the epoch
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Where a replay is and how it's being played.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplayStatus {
+    pub playing: bool,
+    pub speed: f64,
+    /// Packets emitted so far
+    pub position: usize,
+    pub packets: usize,
+    /// Capture timestamp of the next packet, in milliseconds since the epoch
+    pub next_timestamp_ms: Option<u64>,
+}
+
+pub enum ReplayEvent {
+    Packet(Box<PacketInfo>),
+    Finished,
+}
+
+struct ReplayControl {
+    playing: bool,
+    speed: f64,
+    seek_to_us: Option<u64>,
+    stopped: bool,
+    position: usize,
+    /// Bumped on every change so the replay thread re-anchors its clock
+    generation: u64,
+}
+
+/// Control state of the loaded replay and the timestamps of its packets.
+type LoadedReplay = (Arc<Mutex<ReplayControl>>, Arc<Vec<u64>>);
+
+/// The packets of a capture file, parsed and in timestamp order, ready to
+/// be replayed.
+pub struct ReplayFile {
+    path: String,
+    packets: Vec<(u64, PacketInfo)>,
+}
+
+impl ReplayFile {
+    /// Reads and parses every packet of the file at `path`. This reads the
+    /// whole file, so call it off the async runtime.
+    pub fn read(path: &str) -> Result<Self, WifiError> {
+        let mut cap = open_capture_file(Path::new(path))?;
+        let linktype = cap.get_datalink();
+        let mut reassembler = Ipv4Reassembler::new();
+        let mut packets: Vec<(u64, PacketInfo)> = Vec::new();
+
+        loop {
+            let packet = match cap.next_packet() {
+                Ok(packet) => packet,
+                Err(pcap::Error::NoMorePackets) => break,
+                Err(e) => return Err(e.into()),
+            };
+            if packets.len() >= MAX_REPLAY_PACKETS {
+                return Err(WifiError::CaptureError(format!(
+                    "{} has more than {} packets, too many to replay",
+                    path, MAX_REPLAY_PACKETS
+                )));
+            }
+            let ts = packet.header.ts;
+            let ts_us = ts.tv_sec as u64 * 1_000_000 + ts.tv_usec as u64;
+            let parsed = parse_captured_packet(
+                &packet,
+                linktype,
+                CAPTURE_PRECISION,
+                &mut reassembler,
+                ParseDepth::Application,
+            );
+            packets.push((ts_us, parsed));
+        }
+        if packets.is_empty() {
+            return Err(WifiError::CaptureError(format!(
+                "{} contains no packets",
+                path
+            )));
+        }
+
+        // Files merged from several interfaces aren't always in order
+        packets.sort_by_key(|(ts_us, _)| *ts_us);
+        Ok(ReplayFile {
+            path: path.to_string(),
+            packets,
+        })
+    }
+}
+
+/// Plays a capture file back as "packet" events with the gaps between
+/// packets taken from their original timestamps, scaled by the speed.
+#[derive(Default)]
+pub struct Replay {
+    current: Mutex<Option<LoadedReplay>>,
+}
+
+impl Replay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a paused replay of `file`, replacing any earlier one.
+    pub fn load<F>(&self, file: ReplayFile, mut emit: F) -> ReplayInfo
+    where
+        F: FnMut(ReplayEvent) + Send + 'static,
+    {
+        let ReplayFile { path, packets } = file;
+        let timestamps: Arc<Vec<u64>> = Arc::new(packets.iter().map(|(ts, _)| *ts).collect());
+        let replay_info = ReplayInfo {
+            path: path.clone(),
+            packets: packets.len(),
+            start_ms: timestamps[0] / 1000,
+            end_ms: timestamps[timestamps.len() - 1] / 1000,
+        };
+
+        let control = Arc::new(Mutex::new(ReplayControl {
+            playing: false,
+            speed: 1.0,
+            seek_to_us: None,
+            stopped: false,
+            position: 0,
+            generation: 0,
+        }));
+        let previous = self
+            .current
+            .lock()
+            .unwrap()
+            .replace((Arc::clone(&control), Arc::clone(&timestamps)));
+        if let Some((previous, _)) = previous {
+            previous.lock().unwrap().stopped = true;
+        }
+
+        info!("Loaded {} packets from {} for replay", packets.len(), path);
+        thread::spawn(move || run(control, packets, &mut emit));
+        replay_info
+    }
+
+    /// Plays from the current position, or from the start again once the
+    /// replay has finished.
+    pub fn play(&self) -> Result<(), WifiError> {
+        let current = self.current.lock().unwrap();
+        let (control, timestamps) = current.as_ref().ok_or_else(not_loaded)?;
+        let mut control = control.lock().unwrap();
+        if control.position >= timestamps.len() && control.seek_to_us.is_none() {
+            control.position = 0;
+        }
+        control.playing = true;
+        control.generation += 1;
+        Ok(())
+    }
+
+    pub fn pause(&self) -> Result<(), WifiError> {
+        self.update(|control| control.playing = false)
+    }
+
+    pub fn set_speed(&self, speed: f64) -> Result<(), WifiError> {
+        check_speed(speed)?;
+        self.update(|control| control.speed = speed)
+    }
+
+    /// Moves the replay to the first packet captured at or after
+    /// `timestamp_ms` milliseconds since the epoch.
+    pub fn seek(&self, timestamp_ms: u64) -> Result<(), WifiError> {
+        self.update(|control| control.seek_to_us = Some(timestamp_ms.saturating_mul(1000)))
+    }
+
+    /// Stops the replay thread and drops the loaded packets. Returns false
+    /// if nothing was loaded.
+    pub fn stop(&self) -> bool {
+        match self.current.lock().unwrap().take() {
+            Some((control, _)) => {
+                control.lock().unwrap().stopped = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn status(&self) -> Result<ReplayStatus, WifiError> {
+        let current = self.current.lock().unwrap();
+        let (control, timestamps) = current.as_ref().ok_or_else(not_loaded)?;
+        let control = control.lock().unwrap();
+        Ok(ReplayStatus {
+            playing: control.playing,
+            speed: control.speed,
+            position: control.position,
+            packets: timestamps.len(),
+            next_timestamp_ms: timestamps.get(control.position).map(|ts| ts / 1000),
+        })
+    }
+
+    fn update<F>(&self, change: F) -> Result<(), WifiError>
+    where
+        F: FnOnce(&mut ReplayControl),
+    {
+        let current = self.current.lock().unwrap();
+        let (control, _) = current.as_ref().ok_or_else(not_loaded)?;
+        let mut control = control.lock().unwrap();
+        change(&mut control);
+        control.generation += 1;
+        Ok(())
+    }
+}
+
+fn not_loaded() -> WifiError {
+    WifiError::CaptureError("No capture is loaded for replay".to_string())
+}
+
+fn run<F>(control: Arc<Mutex<ReplayControl>>, packets: Vec<(u64, PacketInfo)>, emit: &mut F)
+where
+    F: FnMut(ReplayEvent),
+{
+    let mut seen_generation = u64::MAX;
+    // Wall-clock time the packet at `position` is measured from
+    let mut anchor = (Instant::now(), 0u64);
+
+    loop {
+        let (position, speed) = {
+            let mut control = control.lock().unwrap();
+            if control.stopped {
+                break;
+            }
+            if let Some(seek_to_us) = control.seek_to_us.take() {
+                control.position = packets.partition_point(|(ts_us, _)| *ts_us < seek_to_us);
+            }
+            if control.generation != seen_generation {
+                seen_generation = control.generation;
+                if let Some((ts_us, _)) = packets.get(control.position) {
+                    anchor = (Instant::now(), *ts_us);
+                }
+            }
+            if !control.playing || control.position >= packets.len() {
+                drop(control);
+                thread::sleep(CONTROL_POLL);
+                continue;
+            }
+            (control.position, control.speed)
+        };
+
+        let (ts_us, packet) = &packets[position];
+        let offset = Duration::from_micros(ts_us.saturating_sub(anchor.1)).div_f64(speed);
+        let waited = anchor.0.elapsed();
+        if waited < offset {
+            thread::sleep((offset - waited).min(CONTROL_POLL));
+            continue;
+        }
+
+        emit(ReplayEvent::Packet(Box::new(packet.clone())));
+        let mut control = control.lock().unwrap();
+        // A seek or reload while emitting takes priority over advancing
+        if control.position == position && control.seek_to_us.is_none() {
+            control.position += 1;
+            if control.position == packets.len() {
+                control.playing = false;
+                drop(control);
+                emit(ReplayEvent::Finished);
+            }
+        }
+    }
+
+    info!("Replay stopped");
+}
+
+/// Rejects speeds outside `MIN_SPEED..=MAX_SPEED`.
+pub fn check_speed(speed: f64) -> Result<(), WifiError> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        return Err(WifiError::ParseError(format!(
+            "Replay speed must be between {}x and {}x, got {}x",
+            MIN_SPEED, MAX_SPEED, speed
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::packet_sniffer::ParsedPacket;
+    use std::sync::mpsc::{channel, Receiver};
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    /// Starts a paused replay of `count` packets captured at the same time,
+    /// returning the events it emits.
+    fn load(replay: &Replay, count: usize) -> Receiver<ReplayEvent> {
+        let packet = PacketInfo::from(ParsedPacket {
+            layers: Vec::new(),
+            length: 60,
+            timestamp: 0,
+            timestamp_iso: String::new(),
+            reassembled: false,
+            malformed: None,
+        });
+        let file = ReplayFile {
+            path: "test.pcap".to_string(),
+            packets: vec![(1_000_000, packet); count],
+        };
+        let (tx, rx) = channel();
+        replay.load(file, move |event| {
+            let _ = tx.send(event);
+        });
+        rx
+    }
+
+    /// Waits for the replay to finish, returning how many packets it emitted.
+    fn packets_until_finished(events: &Receiver<ReplayEvent>) -> usize {
+        let mut packets = 0;
+        loop {
+            match events.recv_timeout(WAIT).expect("replay event") {
+                ReplayEvent::Packet(_) => packets += 1,
+                ReplayEvent::Finished => return packets,
+            }
+        }
+    }
+
+    #[test]
+    fn play_after_finishing_starts_over() {
+        let replay = Replay::new();
+        let events = load(&replay, 3);
+
+        replay.play().unwrap();
+        assert_eq!(packets_until_finished(&events), 3);
+        let status = replay.status().unwrap();
+        assert!(!status.playing);
+        assert_eq!(status.position, 3);
+
+        replay.play().unwrap();
+        assert_eq!(packets_until_finished(&events), 3);
+        replay.stop();
+    }
+
+    #[test]
+    fn play_without_a_replay_fails() {
+        assert!(Replay::new().play().is_err());
+    }
+}
@@ -0,0 +1,474 @@
+use log::{debug, info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::endpoints::IpScope;
+use crate::error::WifiError;
+use crate::packet_sniffer::PacketInfo;
+
+pub const DEFAULT_CACHE_SIZE: usize = 4096;
+const FALLBACK_SERVER: &str = "1.1.1.1:53";
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Lookups waiting for the resolver thread; more are dropped and retried
+/// when the address shows up again.
+const MAX_QUEUED_LOOKUPS: usize = 256;
+// Bounds following compression pointers in a malicious response
+const MAX_NAME_POINTERS: usize = 16;
+
+const DNS_TYPE_PTR: u16 = 12;
+const DNS_CLASS_IN: u16 = 1;
+const DNS_RCODE_NO_ERROR: u8 = 0;
+const DNS_RCODE_NX_DOMAIN: u8 = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResolverConfig {
+    pub enabled: bool,
+    /// DNS server PTR queries are sent to, port 53 unless given
+    pub server: SocketAddr,
+    /// Addresses whose answer, or lack of one, is remembered. Failed lookups
+    /// aren't cached, so they're retried when the address shows up again.
+    pub cache_size: usize,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        ResolverConfig {
+            enabled: false,
+            server: system_nameserver()
+                .unwrap_or_else(|| FALLBACK_SERVER.parse().expect("valid fallback server")),
+            cache_size: DEFAULT_CACHE_SIZE,
+        }
+    }
+}
+
+/// Payload of the "host_resolved" event, sent when a lookup finishes after
+/// packets with the address have already gone out.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct HostResolved {
+    pub ip: String,
+    pub host: String,
+}
+
+#[derive(Default)]
+struct HostCache {
+    /// None when the server said the address has no PTR record
+    hosts: HashMap<IpAddr, Option<String>>,
+    order: VecDeque<IpAddr>,
+    pending: HashSet<IpAddr>,
+}
+
+impl HostCache {
+    fn insert(&mut self, ip: IpAddr, host: Option<String>, capacity: usize) {
+        self.pending.remove(&ip);
+        if self.hosts.insert(ip, host).is_none() {
+            self.order.push_back(ip);
+        }
+        while self.order.len() > capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hosts.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Opt-in PTR lookups for the public addresses in captured packets. Lookups
+/// run on their own thread so the capture never waits on the network;
+/// packets get a hostname once the address is cached.
+#[derive(Default)]
+pub struct ReverseDns {
+    config: Arc<Mutex<ResolverConfig>>,
+    cache: Arc<Mutex<HostCache>>,
+    queue: Mutex<Option<SyncSender<IpAddr>>>,
+}
+
+impl ReverseDns {
+    pub fn config(&self) -> ResolverConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn configure(&self, config: ResolverConfig) -> Result<(), WifiError> {
+        if config.cache_size == 0 {
+            return Err(WifiError::ParseError(
+                "The reverse DNS cache needs room for at least one address".to_string(),
+            ));
+        }
+        let server_changed = self.config.lock().unwrap().server != config.server;
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if server_changed {
+                *cache = HostCache::default();
+            } else {
+                let capacity = config.cache_size;
+                while cache.order.len() > capacity {
+                    if let Some(oldest) = cache.order.pop_front() {
+                        cache.hosts.remove(&oldest);
+                    }
+                }
+            }
+        }
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    /// Starts a resolver thread for a new capture, replacing the previous
+    /// one. `on_resolved` is called for every address a name was found for.
+    pub fn start<F>(&self, on_resolved: F)
+    where
+        F: Fn(HostResolved) + Send + 'static,
+    {
+        let (queue_tx, queue_rx) = sync_channel(MAX_QUEUED_LOOKUPS);
+        // Dropping the old sender ends the old thread once its queue drains
+        *self.queue.lock().unwrap() = Some(queue_tx);
+        self.cache.lock().unwrap().pending.clear();
+
+        let cache = Arc::clone(&self.cache);
+        let config = Arc::clone(&self.config);
+        thread::spawn(move || resolve_queued(queue_rx, cache, config, on_resolved));
+    }
+
+    /// Fills in the hostnames of the packet's public addresses that are
+    /// cached, and queues lookups for the rest.
+    pub fn annotate(&self, packet: &mut PacketInfo) {
+        if !self.config.lock().unwrap().enabled {
+            return;
+        }
+
+        packet.src_host = self.lookup(packet.src_ip.as_deref(), packet.src_ip_scope);
+        packet.dst_host = self.lookup(packet.dst_ip.as_deref(), packet.dst_ip_scope);
+    }
+
+    fn lookup(&self, ip: Option<&str>, scope: Option<IpScope>) -> Option<String> {
+        if scope != Some(IpScope::Public) {
+            return None;
+        }
+        let ip: IpAddr = ip?.parse().ok()?;
+
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(host) = cache.hosts.get(&ip) {
+            return host.clone();
+        }
+        if cache.pending.contains(&ip) {
+            return None;
+        }
+        if let Some(queue) = self.queue.lock().unwrap().as_ref() {
+            match queue.try_send(ip) {
+                Ok(()) => {
+                    cache.pending.insert(ip);
+                }
+                Err(TrySendError::Full(_)) => debug!("Reverse DNS queue full, skipping {}", ip),
+                Err(TrySendError::Disconnected(_)) => {}
+            }
+        }
+        None
+    }
+}
+
+fn resolve_queued<F>(
+    queue: Receiver<IpAddr>,
+    cache: Arc<Mutex<HostCache>>,
+    config: Arc<Mutex<ResolverConfig>>,
+    on_resolved: F,
+) where
+    F: Fn(HostResolved),
+{
+    info!("Reverse DNS resolver started");
+    while let Ok(ip) = queue.recv() {
+        // Read per lookup so configuration changes apply to a running capture
+        let ResolverConfig {
+            server, cache_size, ..
+        } = config.lock().unwrap().clone();
+        let host = match query_ptr(ip, server) {
+            Ok(host) => host,
+            Err(e) => {
+                debug!("PTR lookup for {} failed: {}", ip, e);
+                cache.lock().unwrap().pending.remove(&ip);
+                continue;
+            }
+        };
+        cache.lock().unwrap().insert(ip, host.clone(), cache_size);
+        if let Some(host) = host {
+            on_resolved(HostResolved {
+                ip: ip.to_string(),
+                host,
+            });
+        }
+    }
+    debug!("Reverse DNS resolver stopped");
+}
+
+/// Sends a PTR query for `ip` to `server` and waits up to `LOOKUP_TIMEOUT`
+/// for the answer. Ok(None) means the server says there's no name.
+fn query_ptr(ip: IpAddr, server: SocketAddr) -> std::io::Result<Option<String>> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        "0.0.0.0:0".parse().expect("valid bind address")
+    } else {
+        "[::]:0".parse().expect("valid bind address")
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.connect(server)?;
+
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u16)
+        .unwrap_or(0);
+    socket.send(&ptr_query(id, &ptr_name(ip)))?;
+
+    // Stray replies don't extend the wait
+    let deadline = Instant::now() + LOOKUP_TIMEOUT;
+    let mut response = [0u8; 512];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        let len = socket.recv(&mut response)?;
+        // Stray answers to an earlier, timed-out query carry another id
+        if len >= 2 && u16::from_be_bytes([response[0], response[1]]) == id {
+            return parse_ptr_response(&response[..len])
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+        }
+    }
+}
+
+/// The in-addr.arpa or ip6.arpa name PTR records for `ip` live under.
+fn ptr_name(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, d] = ip.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(ip) => {
+            let mut labels: Vec<String> = ip
+                .octets()
+                .iter()
+                .flat_map(|byte| [byte >> 4, byte & 0x0F])
+                .map(|nibble| format!("{:x}", nibble))
+                .collect();
+            labels.reverse();
+            format!("{}.ip6.arpa", labels.join("."))
+        }
+    }
+}
+
+fn ptr_query(id: u16, name: &str) -> Vec<u8> {
+    // Header: id, recursion desired, one question
+    let mut query = Vec::with_capacity(18 + name.len());
+    query.extend_from_slice(&id.to_be_bytes());
+    query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&DNS_TYPE_PTR.to_be_bytes());
+    query.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+    query
+}
+
+/// The first PTR target in a DNS response. Ok(None) for NXDOMAIN and for
+/// answers without a PTR record; errors for server failures and responses
+/// that can't be parsed, which say nothing about the address.
+fn parse_ptr_response(data: &[u8]) -> Result<Option<String>, &'static str> {
+    const MALFORMED: &str = "malformed DNS response";
+    if data.len() < 12 {
+        return Err(MALFORMED);
+    }
+    match data[3] & 0x0F {
+        DNS_RCODE_NO_ERROR => {}
+        DNS_RCODE_NX_DOMAIN => return Ok(None),
+        _ => return Err("DNS server failed the query"),
+    }
+    let question_count = u16::from_be_bytes([data[4], data[5]]);
+    let answer_count = u16::from_be_bytes([data[6], data[7]]);
+
+    let mut offset = 12;
+    for _ in 0..question_count {
+        // Name, then type (2) and class (2)
+        offset = read_name(data, offset).ok_or(MALFORMED)?.1 + 4;
+    }
+    for _ in 0..answer_count {
+        let (_, name_end) = read_name(data, offset).ok_or(MALFORMED)?;
+        // Type (2), class (2), TTL (4), data length (2)
+        let fixed = data.get(name_end..name_end + 10).ok_or(MALFORMED)?;
+        let record_type = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let rdata_start = name_end + 10;
+        let rdata_end = rdata_start + u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        if record_type == DNS_TYPE_PTR {
+            let (host, _) = read_name(data, rdata_start).ok_or(MALFORMED)?;
+            return Ok((!host.is_empty()).then_some(host));
+        }
+        offset = rdata_end;
+    }
+    Ok(None)
+}
+
+/// Reads a possibly compressed DNS name at `offset`, returning it and the
+/// offset just past it.
+fn read_name(data: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        let len = *data.get(offset)?;
+        match len {
+            0 => {
+                let end = end.unwrap_or(offset + 1);
+                return Some((labels.join("."), end));
+            }
+            l if l & 0xC0 == 0xC0 => {
+                pointers += 1;
+                if pointers > MAX_NAME_POINTERS {
+                    return None;
+                }
+                let low = *data.get(offset + 1)?;
+                end.get_or_insert(offset + 2);
+                offset = (((l & 0x3F) as usize) << 8) | low as usize;
+            }
+            l => {
+                let label = data.get(offset + 1..offset + 1 + l as usize)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + l as usize;
+            }
+        }
+    }
+}
+
+/// The first nameserver in /etc/resolv.conf.
+fn system_nameserver() -> Option<SocketAddr> {
+    let resolv_conf = std::fs::read_to_string("/etc/resolv.conf").ok()?;
+    let server = resolv_conf.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        (fields.next() == Some("nameserver"))
+            .then(|| fields.next())
+            .flatten()
+    })?;
+    match server.parse::<IpAddr>() {
+        Ok(ip) => Some(SocketAddr::new(ip, 53)),
+        Err(e) => {
+            warn!("Ignoring nameserver {:?} in resolv.conf: {}", server, e);
+            None
+        }
+    }
+}
+
+/// Parses a DNS server given as an address, with or without a port.
+pub fn parse_server(server: &str) -> Result<SocketAddr, WifiError> {
+    server
+        .parse::<SocketAddr>()
+        .or_else(|_| server.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+        .map_err(|_| WifiError::ParseError(format!("Invalid DNS server {:?}", server)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response to `ptr_query(0x1234, name)` with `answers` appended.
+    fn response(rcode: u8, name: &str, answers: &[&[u8]]) -> Vec<u8> {
+        let mut data = ptr_query(0x1234, name);
+        data[2] = 0x81;
+        data[3] = 0x80 | rcode;
+        data[7] = answers.len() as u8;
+        for answer in answers {
+            data.extend_from_slice(answer);
+        }
+        data
+    }
+
+    /// A record whose name points at the question, with `rdata`.
+    fn record(record_type: u16, rdata: &[u8]) -> Vec<u8> {
+        let mut record = vec![0xC0, 12];
+        record.extend_from_slice(&record_type.to_be_bytes());
+        record.extend_from_slice(&DNS_CLASS_IN.to_be_bytes());
+        record.extend_from_slice(&300u32.to_be_bytes());
+        record.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        record.extend_from_slice(rdata);
+        record
+    }
+
+    const NAME: &str = "4.3.2.1.in-addr.arpa";
+
+    #[test]
+    fn ptr_names() {
+        assert_eq!(ptr_name("1.2.3.4".parse().unwrap()), NAME);
+        let v6 = ptr_name("2001:db8::1".parse().unwrap());
+        assert!(v6.starts_with("1.0.0.0.0.0.0.0."));
+        assert!(v6.ends_with(".8.b.d.0.1.0.0.2.ip6.arpa"));
+    }
+
+    #[test]
+    fn ptr_answer() {
+        let ptr = record(DNS_TYPE_PTR, b"\x04host\x07example\x03com\x00");
+        let data = response(DNS_RCODE_NO_ERROR, NAME, &[&ptr]);
+        assert_eq!(
+            parse_ptr_response(&data),
+            Ok(Some("host.example.com".to_string()))
+        );
+    }
+
+    #[test]
+    fn compressed_ptr_target() {
+        // "host" followed by a pointer to "in-addr.arpa" in the question
+        let in_addr = 12 + "4.3.2.1.".len() as u8;
+        let ptr = record(DNS_TYPE_PTR, &[4, b'h', b'o', b's', b't', 0xC0, in_addr]);
+        let data = response(DNS_RCODE_NO_ERROR, NAME, &[&ptr]);
+        assert_eq!(
+            parse_ptr_response(&data),
+            Ok(Some("host.in-addr.arpa".to_string()))
+        );
+    }
+
+    #[test]
+    fn records_before_the_ptr_are_skipped() {
+        let cname = record(5, &[0xC0, 12]);
+        let ptr = record(DNS_TYPE_PTR, b"\x04host\x00");
+        let data = response(DNS_RCODE_NO_ERROR, NAME, &[&cname, &ptr]);
+        assert_eq!(parse_ptr_response(&data), Ok(Some("host".to_string())));
+    }
+
+    #[test]
+    fn pointer_loops_are_malformed() {
+        // A PTR target that points at itself
+        let mut data = response(DNS_RCODE_NO_ERROR, NAME, &[]);
+        let rdata = data.len() + 12;
+        data[7] = 1;
+        data.extend_from_slice(&record(DNS_TYPE_PTR, &[0xC0, rdata as u8]));
+        assert!(parse_ptr_response(&data).is_err());
+    }
+
+    #[test]
+    fn truncated_answer_is_malformed() {
+        let ptr = record(DNS_TYPE_PTR, b"\x04host\x07example\x03com\x00");
+        let data = response(DNS_RCODE_NO_ERROR, NAME, &[&ptr]);
+        for len in [11, data.len() - 8, data.len() - 1] {
+            assert!(parse_ptr_response(&data[..len]).is_err(), "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn nxdomain_and_empty_answers_mean_no_name() {
+        assert_eq!(
+            parse_ptr_response(&response(DNS_RCODE_NX_DOMAIN, NAME, &[])),
+            Ok(None)
+        );
+        assert_eq!(
+            parse_ptr_response(&response(DNS_RCODE_NO_ERROR, NAME, &[])),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn server_failures_are_errors() {
+        // SERVFAIL and REFUSED say nothing about the address
+        for rcode in [2, 5] {
+            assert!(parse_ptr_response(&response(rcode, NAME, &[])).is_err());
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::radiotap::InformationElement;
+
+const IE_MOBILITY_DOMAIN: u8 = 54;
+const IE_RM_ENABLED_CAPABILITIES: u8 = 70;
+const IE_EXTENDED_CAPABILITIES: u8 = 127;
+
+// Bit positions in the Extended Capabilities element
+const EXT_CAP_BSS_TRANSITION: usize = 19;
+const EXT_CAP_FILS: usize = 72;
+// Bit position in the RM Enabled Capabilities element
+const RM_CAP_NEIGHBOR_REPORT: usize = 1;
+
+/// 802.11k/v/r/ai roaming features an AP advertises.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RoamingCapabilities {
+    /// BSS Transition Management (802.11v)
+    pub bss_transition: bool,
+    /// Neighbor reports (802.11k)
+    pub neighbor_report: bool,
+    /// Fast BSS Transition (802.11r)
+    pub fast_transition: bool,
+    /// Fast Initial Link Setup (802.11ai)
+    pub fils: bool,
+}
+
+/// Reads bit `bit` of a little-endian bitfield, counting from bit 0 of the
+/// first octet. Elements may be shorter than the bits defined, so missing
+/// octets read as unset.
+fn bit_set(data: &[u8], bit: usize) -> bool {
+    data.get(bit / 8)
+        .is_some_and(|octet| octet & (1 << (bit % 8)) != 0)
+}
+
+/// Collects roaming features from the Extended Capabilities (127), RM
+/// Enabled Capabilities (70) and Mobility Domain (54) elements. FT is
+/// advertised by the Mobility Domain element's presence.
+pub fn parse_roaming(elements: &[InformationElement]) -> RoamingCapabilities {
+    let element = |id: u8| {
+        elements
+            .iter()
+            .find(|ie| ie.id == id)
+            .map(|ie| &ie.data[..])
+    };
+    let extended = element(IE_EXTENDED_CAPABILITIES).unwrap_or_default();
+    let rm_enabled = element(IE_RM_ENABLED_CAPABILITIES).unwrap_or_default();
+
+    RoamingCapabilities {
+        bss_transition: bit_set(extended, EXT_CAP_BSS_TRANSITION),
+        neighbor_report: bit_set(rm_enabled, RM_CAP_NEIGHBOR_REPORT),
+        fast_transition: element(IE_MOBILITY_DOMAIN).is_some(),
+        fils: bit_set(extended, EXT_CAP_FILS),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ie(id: u8, data: &[u8]) -> InformationElement {
+        InformationElement {
+            id,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn bss_transition_is_extended_capabilities_bit_19() {
+        let caps = parse_roaming(&[ie(127, &[0x00, 0x00, 0x08])]);
+        assert_eq!(
+            caps,
+            RoamingCapabilities {
+                bss_transition: true,
+                ..RoamingCapabilities::default()
+            }
+        );
+        // Neighbouring bits don't count
+        assert!(!parse_roaming(&[ie(127, &[0xFF, 0xFF, 0xF7])]).bss_transition);
+    }
+
+    #[test]
+    fn fils_is_extended_capabilities_bit_72() {
+        let mut extended = [0u8; 10];
+        extended[9] = 0x01;
+        let caps = parse_roaming(&[ie(127, &extended)]);
+        assert!(caps.fils);
+        assert!(!caps.bss_transition);
+
+        extended[9] = 0xFE;
+        assert!(!parse_roaming(&[ie(127, &extended)]).fils);
+    }
+
+    #[test]
+    fn neighbor_report_is_rm_enabled_capabilities_bit_1() {
+        let caps = parse_roaming(&[ie(70, &[0x02, 0, 0, 0, 0])]);
+        assert!(caps.neighbor_report);
+        assert!(!parse_roaming(&[ie(70, &[0xFD, 0xFF, 0, 0, 0])]).neighbor_report);
+        // The same bit in another element isn't a neighbor report
+        assert!(!parse_roaming(&[ie(127, &[0x02])]).neighbor_report);
+    }
+
+    #[test]
+    fn fast_transition_from_mobility_domain() {
+        let caps = parse_roaming(&[ie(54, &[0x34, 0x12, 0x01])]);
+        assert!(caps.fast_transition);
+    }
+
+    #[test]
+    fn truncated_elements_read_missing_bits_as_unset() {
+        // Three octets reach bit 19 but not bit 72
+        let caps = parse_roaming(&[ie(127, &[0xFF, 0xFF, 0xFF]), ie(70, &[])]);
+        assert!(caps.bss_transition);
+        assert!(!caps.fils);
+        assert!(!caps.neighbor_report);
+
+        assert_eq!(
+            parse_roaming(&[ie(127, &[])]),
+            RoamingCapabilities::default()
+        );
+        assert_eq!(parse_roaming(&[]), RoamingCapabilities::default());
+    }
+}
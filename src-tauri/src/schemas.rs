@@ -0,0 +1,63 @@
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::arp_watch::BindingConflict;
+use crate::error::WifiError;
+use crate::karma::KarmaAlert;
+use crate::lan_devices::KnownDevice;
+use crate::packet_sniffer::{CaptureComplete, CaptureRate, CaptureStalled, PacketInfo};
+use crate::reverse_dns::HostResolved;
+use crate::signal_meter::SignalReading;
+use crate::wifi_scanner::{AuthExchange, ScanCompletion, SsidChange, WiFiNetwork};
+use crate::ChannelReport;
+
+/// JSON Schemas of the payloads the frontend receives, generated from the
+/// Rust types so TypeScript types can be checked against them.
+#[derive(Debug, Serialize, Clone)]
+pub struct PayloadSchemas {
+    /// Keyed by event name
+    pub events: BTreeMap<String, RootSchema>,
+    /// Keyed by command name
+    pub commands: BTreeMap<String, RootSchema>,
+}
+
+pub fn payload_schemas() -> PayloadSchemas {
+    let events = BTreeMap::from([
+        ("packet".to_string(), schema_for!(PacketInfo)),
+        // The scanner's progress updates go out as the network list alone
+        (
+            "wifi_scan_progress".to_string(),
+            schema_for!(Vec<WiFiNetwork>),
+        ),
+        (
+            "wifi_scan_complete".to_string(),
+            schema_for!(ScanCompletion),
+        ),
+        ("multi_channel_bssid".to_string(), schema_for!(WiFiNetwork)),
+        ("ssid_changed".to_string(), schema_for!(SsidChange)),
+        ("karma_attack".to_string(), schema_for!(KarmaAlert)),
+        ("auth_exchange".to_string(), schema_for!(AuthExchange)),
+        ("bssid_signal".to_string(), schema_for!(SignalReading)),
+        ("capture_rate".to_string(), schema_for!(CaptureRate)),
+        ("capture_stalled".to_string(), schema_for!(CaptureStalled)),
+        ("capture_complete".to_string(), schema_for!(CaptureComplete)),
+        ("capture_error".to_string(), schema_for!(WifiError)),
+        ("capture_save_failed".to_string(), schema_for!(WifiError)),
+        ("host_resolved".to_string(), schema_for!(HostResolved)),
+        ("new_device".to_string(), schema_for!(KnownDevice)),
+        ("arp_spoof_alert".to_string(), schema_for!(BindingConflict)),
+        // Sent with no payload once a replay reaches the end of its file
+        ("replay_finished".to_string(), schema_for!(())),
+    ]);
+    let commands = BTreeMap::from([
+        ("scan_wifi".to_string(), schema_for!(Vec<WiFiNetwork>)),
+        ("get_channel_data".to_string(), schema_for!(ChannelReport)),
+        (
+            "get_latest_packets".to_string(),
+            schema_for!(Vec<PacketInfo>),
+        ),
+    ]);
+    PayloadSchemas { events, commands }
+}
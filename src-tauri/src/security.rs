@@ -0,0 +1,244 @@
+use byteorder::{ByteOrder, LittleEndian};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::radiotap::InformationElement;
+
+// Element IDs
+const IE_RSN: u8 = 48;
+const IE_VENDOR: u8 = 221;
+const IE_RSNXE: u8 = 244;
+
+const OUI_IEEE: [u8; 3] = [0x00, 0x0F, 0xAC];
+const OUI_MICROSOFT_WPA: [u8; 4] = [0x00, 0x50, 0xF2, 0x01];
+const OUI_MICROSOFT_WPS: [u8; 4] = [0x00, 0x50, 0xF2, 0x04];
+
+// AKM suite types (IEEE 802.11-2020 Table 9-151)
+const AKM_8021X: u8 = 1;
+const AKM_PSK: u8 = 2;
+const AKM_8021X_SHA256: u8 = 5;
+const AKM_PSK_SHA256: u8 = 6;
+const AKM_SAE: u8 = 8;
+const AKM_SUITE_B_192: u8 = 12;
+const AKM_OWE: u8 = 18;
+const AKM_SAE_EXT_KEY: u8 = 24;
+
+// RSN capabilities bits
+const RSN_CAP_MFP_REQUIRED: u16 = 0x0040;
+const RSN_CAP_MFP_CAPABLE: u16 = 0x0080;
+
+// RSNXE capabilities bits
+const RSNXE_SAE_H2E: u8 = 0x20;
+
+// Capability information bit in the beacon fixed parameters
+const CAPABILITY_PRIVACY: u16 = 0x0010;
+
+/// Protected Management Frames (802.11w) support from the RSN capabilities.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PmfStatus {
+    #[default]
+    Disabled,
+    Capable,
+    /// Clients that don't support PMF are refused
+    Required,
+}
+
+impl PmfStatus {
+    /// Decodes the MFPR/MFPC bits. MFPR without MFPC is invalid per the
+    /// standard but is reported as required, since that's what the AP enforces.
+    fn from_capabilities(capabilities: u16) -> Self {
+        if capabilities & RSN_CAP_MFP_REQUIRED != 0 {
+            PmfStatus::Required
+        } else if capabilities & RSN_CAP_MFP_CAPABLE != 0 {
+            PmfStatus::Capable
+        } else {
+            PmfStatus::Disabled
+        }
+    }
+}
+
+/// Security settings advertised by an access point.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SecurityInfo {
+    pub label: String,
+    pub pmf: PmfStatus,
+    /// SAE hash-to-element is supported (from the RSNXE)
+    pub sae_h2e: bool,
+    /// Wi-Fi Protected Setup is advertised
+    pub wps: bool,
+}
+
+/// The parts of an RSN element we care about.
+#[derive(Debug, Clone, Default)]
+struct RsnInfo {
+    akm_suites: Vec<u8>,
+    capabilities: u16,
+}
+
+/// Parses an RSN element body. Suites from other OUIs are skipped.
+fn parse_rsn(data: &[u8]) -> Option<RsnInfo> {
+    // Version (2) + group cipher suite (4)
+    let mut offset = 6;
+    if data.len() < offset + 2 {
+        return None;
+    }
+
+    let pairwise_count = LittleEndian::read_u16(&data[offset..]) as usize;
+    offset += 2 + pairwise_count * 4;
+    if data.len() < offset + 2 {
+        return None;
+    }
+
+    let akm_count = LittleEndian::read_u16(&data[offset..]) as usize;
+    offset += 2;
+    if data.len() < offset + akm_count * 4 {
+        return None;
+    }
+
+    let akm_suites = data[offset..offset + akm_count * 4]
+        .chunks_exact(4)
+        .filter(|suite| suite[..3] == OUI_IEEE)
+        .map(|suite| suite[3])
+        .collect();
+    offset += akm_count * 4;
+
+    // RSN capabilities are optional at the end of the element
+    let capabilities = if data.len() >= offset + 2 {
+        LittleEndian::read_u16(&data[offset..])
+    } else {
+        0
+    };
+
+    Some(RsnInfo {
+        akm_suites,
+        capabilities,
+    })
+}
+
+/// Classifies a network from its beacon capability field and tagged
+/// parameters.
+pub fn classify(capability_info: Option<u16>, elements: &[InformationElement]) -> SecurityInfo {
+    let privacy = capability_info.is_some_and(|cap| cap & CAPABILITY_PRIVACY != 0);
+
+    let sae_h2e = elements
+        .iter()
+        .find(|ie| ie.id == IE_RSNXE)
+        .and_then(|ie| ie.data.first())
+        .is_some_and(|caps| caps & RSNXE_SAE_H2E != 0);
+
+    let wps = elements
+        .iter()
+        .any(|ie| ie.id == IE_VENDOR && ie.data.starts_with(&OUI_MICROSOFT_WPS));
+
+    let rsn = elements
+        .iter()
+        .find(|ie| ie.id == IE_RSN)
+        .and_then(|ie| parse_rsn(&ie.data));
+
+    let Some(rsn) = rsn else {
+        let wpa = elements
+            .iter()
+            .any(|ie| ie.id == IE_VENDOR && ie.data.starts_with(&OUI_MICROSOFT_WPA));
+        let label = if wpa {
+            "WPA"
+        } else if privacy {
+            "WEP"
+        } else {
+            "Open"
+        };
+        return SecurityInfo {
+            label: label.to_string(),
+            wps,
+            ..SecurityInfo::default()
+        };
+    };
+
+    let has = |akms: &[u8]| rsn.akm_suites.iter().any(|akm| akms.contains(akm));
+    let sae = has(&[AKM_SAE, AKM_SAE_EXT_KEY]);
+    let psk = has(&[AKM_PSK, AKM_PSK_SHA256]);
+
+    let label = if sae && psk {
+        // Same BSSID accepts both, so clients can be downgraded to WPA2
+        "WPA2/WPA3 Transition"
+    } else if sae {
+        "WPA3-SAE"
+    } else if has(&[AKM_SUITE_B_192]) {
+        "WPA3-Enterprise"
+    } else if has(&[AKM_OWE]) {
+        "OWE"
+    } else if has(&[AKM_8021X, AKM_8021X_SHA256]) {
+        "WPA2-Enterprise"
+    } else if psk {
+        "WPA2-PSK"
+    } else {
+        "WPA2"
+    };
+
+    SecurityInfo {
+        label: label.to_string(),
+        pmf: PmfStatus::from_capabilities(rsn.capabilities),
+        sae_h2e,
+        wps,
+    }
+}
+
+/// Whether a `classify` label means the network accepts SAE authentication.
+pub fn advertises_sae(label: &str) -> bool {
+    matches!(label, "WPA3-SAE" | "WPA2/WPA3 Transition")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An RSN element with CCMP, one AKM suite and the given capabilities.
+    fn rsn_element(akm: u8, capabilities: u16) -> InformationElement {
+        let mut data = vec![1, 0];
+        data.extend_from_slice(&[0x00, 0x0F, 0xAC, 4]);
+        data.extend_from_slice(&[1, 0, 0x00, 0x0F, 0xAC, 4]);
+        data.extend_from_slice(&[1, 0, 0x00, 0x0F, 0xAC, akm]);
+        data.extend_from_slice(&capabilities.to_le_bytes());
+        InformationElement { id: IE_RSN, data }
+    }
+
+    fn pmf(capabilities: u16) -> PmfStatus {
+        classify(
+            Some(CAPABILITY_PRIVACY),
+            &[rsn_element(AKM_PSK, capabilities)],
+        )
+        .pmf
+    }
+
+    #[test]
+    fn pmf_from_mfpc_and_mfpr_bits() {
+        assert_eq!(pmf(0), PmfStatus::Disabled);
+        assert_eq!(pmf(RSN_CAP_MFP_CAPABLE), PmfStatus::Capable);
+        assert_eq!(
+            pmf(RSN_CAP_MFP_CAPABLE | RSN_CAP_MFP_REQUIRED),
+            PmfStatus::Required
+        );
+    }
+
+    #[test]
+    fn mfpr_without_mfpc_is_reported_as_required() {
+        assert_eq!(pmf(RSN_CAP_MFP_REQUIRED), PmfStatus::Required);
+    }
+
+    #[test]
+    fn other_capability_bits_leave_pmf_alone() {
+        // Preauthentication and the PTKSA replay counter field
+        assert_eq!(pmf(0x0001 | 0x000C), PmfStatus::Disabled);
+    }
+
+    #[test]
+    fn missing_rsn_capabilities_mean_pmf_disabled() {
+        let mut element = rsn_element(AKM_SAE, 0);
+        element.data.truncate(element.data.len() - 2);
+
+        let info = classify(Some(CAPABILITY_PRIVACY), &[element]);
+
+        assert_eq!(info.label, "WPA3-SAE");
+        assert_eq!(info.pmf, PmfStatus::Disabled);
+    }
+}
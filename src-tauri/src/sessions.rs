@@ -0,0 +1,241 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::WifiError;
+use crate::packet_sniffer::PacketInfo;
+use crate::wifi_scanner::WiFiNetwork;
+
+// Packets beyond this are counted but not stored, to bound memory on long surveys
+const MAX_SESSION_PACKETS: usize = 100_000;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionMeta {
+    pub id: String,
+    pub name: String,
+    pub notes: String,
+    /// Where the survey took place, e.g. a site or floor
+    pub location: Option<String>,
+    pub started_at: u64,
+    pub ended_at: Option<u64>,
+    pub network_count: usize,
+    pub packet_count: u64,
+}
+
+/// A labelled survey: everything scanned or captured between
+/// `start_session` and `end_session`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    #[serde(flatten)]
+    pub meta: SessionMeta,
+    pub networks: Vec<WiFiNetwork>,
+    pub packets: Vec<PacketInfo>,
+}
+
+/// Tracks the active session and the ones saved to disk.
+#[derive(Default)]
+pub struct SessionManager {
+    active: Option<Session>,
+    dir: Option<PathBuf>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+impl SessionManager {
+    /// Saves ended sessions under `dir` as `<id>.json`, with their metadata
+    /// alone in `<id>.meta.json` so listing them stays cheap.
+    pub fn new(dir: PathBuf) -> Self {
+        SessionManager {
+            active: None,
+            dir: Some(dir),
+        }
+    }
+
+    /// Starts a session. Sessions don't nest: starting one while another is
+    /// running is an error rather than silently ending the first.
+    pub fn start(
+        &mut self,
+        name: &str,
+        notes: &str,
+        location: Option<String>,
+    ) -> Result<SessionMeta, WifiError> {
+        if let Some(active) = &self.active {
+            return Err(WifiError::SessionError(format!(
+                "Session \"{}\" is already running, end it first",
+                active.meta.name
+            )));
+        }
+
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards");
+        let meta = SessionMeta {
+            id: started.as_millis().to_string(),
+            name: name.to_string(),
+            notes: notes.to_string(),
+            location: location.filter(|l| !l.trim().is_empty()),
+            started_at: started.as_secs(),
+            ended_at: None,
+            network_count: 0,
+            packet_count: 0,
+        };
+
+        info!("Started session {} ({})", meta.name, meta.id);
+        self.active = Some(Session {
+            meta: meta.clone(),
+            networks: Vec::new(),
+            packets: Vec::new(),
+        });
+        Ok(meta)
+    }
+
+    /// Ends the active session and writes it to disk. If saving fails the
+    /// session stays active so nothing is lost.
+    pub fn end(&mut self) -> Result<SessionMeta, WifiError> {
+        let mut session = self
+            .active
+            .take()
+            .ok_or_else(|| WifiError::SessionError("No session is running".to_string()))?;
+        session.meta.ended_at = Some(now_secs());
+
+        info!(
+            "Ended session {} with {} networks and {} packets",
+            session.meta.name, session.meta.network_count, session.meta.packet_count
+        );
+        if let Err(e) = self.save(&session) {
+            session.meta.ended_at = None;
+            self.active = Some(session);
+            return Err(e);
+        }
+        Ok(session.meta)
+    }
+
+    /// Tags `packet` with the active session and stores it there.
+    pub fn record_packet(&mut self, packet: &mut PacketInfo) {
+        let Some(session) = &mut self.active else {
+            return;
+        };
+
+        packet.session_id = Some(session.meta.id.clone());
+        session.meta.packet_count += 1;
+        if session.packets.len() < MAX_SESSION_PACKETS {
+            session.packets.push(packet.clone());
+        }
+    }
+
+    /// Tags `networks` with the active session and merges them into it,
+    /// keeping the latest copy of each BSSID.
+    pub fn record_networks(&mut self, networks: &mut [WiFiNetwork]) {
+        let Some(session) = &mut self.active else {
+            return;
+        };
+
+        for network in networks.iter_mut() {
+            network.session_id = Some(session.meta.id.clone());
+            match session
+                .networks
+                .iter_mut()
+                .find(|n| n.bssid == network.bssid)
+            {
+                Some(existing) => *existing = network.clone(),
+                None => session.networks.push(network.clone()),
+            }
+        }
+        session.meta.network_count = session.networks.len();
+    }
+
+    /// Metadata of the active session followed by saved ones, newest first.
+    pub fn list(&self) -> Vec<SessionMeta> {
+        let mut sessions = self.load_all_meta();
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.started_at));
+        if let Some(active) = &self.active {
+            sessions.insert(0, active.meta.clone());
+        }
+        sessions
+    }
+
+    pub fn get(&self, id: &str) -> Result<Session, WifiError> {
+        if let Some(active) = self.active.as_ref().filter(|s| s.meta.id == id) {
+            return Ok(active.clone());
+        }
+
+        let path = self
+            .session_path(id, "json")
+            .ok_or_else(|| WifiError::SessionError("Session storage unavailable".to_string()))?;
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|_| WifiError::SessionError(format!("Unknown session {}", id)))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| WifiError::ParseError(format!("Corrupt session {}: {}", id, e)))
+    }
+
+    /// `<id>.<extension>` in the session directory.
+    fn session_path(&self, id: &str, extension: &str) -> Option<PathBuf> {
+        // Ids are generated from timestamps; refuse anything that could escape the dir
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.{}", id, extension)))
+    }
+
+    fn load_all_meta(&self) -> Vec<SessionMeta> {
+        let Some(dir) = &self.dir else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".meta.json"))
+            .filter_map(|entry| {
+                let contents = std::fs::read_to_string(entry.path()).ok()?;
+                match serde_json::from_str::<SessionMeta>(&contents) {
+                    Ok(meta) => Some(meta),
+                    Err(e) => {
+                        warn!("Skipping unreadable session {:?}: {}", entry.path(), e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn save(&self, session: &Session) -> Result<(), WifiError> {
+        let (Some(path), Some(meta_path)) = (
+            self.session_path(&session.meta.id, "json"),
+            self.session_path(&session.meta.id, "meta.json"),
+        ) else {
+            warn!(
+                "No session directory, session {} not saved",
+                session.meta.id
+            );
+            return Ok(());
+        };
+
+        let json = serde_json::to_string(session)
+            .map_err(|e| WifiError::CaptureError(format!("Failed to serialize session: {}", e)))?;
+        let meta_json = serde_json::to_string(&session.meta)
+            .map_err(|e| WifiError::CaptureError(format!("Failed to serialize session: {}", e)))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                WifiError::CaptureError(format!("Failed to create {:?}: {}", dir, e))
+            })?;
+        }
+        // The data goes first so a listed session can always be opened
+        for (path, json) in [(&path, json), (&meta_path, meta_json)] {
+            std::fs::write(path, json).map_err(|e| {
+                WifiError::CaptureError(format!("Failed to write {:?}: {}", path, e))
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use log::{error, info};
+use pcap::Capture;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::devices::DeviceLease;
+use crate::error::WifiError;
+use crate::radiotap::RadiotapParser;
+
+/// How often a reading is reported, fast enough to aim an antenna by.
+const REPORT_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Payload of the "bssid_signal" event.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct SignalReading {
+    pub bssid: String,
+    /// Signal of the latest frame from the BSSID, or None if nothing was
+    /// heard from it during the interval
+    pub signal_dbm: Option<i8>,
+    /// Frames heard from the BSSID during the interval
+    pub frames: u32,
+}
+
+/// Tight-loop signal readout for a single BSSID.
+#[derive(Default)]
+pub struct SignalMeter {
+    running: Arc<Mutex<bool>>,
+}
+
+impl SignalMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tunes `interface` to `frequency` MHz and reports the signal of frames
+    /// sent by `bssid` every `REPORT_INTERVAL` until stopped. The lease is
+    /// held until the meter thread exits.
+    pub fn start<F>(
+        &self,
+        lease: DeviceLease,
+        interface: &str,
+        bssid: &str,
+        frequency: u32,
+        mut report: F,
+    ) -> Result<(), WifiError>
+    where
+        F: FnMut(SignalReading) + Send + 'static,
+    {
+        let bssid = normalize_bssid(bssid)?;
+
+        let mut cap = Capture::from_device(interface)?
+            .promisc(true)
+            .snaplen(256)
+            .timeout(50)
+            .open()?;
+        cap.set_datalink(pcap::Linktype::IEEE802_11_RADIOTAP)
+            .map_err(|e| {
+                WifiError::MonitorModeUnavailable(format!(
+                    "Failed to set radiotap datalink on {}: {}",
+                    interface, e
+                ))
+            })?;
+        cap.filter(&format!("wlan addr2 {}", bssid), true)
+            .map_err(|e| WifiError::CaptureError(format!("Failed to set filter: {}", e)))?;
+        lock_frequency(interface, frequency)?;
+
+        info!(
+            "Metering signal of {} on {} at {} MHz",
+            bssid, interface, frequency
+        );
+        *self.running.lock().unwrap() = true;
+        let running = Arc::clone(&self.running);
+
+        thread::spawn(move || {
+            let _lease = lease;
+            let mut latest = None;
+            let mut frames = 0;
+            let mut last_report = Instant::now();
+
+            while *running.lock().unwrap() {
+                match cap.next_packet() {
+                    Ok(packet) => {
+                        let radiotap = RadiotapParser::new(packet.data).parse_radiotap_header();
+                        if let Some(signal) = radiotap.ok().and_then(|r| r.antenna_signal) {
+                            latest = Some(signal);
+                            frames += 1;
+                        }
+                    }
+                    Err(pcap::Error::TimeoutExpired) => {}
+                    Err(e) => {
+                        error!("Error receiving packet: {}", e);
+                        *running.lock().unwrap() = false;
+                        break;
+                    }
+                }
+
+                if last_report.elapsed() >= REPORT_INTERVAL {
+                    report(SignalReading {
+                        bssid: bssid.clone(),
+                        signal_dbm: latest.take(),
+                        frames,
+                    });
+                    frames = 0;
+                    last_report = Instant::now();
+                }
+            }
+
+            info!("Stopped metering signal of {}", bssid);
+        });
+
+        Ok(())
+    }
+
+    /// Stops the meter. Returns false if it wasn't running.
+    pub fn stop(&self) -> bool {
+        std::mem::replace(&mut *self.running.lock().unwrap(), false)
+    }
+}
+
+/// Checks `bssid` is a MAC address before it goes into a filter expression.
+fn normalize_bssid(bssid: &str) -> Result<String, WifiError> {
+    let octets: Vec<&str> = bssid.split(':').collect();
+    let valid = octets.len() == 6
+        && octets
+            .iter()
+            .all(|o| o.len() == 2 && o.chars().all(|c| c.is_ascii_hexdigit()));
+    if !valid {
+        return Err(WifiError::ParseError(format!("Invalid BSSID '{}'", bssid)));
+    }
+    Ok(bssid.to_ascii_lowercase())
+}
+
+/// Parks a monitor-mode interface on one frequency with `iw`, so the meter
+/// doesn't miss frames while the adapter hops channels.
+fn lock_frequency(interface: &str, frequency: u32) -> Result<(), WifiError> {
+    let output = Command::new("iw")
+        .args(["dev", interface, "set", "freq", &frequency.to_string()])
+        .output()
+        .map_err(|e| WifiError::MonitorModeUnavailable(format!("Failed to run iw: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let msg = format!(
+            "Failed to tune {} to {} MHz: {}",
+            interface,
+            frequency,
+            stderr.trim()
+        );
+        return Err(if stderr.contains("Operation not permitted") {
+            WifiError::PermissionDenied(msg)
+        } else {
+            WifiError::MonitorModeUnavailable(msg)
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,451 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::radiotap::InformationElement;
+
+/// WiFi frequency band.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Band {
+    #[serde(rename = "2.4GHz")]
+    TwoPointFour,
+    #[serde(rename = "5GHz")]
+    Five,
+    #[serde(rename = "6GHz")]
+    Six,
+}
+
+impl Band {
+    pub const ALL: [Band; 3] = [Band::TwoPointFour, Band::Five, Band::Six];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Band::TwoPointFour => "2.4 GHz",
+            Band::Five => "5 GHz",
+            Band::Six => "6 GHz",
+        }
+    }
+
+    /// 20 MHz channel numbers available in this band.
+    pub fn channels(self) -> Vec<u32> {
+        match self {
+            Band::TwoPointFour => (1..=13).collect(),
+            Band::Five => (36..=64)
+                .step_by(4)
+                .chain((100..=144).step_by(4))
+                .chain((149..=165).step_by(4))
+                .collect(),
+            Band::Six => (1..=233).step_by(4).collect(),
+        }
+    }
+
+    /// Center frequency in MHz of a 20 MHz channel in this band.
+    pub fn channel_frequency(self, channel: u32) -> u32 {
+        match self {
+            Band::TwoPointFour if channel == 14 => 2484,
+            Band::TwoPointFour => 2407 + channel * 5,
+            Band::Five => 5000 + channel * 5,
+            Band::Six => 5950 + channel * 5,
+        }
+    }
+
+    /// Whether a 20 MHz channel in this band needs Dynamic Frequency
+    /// Selection: an AP on it must listen for radar before transmitting and
+    /// move off if it hears any.
+    pub fn is_dfs(self, channel: u32) -> bool {
+        self == Band::Five && matches!(channel, 52..=64 | 100..=144)
+    }
+
+    /// Classifies a center frequency in MHz.
+    pub fn from_frequency(freq: u32) -> Option<Band> {
+        match freq {
+            2400..=2500 => Some(Band::TwoPointFour),
+            5150..=5895 => Some(Band::Five),
+            5925..=7125 => Some(Band::Six),
+            _ => None,
+        }
+    }
+}
+
+/// Derives the channel number for a network. 5 and 6 GHz channels come from
+/// the radiotap frequency, since the DS Parameter Set only reliably encodes
+/// 2.4 GHz channels; 2.4 GHz prefers the DS Parameter Set value.
+pub fn channel_number(freq: u32, ds_channel: Option<u8>) -> u32 {
+    match Band::from_frequency(freq) {
+        Some(Band::Five) => (freq - 5000) / 5,
+        // 6 GHz channel 1 is centered at 5955 MHz; 5935 is channel 2
+        Some(Band::Six) if freq == 5935 => 2,
+        // Below channel 1 there's no 20 MHz channel to report
+        Some(Band::Six) => freq.checked_sub(5950).map_or(0, |offset| offset / 5),
+        Some(Band::TwoPointFour) => match (ds_channel, freq) {
+            (Some(channel), _) => channel as u32,
+            (None, 2484) => 14,
+            (None, freq) if freq >= 2412 => (freq - 2407) / 5,
+            (None, _) => 0,
+        },
+        None => ds_channel.map(|channel| channel as u32).unwrap_or(0),
+    }
+}
+
+// Radiotap channel flags
+const CHAN_CCK: u16 = 0x0020;
+const CHAN_OFDM: u16 = 0x0040;
+const CHAN_2GHZ: u16 = 0x0080;
+const CHAN_5GHZ: u16 = 0x0100;
+const CHAN_DYNAMIC_CCK_OFDM: u16 = 0x0400;
+const CHAN_GFSK: u16 = 0x0800;
+
+/// Modulation family from the radiotap channel flags.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Modulation {
+    /// 802.11b
+    Cck,
+    /// 802.11a/g and later
+    Ofdm,
+    /// 802.11g channel shared with 802.11b stations
+    DynamicCckOfdm,
+    /// Legacy frequency-hopping PHY
+    Gfsk,
+}
+
+/// What the radiotap channel flags say about the channel, independent of
+/// the frequency and the frame's IEs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelFlags {
+    /// 2.4 or 5 GHz; radiotap has no 6 GHz flag, and drivers set the 5 GHz
+    /// one there
+    pub band: Option<Band>,
+    pub modulation: Option<Modulation>,
+}
+
+impl ChannelFlags {
+    pub fn decode(flags: u16) -> Self {
+        let band = if flags & CHAN_2GHZ != 0 {
+            Some(Band::TwoPointFour)
+        } else if flags & CHAN_5GHZ != 0 {
+            Some(Band::Five)
+        } else {
+            None
+        };
+        let modulation = if flags & CHAN_DYNAMIC_CCK_OFDM != 0 {
+            Some(Modulation::DynamicCckOfdm)
+        } else if flags & CHAN_OFDM != 0 {
+            Some(Modulation::Ofdm)
+        } else if flags & CHAN_CCK != 0 {
+            Some(Modulation::Cck)
+        } else if flags & CHAN_GFSK != 0 {
+            Some(Modulation::Gfsk)
+        } else {
+            None
+        };
+        ChannelFlags { band, modulation }
+    }
+
+    /// Whether the flags, the radiotap frequency and the DS Parameter Set
+    /// channel describe the same channel. Sources that are missing count as
+    /// agreeing. On 2.4 GHz a beacon picked up from an adjacent channel has
+    /// a DS channel that differs from the frequency it was heard on.
+    pub fn agrees_with(&self, freq: u32, ds_channel: Option<u8>) -> bool {
+        let freq_band = Band::from_frequency(freq);
+        let band_agrees = match (self.band, freq_band) {
+            (Some(Band::Five), Some(Band::Six)) => true,
+            (Some(flags_band), Some(freq_band)) => flags_band == freq_band,
+            _ => true,
+        };
+        let ds_agrees = match (freq_band, ds_channel) {
+            (Some(Band::TwoPointFour), Some(ds)) => channel_number(freq, None) == ds as u32,
+            _ => true,
+        };
+        band_agrees && ds_agrees
+    }
+}
+
+/// A frequency range in MHz that a transmission occupies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub low: u32,
+    pub high: u32,
+}
+
+impl Span {
+    pub fn centered(center_mhz: u32, width_mhz: u32) -> Self {
+        Span {
+            low: center_mhz.saturating_sub(width_mhz / 2),
+            high: center_mhz + width_mhz / 2,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.high - self.low
+    }
+
+    /// MHz the two spans have in common.
+    pub fn overlap(&self, other: &Span) -> u32 {
+        self.high
+            .min(other.high)
+            .saturating_sub(self.low.max(other.low))
+    }
+
+    /// MHz between the two spans, 0 when they touch or overlap.
+    pub fn gap(&self, other: &Span) -> u32 {
+        self.low
+            .saturating_sub(other.high)
+            .max(other.low.saturating_sub(self.high))
+    }
+}
+
+const IE_HT_OPERATION: u8 = 61;
+const IE_VHT_OPERATION: u8 = 192;
+const IE_EXTENSION: u8 = 255;
+const IE_EXT_HE_OPERATION: u8 = 36;
+
+// HE Operation Parameters bits announcing optional fields
+const HE_VHT_OPERATION_PRESENT: u32 = 1 << 14;
+const HE_CO_HOSTED_BSS: u32 = 1 << 15;
+const HE_6GHZ_OPERATION_PRESENT: u32 = 1 << 17;
+
+/// Width and center frequency in MHz of the channel an AP operates on, from
+/// its HT, VHT and, on 6 GHz, HE Operation elements. APs that advertise
+/// none of them use the 20 MHz channel at `freq`.
+pub fn operating_channel(elements: &[InformationElement], freq: u32) -> (u32, u32) {
+    let Some(band) = Band::from_frequency(freq) else {
+        return (20, freq);
+    };
+    let element = |id: u8| {
+        elements
+            .iter()
+            .find(|ie| ie.id == id)
+            .map(|ie| ie.data.as_slice())
+    };
+
+    if band == Band::Six {
+        let he_operation = elements
+            .iter()
+            .find(|ie| ie.id == IE_EXTENSION && ie.data.first() == Some(&IE_EXT_HE_OPERATION))
+            .map(|ie| &ie.data[1..]);
+        if let Some(channel) = he_operation.and_then(he_6ghz_channel) {
+            return channel;
+        }
+    }
+
+    // VHT channel center frequency segments 0 and 1
+    if let Some([width, ccfs0, ccfs1, ..]) =
+        element(IE_VHT_OPERATION).filter(|_| band == Band::Five)
+    {
+        let (ccfs0, ccfs1) = (*ccfs0 as u32, *ccfs1 as u32);
+        match width {
+            // 160 MHz is signaled with segment 1 at its center, 8 channels
+            // from the primary 80 MHz segment's
+            1 if ccfs1 != 0 && ccfs1.abs_diff(ccfs0) == 8 => {
+                return (160, band.channel_frequency(ccfs1))
+            }
+            // 80+80 MHz counts as its primary 80 MHz segment
+            1 | 3 => return (80, band.channel_frequency(ccfs0)),
+            2 => return (160, band.channel_frequency(ccfs0)),
+            _ => {}
+        }
+    }
+
+    // HT secondary channel offset, honored only when any width is allowed
+    match element(IE_HT_OPERATION) {
+        Some([_, info, ..]) if info & 0x04 != 0 => match info & 0x03 {
+            1 => (40, freq + 10),
+            3 => (40, freq - 10),
+            _ => (20, freq),
+        },
+        _ => (20, freq),
+    }
+}
+
+/// Width and center frequency from the 6 GHz Operation Information of an
+/// HE Operation element (without its extension id).
+fn he_6ghz_channel(he: &[u8]) -> Option<(u32, u32)> {
+    let params = u32::from_le_bytes([*he.first()?, *he.get(1)?, *he.get(2)?, 0]);
+    if params & HE_6GHZ_OPERATION_PRESENT == 0 {
+        return None;
+    }
+    // Parameters (3), BSS color (1) and basic HE-MCS/NSS set (2), then the
+    // optional fields in order
+    let mut offset = 6;
+    if params & HE_VHT_OPERATION_PRESENT != 0 {
+        offset += 3;
+    }
+    if params & HE_CO_HOSTED_BSS != 0 {
+        offset += 1;
+    }
+    // Primary channel, control, segment 0 and 1 centers, minimum rate
+    let [_, control, ccfs0, ccfs1, _] = *he.get(offset..offset + 5)? else {
+        return None;
+    };
+    let (ccfs0, ccfs1) = (ccfs0 as u32, ccfs1 as u32);
+    let channel = match control & 0x03 {
+        0 => (20, ccfs0),
+        1 => (40, ccfs0),
+        3 if ccfs1 != 0 && ccfs1.abs_diff(ccfs0) == 8 => (160, ccfs1),
+        _ => (80, ccfs0),
+    };
+    Some((channel.0, Band::Six.channel_frequency(channel.1)))
+}
+
+/// Attenuation in dBr of a 20 MHz OFDM transmit spectral mask at `offset_mhz`
+/// from the center frequency (IEEE 802.11 clause 17), interpolated linearly
+/// between the mask corners. Returns None beyond the mask edge.
+pub fn spectral_mask_dbr(offset_mhz: f64) -> Option<f64> {
+    const CORNERS: [(f64, f64); 4] = [(9.0, 0.0), (11.0, -20.0), (20.0, -28.0), (30.0, -40.0)];
+
+    let offset = offset_mhz.abs();
+    if offset <= CORNERS[0].0 {
+        return Some(0.0);
+    }
+
+    CORNERS.windows(2).find_map(|pair| {
+        let ((f0, a0), (f1, a1)) = (pair[0], pair[1]);
+        (offset <= f1).then(|| a0 + (a1 - a0) * (offset - f0) / (f1 - f0))
+    })
+}
+
+// Supported-rates values are in 500 kbps units; the high bit marks basic rates
+const CCK_RATES: [u8; 4] = [2, 4, 11, 22];
+const OFDM_RATES: [u8; 8] = [12, 18, 24, 36, 48, 72, 96, 108];
+
+/// Infers the 802.11 PHY generation. The HT/VHT/HE capability elements are
+/// decisive when present; otherwise the supported rates and band decide
+/// between the legacy a/b/g PHYs.
+pub fn classify_phy(rates: &[u8], band: Band, has_ht: bool, has_vht: bool, has_he: bool) -> String {
+    let phy = if has_he {
+        "ax"
+    } else if has_vht {
+        "ac"
+    } else if has_ht {
+        "n"
+    } else if band != Band::TwoPointFour {
+        "a"
+    } else {
+        let has_rate = |set: &[u8]| rates.iter().any(|rate| set.contains(&(rate & 0x7F)));
+        if has_rate(&OFDM_RATES) {
+            "g"
+        } else if has_rate(&CCK_RATES) {
+            "b"
+        } else {
+            "unknown"
+        }
+    };
+    phy.to_string()
+}
+
+/// Marketing generation for a PHY mode from `classify_phy`. Wi-Fi 6 on the
+/// 6 GHz band is "Wi-Fi 6E"; pre-802.11n PHYs are "Legacy".
+pub fn wifi_generation(phy_mode: &str, band: Option<Band>) -> String {
+    let generation = match (phy_mode, band) {
+        (_, Some(Band::Six)) => "Wi-Fi 6E",
+        ("ax", _) => "Wi-Fi 6",
+        ("ac", _) => "Wi-Fi 5",
+        ("n", _) => "Wi-Fi 4",
+        ("a" | "b" | "g", _) => "Legacy",
+        _ => "Unknown",
+    };
+    generation.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_point_four_ghz_prefers_ds_channel() {
+        assert_eq!(channel_number(2412, Some(1)), 1);
+        // Heard on channel 1 from an AP on channel 2
+        assert_eq!(channel_number(2412, Some(2)), 2);
+        assert_eq!(channel_number(2412, None), 1);
+        assert_eq!(channel_number(2472, None), 13);
+        assert_eq!(channel_number(2484, None), 14);
+    }
+
+    #[test]
+    fn five_ghz_channel_comes_from_frequency() {
+        assert_eq!(channel_number(5180, None), 36);
+        // A bogus DS Parameter Set value doesn't override the frequency
+        assert_eq!(channel_number(5180, Some(1)), 36);
+        assert_eq!(channel_number(5320, Some(64)), 64);
+        assert_eq!(channel_number(5500, None), 100);
+        assert_eq!(channel_number(5825, None), 165);
+    }
+
+    #[test]
+    fn six_ghz_channel_comes_from_frequency() {
+        assert_eq!(channel_number(5955, None), 1);
+        // The DS Parameter Set would read as a 2.4 GHz channel
+        assert_eq!(channel_number(5955, Some(1)), 1);
+        assert_eq!(channel_number(6175, None), 45);
+        assert_eq!(channel_number(7115, None), 233);
+        assert_eq!(channel_number(5935, None), 2);
+    }
+
+    #[test]
+    fn six_ghz_below_channel_one_has_no_channel() {
+        assert_eq!(channel_number(5925, None), 0);
+        assert_eq!(channel_number(5945, None), 0);
+        assert_eq!(channel_number(5950, None), 0);
+    }
+
+    #[test]
+    fn band_boundaries() {
+        assert_eq!(Band::from_frequency(2399), None);
+        assert_eq!(Band::from_frequency(2400), Some(Band::TwoPointFour));
+        assert_eq!(Band::from_frequency(2500), Some(Band::TwoPointFour));
+        assert_eq!(Band::from_frequency(5149), None);
+        assert_eq!(Band::from_frequency(5150), Some(Band::Five));
+        assert_eq!(Band::from_frequency(5895), Some(Band::Five));
+        assert_eq!(Band::from_frequency(5896), None);
+    }
+
+    fn ie(id: u8, data: &[u8]) -> InformationElement {
+        InformationElement {
+            id,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn operating_channel_from_operation_elements() {
+        assert_eq!(operating_channel(&[], 5180), (20, 5180));
+        // HT 40 MHz with the secondary channel above or below
+        assert_eq!(operating_channel(&[ie(61, &[36, 0x05])], 5180), (40, 5190));
+        assert_eq!(operating_channel(&[ie(61, &[40, 0x07])], 5200), (40, 5190));
+        // Secondary channel set but the AP only uses 20 MHz
+        assert_eq!(operating_channel(&[ie(61, &[36, 0x01])], 5180), (20, 5180));
+
+        let ht40 = ie(61, &[36, 0x05]);
+        let vht80 = ie(192, &[1, 42, 0]);
+        assert_eq!(operating_channel(&[ht40.clone(), vht80], 5180), (80, 5210));
+        let vht160 = ie(192, &[1, 42, 50]);
+        assert_eq!(operating_channel(&[ht40, vht160], 5180), (160, 5250));
+    }
+
+    #[test]
+    fn operating_channel_from_he_6ghz_operation() {
+        // 6 GHz Operation Information present: primary 1, 80 MHz centered on 7
+        let he = ie(255, &[36, 0x00, 0x00, 0x02, 0, 0, 0, 1, 0x02, 7, 0, 0]);
+        assert_eq!(operating_channel(&[he], 5955), (80, 5985));
+    }
+
+    #[test]
+    fn spans_overlap_and_gap() {
+        let ch36 = Span::centered(5180, 20);
+        let ch36_80 = Span::centered(5210, 80);
+        assert_eq!(ch36_80.overlap(&ch36), 20);
+        assert_eq!(ch36_80.overlap(&Span::centered(5240, 20)), 20);
+        assert_eq!(ch36_80.gap(&Span::centered(5260, 20)), 0);
+        assert_eq!(ch36_80.gap(&Span::centered(5280, 20)), 20);
+        assert_eq!(
+            Span::centered(2412, 20).overlap(&Span::centered(2422, 20)),
+            10
+        );
+    }
+
+    #[test]
+    fn unknown_frequency_falls_back_to_ds_channel() {
+        assert_eq!(channel_number(0, Some(6)), 6);
+        assert_eq!(channel_number(0, None), 0);
+    }
+}
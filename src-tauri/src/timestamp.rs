@@ -0,0 +1,83 @@
+use pcap::Precision;
+use serde::{Deserialize, Serialize};
+
+/// Resolution of the fractional part of pcap packet timestamps.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampPrecision {
+    Micro,
+    Nano,
+}
+
+impl From<Precision> for TimestampPrecision {
+    fn from(precision: Precision) -> Self {
+        match precision {
+            Precision::Micro => TimestampPrecision::Micro,
+            Precision::Nano => TimestampPrecision::Nano,
+        }
+    }
+}
+
+impl TimestampPrecision {
+    fn digits(self) -> usize {
+        match self {
+            TimestampPrecision::Micro => 6,
+            TimestampPrecision::Nano => 9,
+        }
+    }
+}
+
+/// Formats a Unix timestamp as RFC 3339 UTC, e.g.
+/// `2024-05-01T12:34:56.123456Z`. `frac` is in micro- or nanoseconds
+/// according to `precision`, and is printed at that precision.
+pub fn format_rfc3339(secs: i64, frac: u32, precision: TimestampPrecision) -> String {
+    let days = secs.div_euclid(86_400);
+    let seconds_of_day = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:0width$}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        frac,
+        width = precision.digits()
+    )
+}
+
+/// Formats a Unix timestamp as compact ISO 8601 UTC, e.g. `20240501T123456Z`,
+/// which is safe to put in file names on every platform.
+pub fn format_basic_utc(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let seconds_of_day = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+// Days since 1970-01-01 to a proleptic Gregorian (year, month, day), after
+// Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,83 +1,714 @@
+use byteorder::{ByteOrder, LittleEndian};
 use log::debug;
 use log::{error, info, warn};
 use pcap::{Active, Capture};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::radiotap::RadiotapParser;
+use crate::control_frames::{ControlFrameStats, ControlFrameTracker};
+use crate::eapol::{parse_eap, redact_identity, EapInfo, EapMethod, ETHERTYPE_EAPOL};
+use crate::error::WifiError;
+use crate::karma::{KarmaAlert, KarmaDetector};
+use crate::probing::ProbeRequest;
+use crate::radiotap::{
+    AccessCategory, AuthAlgorithm, RadiotapData, RadiotapParser, SsidState, WiFiFrame,
+};
+use crate::regulatory::{parse_country, CountryInfo};
+use crate::roaming::parse_roaming;
+use crate::security::{advertises_sae, classify, PmfStatus};
+use crate::spectrum::{
+    channel_number, classify_phy, operating_channel, wifi_generation, Band, ChannelFlags,
+    Modulation,
+};
+
+/// Why a scan finished.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanCompletion {
+    /// No new BSSID appeared for the settling period
+    Stabilized,
+    Timeout,
+    Stopped,
+    CaptureFailed,
+}
+
+/// 802.11 frame classes, by the type field of the frame control.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameClass {
+    Management,
+    Control,
+    Data,
+}
+
+impl FrameClass {
+    fn from_type(frame_type: u16) -> Option<Self> {
+        match frame_type {
+            0 => Some(FrameClass::Management),
+            1 => Some(FrameClass::Control),
+            2 => Some(FrameClass::Data),
+            // Type 3 is the extension type (DMG beacons)
+            _ => None,
+        }
+    }
+}
+
+/// Order of the networks in scan results. Ties fall back to the BSSID so
+/// rows keep their place between progress updates.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+// Apart from the default, keys only come from the frontend through
+// Deserialize, which dead code analysis doesn't count as constructing them
+#[allow(dead_code)]
+pub enum NetworkSortKey {
+    /// Strongest first
+    #[default]
+    Signal,
+    /// Lowest channel first
+    Channel,
+    /// Alphabetical, case-insensitive
+    Ssid,
+    /// Most recently heard first
+    LastSeen,
+}
+
+impl NetworkSortKey {
+    pub fn sort(self, networks: &mut [WiFiNetwork]) {
+        networks.sort_by(|a, b| {
+            let order = match self {
+                NetworkSortKey::Signal => b.signal_quality.cmp(&a.signal_quality),
+                NetworkSortKey::Channel => a.channel.cmp(&b.channel),
+                NetworkSortKey::Ssid => a.ssid.to_lowercase().cmp(&b.ssid.to_lowercase()),
+                NetworkSortKey::LastSeen => b.last_seen.cmp(&a.last_seen),
+            };
+            order.then_with(|| a.bssid.cmp(&b.bssid))
+        });
+    }
+}
+
+/// Management frame subtypes the scanner can capture.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MgmtSubtype {
+    Beacon,
+    ProbeReq,
+    ProbeResp,
+    Assoc,
+    Auth,
+    Deauth,
+    Disassoc,
+    Action,
+}
+
+impl MgmtSubtype {
+    fn from_frame(frame_type: u16, frame_subtype: u16) -> Option<Self> {
+        if frame_type != 0 {
+            return None;
+        }
+        match frame_subtype {
+            0..=3 => Some(MgmtSubtype::Assoc),
+            4 => Some(MgmtSubtype::ProbeReq),
+            5 => Some(MgmtSubtype::ProbeResp),
+            8 => Some(MgmtSubtype::Beacon),
+            10 => Some(MgmtSubtype::Disassoc),
+            11 => Some(MgmtSubtype::Auth),
+            12 => Some(MgmtSubtype::Deauth),
+            13 => Some(MgmtSubtype::Action),
+            _ => None,
+        }
+    }
+
+    /// The pcap filter expressions matching this subtype.
+    fn bpf_names(self) -> &'static [&'static str] {
+        match self {
+            MgmtSubtype::Beacon => &["beacon"],
+            MgmtSubtype::ProbeReq => &["probe-req"],
+            MgmtSubtype::ProbeResp => &["probe-resp"],
+            MgmtSubtype::Assoc => &["assoc-req", "assoc-resp", "reassoc-req", "reassoc-resp"],
+            MgmtSubtype::Auth => &["auth"],
+            MgmtSubtype::Deauth => &["deauth"],
+            MgmtSubtype::Disassoc => &["disassoc"],
+            MgmtSubtype::Action => &["action"],
+        }
+    }
+}
+
+/// Subtypes captured unless configured otherwise: beacons, plus the action
+/// and authentication frames the roaming and SAE checks read.
+pub const DEFAULT_SUBTYPES: [MgmtSubtype; 3] =
+    [MgmtSubtype::Beacon, MgmtSubtype::Action, MgmtSubtype::Auth];
+
+/// Payload of the "ssid_changed" event.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct SsidChange {
+    pub bssid: String,
+    pub previous: String,
+    pub current: String,
+    pub timestamp: u64,
+}
+
+/// Payload of the "auth_exchange" event: one Authentication frame.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct AuthExchange {
+    pub bssid: String,
+    pub client: String,
+    /// Whether the AP sent the frame rather than the client
+    pub from_ap: bool,
+    pub algorithm: AuthAlgorithm,
+    pub sequence: u16,
+    pub status: u16,
+    pub timestamp: u64,
+}
 
 #[derive(Clone, Debug)]
 pub struct ScanProgress {
     pub networks: Vec<WiFiNetwork>,
-    pub is_complete: bool,
+    /// Set on the final update only
+    pub completion: Option<ScanCompletion>,
+    /// Authentication frames seen since the previous update
+    pub auth_exchanges: Vec<AuthExchange>,
+    /// Probe requests seen since the previous update, if collected
+    pub probe_requests: Vec<ProbeRequest>,
+    /// Control frames per channel so far in the scan, if captured
+    pub control_frames: Vec<ControlFrameStats>,
+    /// BSSIDs flagged as KARMA responders since the previous update
+    pub karma_alerts: Vec<KarmaAlert>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct WiFiNetwork {
+    /// Empty for hidden networks until a probe response reveals the name
     pub ssid: String,
+    /// How the network's beacons present the SSID element
+    pub ssid_state: SsidState,
     pub bssid: String,
     pub signal_quality: u32,
     pub frequency: u32,
     pub channel: u32,
+    /// Width in MHz of the channel the AP operates on, 40/80/160 when it
+    /// bonds channels
+    pub channel_width_mhz: u32,
+    /// Center frequency in MHz of the whole, possibly bonded, channel
+    pub center_frequency: u32,
     pub security: String,
+    /// Protected Management Frames (802.11w) from the RSN capabilities
+    pub pmf: PmfStatus,
+    /// SAE hash-to-element is advertised in the RSNXE
+    pub sae_h2e: bool,
+    /// Wi-Fi Protected Setup is advertised
+    pub wps: bool,
+    /// 802.11v BSS Transition Management from the Extended Capabilities
+    pub supports_bss_transition: bool,
+    /// 802.11k neighbor reports from the RM Enabled Capabilities
+    pub supports_neighbor_report: bool,
+    /// 802.11r Fast BSS Transition, from a Mobility Domain element
+    pub ft_capable: bool,
+    /// 802.11ai Fast Initial Link Setup from the Extended Capabilities
+    pub fils_capable: bool,
+    /// 802.11 PHY generation: "b", "g", "a", "n", "ac", "ax" or "unknown"
+    pub phy_mode: String,
+    /// "Wi-Fi 4", "Wi-Fi 5", "Wi-Fi 6", "Wi-Fi 6E", "Legacy" or "Unknown"
+    pub wifi_generation: String,
+    /// Regulatory domain from the Country element, if advertised
+    pub country: Option<CountryInfo>,
+    /// Id of the session the network was recorded in, if any
+    pub session_id: Option<String>,
+    /// Surveyor's label for the BSSID, from `set_network_label`
+    pub label: Option<String>,
+    pub label_color: Option<String>,
+    pub first_seen: std::time::SystemTime,
     pub last_seen: std::time::SystemTime,
+    /// Seconds between the first and latest beacon of the current sighting
+    pub observed_duration_secs: u64,
+    /// Latest TSF timer value (µs) from the AP's beacons
+    pub tsf_timestamp: Option<u64>,
+    /// Approximate time since the AP started beaconing, from its TSF timer.
+    /// Some APs randomize or resync the TSF, so treat it as a hint.
+    pub estimated_uptime_secs: Option<u64>,
+    /// Advertised beacon interval in TUs (1024 µs)
+    pub beacon_interval: Option<u16>,
+    /// (Unix seconds, SSID) each time this BSSID started advertising a new
+    /// name, oldest first; the last entry is the current SSID
+    pub ssid_history: Vec<(u64, String)>,
     pub beacon_count: u32,
+    /// Beacons of the current sighting only, for rates over
+    /// `first_seen`..`last_seen`
+    pub sighting_beacon_count: u32,
     pub avg_signal: i32,
+    /// Weakest and strongest beacon signal in dBm since the network was found
+    pub min_signal: i32,
+    pub max_signal: i32,
+    pub qos_breakdown: QosBreakdown,
+    pub frame_count: u32,
+    pub retry_count: u32,
+    pub retry_rate: f32,
+    /// Seconds since the last beacon, as of the latest `get_networks` call
+    pub age_secs: u64,
+    /// Whether a beacon arrived within the liveness window
+    pub is_live: bool,
+    /// Latest (antenna index, dBm) pairs reported by multi-antenna adapters
+    pub antenna_signals: Vec<(u8, i8)>,
+    /// Strongest per-antenna signal from the latest beacon
+    pub best_antenna_signal: Option<i8>,
+    /// Standard deviation in dB of the recent beacon signal samples
+    pub signal_stddev: Option<f32>,
+    /// Channels this BSSID beaconed on within the last few seconds
+    pub recent_channels: Vec<u32>,
+    /// Beacons from this BSSID arrived on more than one channel at once,
+    /// which a real AP never does; stays set once detected
+    pub multi_channel_anomaly: bool,
+    /// Band from the radiotap channel flags of the latest beacon
+    pub radiotap_band: Option<Band>,
+    /// Modulation from the radiotap channel flags of the latest beacon
+    pub modulation: Option<Modulation>,
+    /// The radiotap channel flags, the radiotap frequency and the DS
+    /// Parameter Set channel of the latest beacon disagree
+    pub channel_mismatch: bool,
+    /// Unprotected Action frames sent by the AP
+    pub action_frames: u32,
+    /// BSS Transition Management requests sent by the AP
+    pub btm_requests: u32,
+    /// BTM requests that threatened to disassociate the client
+    pub btm_disassociation_imminent: u32,
+    /// Authentication algorithms clients were seen using with this AP
+    pub observed_auth: Vec<AuthAlgorithm>,
+    /// Clients authenticated with SAE although the beacons don't advertise
+    /// it, so the beacon's security label may not tell the whole story
+    pub auth_mismatch: bool,
+    /// EAP methods heard in 802.1X exchanges with this AP
+    pub eap_methods: Vec<EapMethod>,
+    /// Identities clients gave in EAP identity responses, with the user
+    /// part hidden unless identity redaction is off
+    pub eap_identities: Vec<String>,
+    #[serde(skip)]
+    signal_history: VecDeque<i8>,
+    #[serde(skip)]
+    channel_sightings: Vec<(u32, Instant)>,
+}
+
+impl WiFiNetwork {
+    /// A network heard for the first time just now, before any of its
+    /// beacons are recorded.
+    pub fn new(
+        bssid: String,
+        ssid: String,
+        ssid_state: SsidState,
+        frequency: u32,
+        ds_channel: Option<u8>,
+    ) -> Self {
+        WiFiNetwork {
+            ssid,
+            ssid_state,
+            bssid,
+            signal_quality: 0,
+            frequency,
+            channel: channel_number(frequency, ds_channel),
+            channel_width_mhz: 20,
+            center_frequency: frequency,
+            security: String::new(),
+            pmf: PmfStatus::Disabled,
+            sae_h2e: false,
+            wps: false,
+            supports_bss_transition: false,
+            supports_neighbor_report: false,
+            ft_capable: false,
+            fils_capable: false,
+            phy_mode: String::new(),
+            wifi_generation: String::new(),
+            country: None,
+            session_id: None,
+            label: None,
+            label_color: None,
+            first_seen: std::time::SystemTime::now(),
+            last_seen: std::time::SystemTime::now(),
+            observed_duration_secs: 0,
+            tsf_timestamp: None,
+            estimated_uptime_secs: None,
+            beacon_interval: None,
+            ssid_history: Vec::new(),
+            beacon_count: 0,
+            sighting_beacon_count: 0,
+            avg_signal: 0,
+            min_signal: 0,
+            max_signal: 0,
+            qos_breakdown: QosBreakdown::default(),
+            frame_count: 0,
+            retry_count: 0,
+            retry_rate: 0.0,
+            age_secs: 0,
+            is_live: true,
+            antenna_signals: Vec::new(),
+            best_antenna_signal: None,
+            signal_stddev: None,
+            recent_channels: Vec::new(),
+            multi_channel_anomaly: false,
+            radiotap_band: None,
+            modulation: None,
+            channel_mismatch: false,
+            action_frames: 0,
+            btm_requests: 0,
+            btm_disassociation_imminent: 0,
+            observed_auth: Vec::new(),
+            auth_mismatch: false,
+            eap_methods: Vec::new(),
+            eap_identities: Vec::new(),
+            signal_history: VecDeque::with_capacity(SIGNAL_HISTORY_LEN),
+            channel_sightings: Vec::new(),
+        }
+    }
+
+    /// Updates liveness, counters and signal statistics for a received beacon.
+    fn record_beacon(&mut self, radiotap: &RadiotapData, retry: bool) {
+        self.last_seen = std::time::SystemTime::now();
+        self.beacon_count += 1;
+        self.sighting_beacon_count += 1;
+        self.record_frame(retry);
+
+        if !radiotap.antenna_signals.is_empty() {
+            self.antenna_signals = radiotap.antenna_signals.clone();
+            self.best_antenna_signal = self.antenna_signals.iter().map(|(_, s)| *s).max();
+        }
+
+        let signal = radiotap.antenna_signal;
+        // Safe signal quality calculation
+        if let Some(signal) = signal {
+            // Convert to positive scale
+            let normalized_signal = (signal + 100).max(0) as u32;
+            // Scale to 0-100 range, capping at 100
+            self.signal_quality = normalized_signal.saturating_mul(2).min(100);
+
+            debug!(
+                "Updated signal quality for {}: {} (raw: {} dBm)",
+                self.ssid, self.signal_quality, signal
+            );
+
+            if self.signal_history.len() == SIGNAL_HISTORY_LEN {
+                self.signal_history.pop_front();
+            }
+            self.signal_history.push_back(signal);
+            self.signal_stddev = signal_stddev(&self.signal_history);
+
+            // Safe average signal calculation
+            let beacon_count = self.beacon_count as i32;
+            if beacon_count > 1 {
+                self.avg_signal =
+                    (self.avg_signal * (beacon_count - 1) + signal as i32) / beacon_count;
+            } else {
+                self.avg_signal = signal as i32;
+            }
+
+            // Beacons without a signal reading don't count as the first sample
+            if self.signal_history.len() > 1 {
+                self.min_signal = self.min_signal.min(signal as i32);
+                self.max_signal = self.max_signal.max(signal as i32);
+            } else {
+                self.min_signal = signal as i32;
+                self.max_signal = signal as i32;
+            }
+        }
+    }
+
+    /// Starts a new sighting of a network that dropped out of the results.
+    fn start_sighting(&mut self) {
+        self.first_seen = std::time::SystemTime::now();
+        self.sighting_beacon_count = 0;
+    }
+
+    /// Records the channel a beacon was heard on. Returns true the first
+    /// time beacons overlap on two channels.
+    fn record_channel(&mut self, channel: u32) -> bool {
+        if channel == 0 {
+            return false;
+        }
+
+        let now = Instant::now();
+        self.channel_sightings
+            .retain(|(seen, at)| *seen != channel && at.elapsed() < MULTI_CHANNEL_WINDOW);
+        self.channel_sightings.push((channel, now));
+
+        self.recent_channels = self.channel_sightings.iter().map(|(c, _)| *c).collect();
+        self.recent_channels.sort_unstable();
+
+        let newly_flagged = !self.multi_channel_anomaly && self.recent_channels.len() > 1;
+        self.multi_channel_anomaly |= newly_flagged;
+        newly_flagged
+    }
+
+    fn record_frame(&mut self, retry: bool) {
+        self.frame_count += 1;
+        if retry {
+            self.retry_count += 1;
+        }
+        self.retry_rate = self.retry_count as f32 / self.frame_count as f32;
+    }
+}
+
+/// Population standard deviation of `samples`, or None with fewer than two.
+fn signal_stddev(samples: &VecDeque<i8>) -> Option<f32> {
+    if samples.len() < 2 {
+        return None;
+    }
+
+    let count = samples.len() as f32;
+    let mean = samples.iter().map(|&s| s as f32).sum::<f32>() / count;
+    let variance = samples
+        .iter()
+        .map(|&s| (s as f32 - mean).powi(2))
+        .sum::<f32>()
+        / count;
+    Some(variance.sqrt())
+}
+
+/// Number of QoS data frames observed per WMM access category.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct QosBreakdown {
+    pub background: u32,
+    pub best_effort: u32,
+    pub video: u32,
+    pub voice: u32,
+}
+
+impl QosBreakdown {
+    fn record(&mut self, category: AccessCategory) {
+        match category {
+            AccessCategory::Background => self.background += 1,
+            AccessCategory::BestEffort => self.best_effort += 1,
+            AccessCategory::Video => self.video += 1,
+            AccessCategory::Voice => self.voice += 1,
+        }
+    }
+}
+
+/// Default capture length for the scanner. Beacons from 802.11ax APs with
+/// long HE/vendor IEs routinely exceed 2 KB.
+pub const DEFAULT_SNAPLEN: i32 = 4096;
+
+/// Networks heard within this window are flagged as currently visible.
+const LIVE_WINDOW: Duration = Duration::from_secs(10);
+pub const DEFAULT_RETENTION_SECS: u64 = 60;
+/// Networks can't be dropped while they're still live, and after a day the
+/// list mostly holds networks long out of range.
+pub const MIN_RETENTION_SECS: u64 = LIVE_WINDOW.as_secs();
+pub const MAX_RETENTION_SECS: u64 = 24 * 60 * 60;
+pub const DEFAULT_BEACON_THROTTLE_MS: u64 = 200;
+/// A signal change at least this large bypasses the beacon throttle.
+const THROTTLE_SIGNAL_DELTA_DBM: i32 = 5;
+/// Beacons from one BSSID on different channels within this window are
+/// treated as simultaneous. Long enough to span a few beacon intervals,
+/// short enough that a legitimate channel change isn't flagged.
+const MULTI_CHANNEL_WINDOW: Duration = Duration::from_secs(2);
+/// Beacon signal samples kept per BSSID for the stability metric.
+const SIGNAL_HISTORY_LEN: usize = 32;
+pub const DEFAULT_SCAN_TIMEOUT_SECS: u64 = 10;
+/// Adaptive scans run at least this long before they can settle.
+const MIN_ADAPTIVE_SCAN: Duration = Duration::from_secs(3);
+/// How often a broadcast probe request is sent when active probing is on.
+const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+/// Authentication frames buffered between two progress updates.
+const MAX_PENDING_AUTH_EXCHANGES: usize = 256;
+/// Probe requests buffered between two progress updates.
+const MAX_PENDING_PROBE_REQUESTS: usize = 256;
+/// Distinct EAP identities kept per network.
+const MAX_EAP_IDENTITIES: usize = 64;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScannerConfig {
+    /// Bytes captured per frame. Larger values keep trailing IEs (RSN, VHT,
+    /// HE) intact at the cost of more memory and copying per frame; too small
+    /// a value silently cuts off the last IEs of big beacons.
+    pub snaplen: i32,
+    /// How long a network stays in the results after its last beacon. Kept
+    /// longer than the liveness window so slow-beaconing APs don't flap.
+    pub retention_secs: u64,
+    /// Minimum interval between full IE parses of beacons from one BSSID.
+    /// Beacons in between only update counters and signal. 0 disables it.
+    pub beacon_throttle_ms: u64,
+    /// Periodically inject broadcast probe requests so APs answer with probe
+    /// responses instead of waiting for their next beacon. Requires a driver
+    /// and adapter that support frame injection in monitor mode; if sending
+    /// fails the scan carries on passively.
+    pub active_probing: bool,
+    /// Upper bound on how long a scan runs.
+    pub scan_timeout_secs: u64,
+    /// Adaptive mode: end the scan early once no new BSSID has appeared for
+    /// this many seconds. None always scans until the timeout.
+    pub settle_secs: Option<u64>,
+    /// Bands to report networks from. None reports every band the adapter
+    /// hears; networks on other bands are ignored rather than listed.
+    pub bands: Option<Vec<Band>>,
+    /// Record the SSIDs client devices send probe requests for, which
+    /// reveals the networks they've joined before. Also lets KARMA detection
+    /// match responses against what clients asked for.
+    pub probe_requests: bool,
+    /// Management subtypes to capture. Beacons are always captured, and
+    /// active probing and probe request collection add the subtypes they
+    /// need on top.
+    pub subtypes: Vec<MgmtSubtype>,
+    /// Frame classes to capture. Networks are only discovered from
+    /// management frames, and data frames only count toward networks
+    /// already discovered.
+    pub frame_classes: Vec<FrameClass>,
+    /// Order of the networks in progress updates and results
+    pub sort_by: NetworkSortKey,
+    /// Hide the user part of EAP identities heard on enterprise networks
+    pub redact_eap_identities: bool,
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        ScannerConfig {
+            snaplen: DEFAULT_SNAPLEN,
+            retention_secs: DEFAULT_RETENTION_SECS,
+            beacon_throttle_ms: DEFAULT_BEACON_THROTTLE_MS,
+            active_probing: false,
+            scan_timeout_secs: DEFAULT_SCAN_TIMEOUT_SECS,
+            settle_secs: None,
+            bands: None,
+            probe_requests: false,
+            subtypes: DEFAULT_SUBTYPES.to_vec(),
+            frame_classes: vec![FrameClass::Management, FrameClass::Data],
+            sort_by: NetworkSortKey::default(),
+            redact_eap_identities: true,
+        }
+    }
 }
 
 pub struct WiFiScanner {
     networks: Arc<Mutex<HashMap<String, WiFiNetwork>>>,
     capture: Capture<Active>,
     stop_flag: Arc<Mutex<bool>>,
+    snaplen: i32,
+    retention: Duration,
+    beacon_throttle: Duration,
+    last_full_parse: HashMap<String, Instant>,
+    truncated_frames: u64,
+    active_probing: bool,
+    last_probe: Option<Instant>,
+    auth_exchanges: Vec<AuthExchange>,
+    bands: Option<Vec<Band>>,
+    collect_probe_requests: bool,
+    probe_requests: Vec<ProbeRequest>,
+    frame_classes: Vec<FrameClass>,
+    control_frames: ControlFrameTracker,
+    karma: KarmaDetector,
+    karma_alerts: Vec<KarmaAlert>,
+    sort_by: NetworkSortKey,
+    redact_eap_identities: bool,
 }
 
 impl WiFiScanner {
-    pub fn new(interface: &str) -> Result<Self, String> {
-        let mut capture = match Capture::from_device(interface)
-            .map_err(|e| e.to_string())?
+    pub fn new(interface: &str, config: &ScannerConfig) -> Result<Self, WifiError> {
+        let mut capture = Capture::from_device(interface)?
             .promisc(true)
-            .snaplen(2048)
+            .snaplen(config.snaplen)
             .timeout(100)
-            .open()
-        {
-            Ok(cap) => cap,
-            Err(e) => return Err(format!("Failed to open capture: {}", e)),
-        };
+            .open()?;
 
         capture
             .set_datalink(pcap::Linktype::IEEE802_11_RADIOTAP)
-            .map_err(|e| format!("Failed to set datalink type: {}", e))?;
+            .map_err(|e| {
+                WifiError::MonitorModeUnavailable(format!(
+                    "Failed to set radiotap datalink on {}: {}",
+                    interface, e
+                ))
+            })?;
 
-        let filter = "type mgt subtype beacon";
+        let filter = scan_filter(config);
         debug!("Setting pcap filter: {}", filter);
         capture
-            .filter(filter, true)
-            .map_err(|e| format!("Failed to set filter: {}", e))?;
+            .filter(&filter, true)
+            .map_err(|e| WifiError::CaptureError(format!("Failed to set filter: {}", e)))?;
 
         Ok(Self {
             networks: Arc::new(Mutex::new(HashMap::new())),
             capture,
             stop_flag: Arc::new(Mutex::new(false)),
+            snaplen: config.snaplen,
+            retention: Duration::from_secs(config.retention_secs),
+            beacon_throttle: Duration::from_millis(config.beacon_throttle_ms),
+            last_full_parse: HashMap::new(),
+            truncated_frames: 0,
+            active_probing: config.active_probing,
+            last_probe: None,
+            auth_exchanges: Vec::new(),
+            bands: config.bands.clone(),
+            collect_probe_requests: config.probe_requests,
+            probe_requests: Vec::new(),
+            frame_classes: config.frame_classes.clone(),
+            control_frames: ControlFrameTracker::default(),
+            karma: KarmaDetector::default(),
+            karma_alerts: Vec::new(),
+            sort_by: config.sort_by,
+            redact_eap_identities: config.redact_eap_identities,
         })
     }
 
-    pub fn start_scanning(&mut self) -> Result<(), String> {
+    /// Sends a wildcard probe request if active probing is on and one is due.
+    /// The first injection failure switches the scan back to passive.
+    fn probe_if_due(&mut self) {
+        if !self.active_probing
+            || self
+                .last_probe
+                .is_some_and(|at| at.elapsed() < PROBE_INTERVAL)
+        {
+            return;
+        }
+
+        self.last_probe = Some(Instant::now());
+        if let Err(e) = self.capture.sendpacket(probe_request_frame()) {
+            warn!(
+                "Probe request injection failed ({}); the adapter may not support injection. \
+                 Falling back to passive scanning",
+                e
+            );
+            self.active_probing = false;
+        }
+    }
+
+    /// Records frames cut short by the snaplen, since their trailing IEs are lost.
+    fn note_truncation(&mut self, caplen: u32, len: u32) {
+        if caplen >= len {
+            return;
+        }
+
+        self.truncated_frames += 1;
+        if self.truncated_frames == 1 {
+            warn!(
+                "Frame truncated at snaplen {} ({} of {} bytes); trailing IEs will be missing. \
+                 Consider raising the scanner snaplen",
+                self.snaplen, caplen, len
+            );
+        } else {
+            debug!(
+                "Frame truncated at snaplen ({} of {} bytes), {} so far",
+                caplen, len, self.truncated_frames
+            );
+        }
+    }
+
+    pub fn start_scanning(&mut self) -> Result<(), WifiError> {
         info!("Starting WiFi scan");
         *self.stop_flag.lock().unwrap() = false;
 
         while !*self.stop_flag.lock().unwrap() {
-            let packet_data = match self.capture.next_packet() {
-                Ok(packet) => packet.data.to_vec(),
+            let (packet_data, caplen, len) = match self.capture.next_packet() {
+                Ok(packet) => (
+                    packet.data.to_vec(),
+                    packet.header.caplen,
+                    packet.header.len,
+                ),
                 Err(pcap::Error::TimeoutExpired) => continue,
                 Err(e) => {
                     error!("Error capturing packet: {}", e);
                     break;
                 }
             };
+            self.note_truncation(caplen, len);
 
             match self.process_packet(&packet_data) {
                 Ok(_) => (),
@@ -92,123 +723,463 @@ impl WiFiScanner {
         *self.stop_flag.lock().unwrap() = true;
     }
 
-    fn process_packet(&self, data: &[u8]) -> Result<(), String> {
+    fn process_packet(&mut self, data: &[u8]) -> Result<(), WifiError> {
         debug!("Processing packet of size: {} bytes", data.len());
 
         if data.len() < 8 {
-            return Err(format!("Packet too small: {} bytes", data.len()));
+            return Err(WifiError::ParseError(format!(
+                "Packet too small: {} bytes",
+                data.len()
+            )));
         }
 
-        let mut parser = RadiotapParser::new(data);
-        match parser.parse_wifi_frame() {
-            Ok(frame) => {
-                // Only process beacon frames (type = 0, subtype = 8)
-                let frame_type = (frame.frame_control & 0x000C) >> 2;
-                let frame_subtype = (frame.frame_control & 0x00F0) >> 4;
+        // Control frames are too short for the three-address header, so the
+        // class is read from the frame control before parsing any further
+        let Some(frame_control) = read_frame_u16(data, 0) else {
+            debug!("Skipping frame without a readable frame control");
+            return Ok(());
+        };
+        let Some(class) = FrameClass::from_type((frame_control & 0x000C) >> 2) else {
+            debug!("Skipping extension frame");
+            return Ok(());
+        };
+        if !self.frame_classes.contains(&class) {
+            return Ok(());
+        }
+        if class == FrameClass::Control {
+            self.process_control_frame(data, frame_control);
+            return Ok(());
+        }
 
-                debug!(
-                    "Frame type: {}, subtype: {}, frame control: {:04X}",
-                    frame_type, frame_subtype, frame.frame_control
+        // Parse headers only first; tagged parameters are parsed on demand
+        let header = match RadiotapParser::new(data).parse_frame_header() {
+            Ok(header) => header,
+            // Cut-short frames are routine with a small snaplen; anything
+            // else means the buffer isn't the radiotap we asked for
+            Err(e) if e.is_truncation() => {
+                debug!("Skipping truncated frame: {}", e);
+                return Ok(());
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to parse packet: {}. First 16 bytes: {:02X?}",
+                    e,
+                    &data[..16.min(data.len())]
                 );
+                return Ok(());
+            }
+        };
 
-                if frame_type == 0 && (frame_subtype == 8) {
-                    if let Some(ssid) = frame.ssid {
-                        // Skip hidden networks
-                        if ssid.is_empty() {
-                            debug!("Skipping hidden network");
-                            return Ok(());
-                        }
+        debug!(
+            "Frame class: {:?}, frame control: {:04X}",
+            class, header.frame_control
+        );
+        match class {
+            FrameClass::Management => self.process_management_frame(data, &header),
+            FrameClass::Data => self.process_data_frame(data, &header),
+            FrameClass::Control => {}
+        }
+        Ok(())
+    }
 
-                        let bssid = format!(
-                            "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
-                            frame.addr3[0],
-                            frame.addr3[1],
-                            frame.addr3[2],
-                            frame.addr3[3],
-                            frame.addr3[4],
-                            frame.addr3[5]
-                        );
+    fn process_management_frame(&mut self, data: &[u8], header: &WiFiFrame) {
+        let frame_subtype = (header.frame_control & 0x00F0) >> 4;
 
-                        debug!("Processing network - SSID: {}, BSSID: {}", ssid, bssid);
-
-                        if let Ok(mut networks) = self.networks.lock() {
-                            let network = networks.entry(bssid.clone()).or_insert_with(|| {
-                                info!("Found new network: {} ({})", ssid, bssid);
-                                WiFiNetwork {
-                                    ssid: ssid.clone(),
-                                    bssid: bssid.clone(),
-                                    signal_quality: 0,
-                                    frequency: frame.radiotap.channel_freq.unwrap_or(0) as u32,
-                                    channel: frame.channel.unwrap_or(0) as u32,
-                                    security: parse_security_info(frame.frame_control),
-                                    last_seen: std::time::SystemTime::now(),
-                                    beacon_count: 0,
-                                    avg_signal: 0,
-                                }
-                            });
-
-                            network.last_seen = std::time::SystemTime::now();
-                            network.beacon_count += 1;
-
-                            // Safe signal quality calculation
-                            if let Some(signal) = frame.radiotap.antenna_signal {
-                                // Convert to positive scale
-                                let normalized_signal = (signal + 100).max(0) as u32;
-                                // Scale to 0-100 range, capping at 100
-                                network.signal_quality =
-                                    normalized_signal.saturating_mul(2).min(100);
-
-                                debug!(
-                                    "Updated signal quality for {}: {} (raw: {} dBm)",
-                                    ssid, network.signal_quality, signal
-                                );
-
-                                // Safe average signal calculation
-                                let beacon_count = network.beacon_count as i32;
-                                if beacon_count > 1 {
-                                    network.avg_signal = (network.avg_signal * (beacon_count - 1)
-                                        + signal as i32)
-                                        / beacon_count;
-                                } else {
-                                    network.avg_signal = signal as i32;
-                                }
-                            }
-                        } else {
-                            warn!("Failed to acquire lock for networks");
-                        }
-                    } else {
-                        debug!("Skipping frame with no SSID");
+        // Beacons and probe responses (subtype 5); the filter only lets
+        // probe responses through when they're wanted
+        if frame_subtype == 8 || frame_subtype == 5 {
+            let bssid = format_mac(&header.addr3);
+            // Recorded for every frame, throttled or not, so a responder
+            // cycling through SSIDs faster than the throttle is still seen
+            let ssid = read_leading_ssid(data).unwrap_or_default();
+            if let Some(alert) = self.karma.record_advertised(&bssid, &ssid, unix_secs()) {
+                warn!(
+                    "{} answers for {} SSIDs, possible KARMA responder",
+                    alert.bssid,
+                    alert.ssids.len()
+                );
+                self.karma_alerts.push(alert);
+            }
+
+            if self.is_throttled(&bssid, header.radiotap.antenna_signal) {
+                // Keep beacon statistics current without re-parsing the IEs
+                if let Ok(mut networks) = self.networks.lock() {
+                    if let Some(network) = networks.get_mut(&bssid) {
+                        network.record_beacon(&header.radiotap, header.retry);
                     }
-                } else {
-                    debug!("Skipping non-beacon/probe frame");
                 }
-                Ok(())
+                return;
             }
-            Err(e) => {
+
+            match RadiotapParser::new(data).parse_wifi_frame() {
+                Ok(frame) => {
+                    self.process_beacon(frame);
+                    self.last_full_parse.insert(bssid, Instant::now());
+                }
+                Err(e) => warn!("Failed to parse beacon: {}", e),
+            }
+        } else if frame_subtype == 4 && self.collect_probe_requests {
+            match RadiotapParser::new(data).parse_wifi_frame() {
+                Ok(frame) => self.process_probe_request(&frame),
+                Err(e) => debug!("Failed to parse probe request: {}", e),
+            }
+        } else if frame_subtype == 13 {
+            self.process_action_frame(header);
+        } else if frame_subtype == 11 {
+            self.process_auth_frame(header);
+        } else if let Some(subtype) = MgmtSubtype::from_frame(0, frame_subtype) {
+            debug!("No scanner handling for {:?} frames yet", subtype);
+        } else {
+            debug!("Skipping management frame subtype {}", frame_subtype);
+        }
+    }
+
+    fn process_control_frame(&mut self, data: &[u8], frame_control: u16) {
+        let Ok(radiotap) = RadiotapParser::new(data).parse_radiotap_header() else {
+            return;
+        };
+        // The Duration field follows the frame control
+        let start = radiotap.length as usize + 2;
+        let duration = data.get(start..start + 2).map(LittleEndian::read_u16);
+        self.control_frames.record(
+            radiotap.channel_freq.unwrap_or(0) as u32,
+            frame_control,
+            duration,
+        );
+    }
+
+    /// Whether a beacon from `bssid` can skip the full IE parse: it was fully
+    /// processed within the throttle interval and its signal hasn't moved much.
+    fn is_throttled(&self, bssid: &str, signal: Option<i8>) -> bool {
+        if self.beacon_throttle.is_zero() {
+            return false;
+        }
+
+        let recently_parsed = self
+            .last_full_parse
+            .get(bssid)
+            .is_some_and(|at| at.elapsed() < self.beacon_throttle);
+        if !recently_parsed {
+            return false;
+        }
+
+        match (signal, self.networks.lock()) {
+            (Some(signal), Ok(networks)) => networks.get(bssid).is_some_and(|network| {
+                (signal as i32 - network.avg_signal).abs() < THROTTLE_SIGNAL_DELTA_DBM
+            }),
+            (None, Ok(networks)) => networks.contains_key(bssid),
+            (_, Err(_)) => false,
+        }
+    }
+
+    fn process_beacon(&self, frame: WiFiFrame) {
+        if let Some(bands) = &self.bands {
+            let freq = frame.radiotap.channel_freq.unwrap_or(0) as u32;
+            if !Band::from_frequency(freq).is_some_and(|band| bands.contains(&band)) {
+                return;
+            }
+        }
+
+        let phy_mode = phy_mode(&frame);
+
+        // Probe responses name hidden networks but don't say how they beacon
+        let is_beacon = (frame.frame_control & 0x00F0) >> 4 == 8;
+        let ssid = frame.ssid.clone().unwrap_or_default();
+        let bssid = format_mac(&frame.addr3);
+
+        debug!(
+            "Processing network - SSID: {:?}, BSSID: {}",
+            frame.ssid_state, bssid
+        );
+
+        let security = classify(frame.capability_info, &frame.information_elements);
+
+        if let Ok(mut networks) = self.networks.lock() {
+            let network = networks.entry(bssid.clone()).or_insert_with(|| {
+                info!("Found new network: {} ({})", ssid, bssid);
+                WiFiNetwork::new(
+                    bssid.clone(),
+                    ssid.clone(),
+                    frame.ssid_state.clone(),
+                    frame.radiotap.channel_freq.unwrap_or(0) as u32,
+                    frame.channel,
+                )
+            });
+
+            // A network that dropped out of the results and came back is a
+            // new sighting, not one continuous observation
+            if network.last_seen.elapsed().unwrap_or_default() >= self.retention {
+                info!("Network {} ({}) reappeared", network.ssid, bssid);
+                network.start_sighting();
+            }
+
+            if is_beacon {
+                network.ssid_state = frame.ssid_state.clone();
+            }
+            if !ssid.is_empty() && network.ssid_history.last().map(|(_, s)| s) != Some(&ssid) {
+                if !network.ssid_history.is_empty() {
+                    warn!(
+                        "BSSID {} changed SSID from {} to {}",
+                        bssid, network.ssid, ssid
+                    );
+                }
+                network.ssid_history.push((unix_secs(), ssid.clone()));
+                network.ssid = ssid;
+            }
+
+            // Security can change when an AP is reconfigured, so refresh it
+            network.beacon_interval = frame.beacon_interval.or(network.beacon_interval);
+            if let Some(tsf) = frame.tsf_timestamp.filter(|_| is_beacon) {
+                if network.tsf_timestamp.is_some_and(|previous| tsf < previous) {
+                    info!("TSF of {} went backwards, the AP likely restarted", bssid);
+                }
+                network.tsf_timestamp = Some(tsf);
+                network.estimated_uptime_secs = Some(tsf / 1_000_000);
+            }
+            network.security = security.label;
+            network.pmf = security.pmf;
+            network.sae_h2e = security.sae_h2e;
+            network.wps = security.wps;
+            let roaming = parse_roaming(&frame.information_elements);
+            network.supports_bss_transition = roaming.bss_transition;
+            network.supports_neighbor_report = roaming.neighbor_report;
+            network.ft_capable = roaming.fast_transition;
+            network.fils_capable = roaming.fils;
+            let beacon_channel = channel_number(
+                frame.radiotap.channel_freq.unwrap_or(0) as u32,
+                frame.channel,
+            );
+            if let Some(flags) = frame.radiotap.channel_flags.map(ChannelFlags::decode) {
+                let mismatch = !flags.agrees_with(
+                    frame.radiotap.channel_freq.unwrap_or(0) as u32,
+                    frame.channel,
+                );
+                if mismatch && !network.channel_mismatch {
+                    debug!(
+                        "{} ({}): radiotap flags {:?} at {} MHz disagree with DS channel {:?}",
+                        network.ssid, bssid, flags.band, network.frequency, frame.channel
+                    );
+                }
+                network.radiotap_band = flags.band;
+                network.modulation = flags.modulation;
+                network.channel_mismatch = mismatch;
+            }
+            if network.record_channel(beacon_channel) {
                 warn!(
-                    "Failed to parse packet: {}. First 16 bytes: {:02X?}",
-                    e,
-                    &data[..16.min(data.len())]
+                    "BSSID {} ({}) is beaconing on channels {:?} at once, possible spoofing",
+                    bssid, network.ssid, network.recent_channels
                 );
-                Ok(())
             }
+
+            network.wifi_generation =
+                wifi_generation(&phy_mode, Band::from_frequency(network.frequency));
+            network.phy_mode = phy_mode;
+            network.country = parse_country(&frame.information_elements);
+            // On 2.4 GHz the frame may have been heard from a neighboring channel
+            let primary_freq = Band::from_frequency(network.frequency)
+                .map(|band| band.channel_frequency(network.channel))
+                .unwrap_or(network.frequency);
+            (network.channel_width_mhz, network.center_frequency) =
+                operating_channel(&frame.information_elements, primary_freq);
+            network.record_beacon(&frame.radiotap, frame.retry);
+        } else {
+            warn!("Failed to acquire lock for networks");
+        }
+    }
+
+    fn process_data_frame(&self, data: &[u8], frame: &WiFiFrame) {
+        // The BSSID's position depends on the ToDS/FromDS bits
+        let bssid = match frame.frame_control & 0x0300 {
+            0x0100 => format_mac(&frame.addr1),
+            0x0200 => format_mac(&frame.addr2),
+            0x0000 => format_mac(&frame.addr3),
+            // WDS frames don't belong to a single BSS
+            _ => return,
+        };
+
+        if let Ok(mut networks) = self.networks.lock() {
+            // Only account traffic for networks we've seen beacons from
+            if let Some(network) = networks.get_mut(&bssid) {
+                network.record_frame(frame.retry);
+
+                if let Some(qos_control) = frame.qos_control {
+                    let category = AccessCategory::from_qos_control(qos_control);
+                    network.qos_breakdown.record(category);
+                    debug!("QoS data frame for {}: {:?}", bssid, category);
+                }
+
+                let eap = frame
+                    .llc_snap
+                    .as_ref()
+                    .filter(|llc| llc.ethertype == ETHERTYPE_EAPOL)
+                    .and_then(|llc| parse_eap(&data[llc.payload_start..llc.payload_end]));
+                match eap {
+                    Some(EapInfo::Method(method)) if !network.eap_methods.contains(&method) => {
+                        info!("{} ({}) uses EAP method {:?}", network.ssid, bssid, method);
+                        network.eap_methods.push(method);
+                    }
+                    Some(EapInfo::Identity(identity)) => {
+                        let identity = if self.redact_eap_identities {
+                            redact_identity(&identity)
+                        } else {
+                            identity
+                        };
+                        if !network.eap_identities.contains(&identity)
+                            && network.eap_identities.len() < MAX_EAP_IDENTITIES
+                        {
+                            network.eap_identities.push(identity);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            warn!("Failed to acquire lock for networks");
+        }
+    }
+
+    fn process_action_frame(&self, frame: &WiFiFrame) {
+        let Some(action) = &frame.action else {
+            return;
+        };
+        debug!(
+            "Action frame from {}: {}",
+            format_mac(&frame.addr2),
+            action.describe()
+        );
+
+        // Only count what APs we've seen beacons from send themselves
+        if frame.addr2 != frame.addr3 {
+            return;
+        }
+        let bssid = format_mac(&frame.addr3);
+
+        if let Ok(mut networks) = self.networks.lock() {
+            if let Some(network) = networks.get_mut(&bssid) {
+                network.action_frames += 1;
+
+                if let Some(btm) = &action.btm_request {
+                    network.btm_requests += 1;
+                    if btm.disassociation_imminent {
+                        network.btm_disassociation_imminent += 1;
+                        info!(
+                            "{} ({}) is forcing {} to roam (disassociation in {} TBTTs)",
+                            network.ssid,
+                            bssid,
+                            format_mac(&frame.addr1),
+                            btm.disassociation_timer
+                        );
+                    }
+                }
+            }
+        } else {
+            warn!("Failed to acquire lock for networks");
+        }
+    }
+
+    fn process_auth_frame(&mut self, frame: &WiFiFrame) {
+        let Some(auth) = frame.auth else {
+            return;
+        };
+
+        let from_ap = frame.addr2 == frame.addr3;
+        let client = if from_ap { &frame.addr1 } else { &frame.addr2 };
+        let exchange = AuthExchange {
+            bssid: format_mac(&frame.addr3),
+            client: format_mac(client),
+            from_ap,
+            algorithm: auth.algorithm,
+            sequence: auth.sequence,
+            status: auth.status,
+            timestamp: unix_secs(),
+        };
+        debug!(
+            "{} authentication between {} and {}, seq {}, status {}",
+            auth.algorithm, exchange.client, exchange.bssid, auth.sequence, auth.status
+        );
+
+        if let Ok(mut networks) = self.networks.lock() {
+            if let Some(network) = networks.get_mut(&exchange.bssid) {
+                if !network.observed_auth.contains(&auth.algorithm) {
+                    network.observed_auth.push(auth.algorithm);
+                }
+                if auth.algorithm == AuthAlgorithm::Sae
+                    && !advertises_sae(&network.security)
+                    && !network.auth_mismatch
+                {
+                    warn!(
+                        "{} ({}) advertises {} but clients authenticate with SAE",
+                        network.ssid, exchange.bssid, network.security
+                    );
+                    network.auth_mismatch = true;
+                }
+            }
+        } else {
+            warn!("Failed to acquire lock for networks");
+        }
+
+        // Progress updates drain these; a flood between two updates is capped
+        if self.auth_exchanges.len() < MAX_PENDING_AUTH_EXCHANGES {
+            self.auth_exchanges.push(exchange);
+        }
+    }
+
+    fn process_probe_request(&mut self, frame: &WiFiFrame) {
+        // A zero-length SSID is the wildcard; anything else unnamed is malformed
+        let ssid = match &frame.ssid_state {
+            SsidState::Broadcast(name) => Some(name.clone()),
+            SsidState::ZeroLength => None,
+            SsidState::NullHidden | SsidState::Absent => return,
+        };
+        let probe = ProbeRequest {
+            client: format_mac(&frame.addr2),
+            ssid,
+            timestamp: unix_secs(),
+        };
+        if let Some(ssid) = &probe.ssid {
+            self.karma.record_probe(ssid, probe.timestamp);
+        }
+        debug!("Probe request from {} for {:?}", probe.client, probe.ssid);
+
+        // Progress updates drain these; a flood between two updates is capped
+        if self.probe_requests.len() < MAX_PENDING_PROBE_REQUESTS {
+            self.probe_requests.push(probe);
         }
     }
 
+    fn network_count(&self) -> usize {
+        self.networks.lock().map(|n| n.len()).unwrap_or(0)
+    }
+
     pub fn get_networks(&self) -> Vec<WiFiNetwork> {
         match self.networks.lock() {
             Ok(networks) => {
-                let result: Vec<WiFiNetwork> = networks
+                let mut result: Vec<WiFiNetwork> = networks
                     .values()
-                    .filter(|network| {
-                        network.last_seen.elapsed().unwrap_or_default() < Duration::from_secs(10)
+                    .filter_map(|network| {
+                        let age = network.last_seen.elapsed().unwrap_or_default();
+                        if age >= self.retention {
+                            return None;
+                        }
+
+                        let mut network = network.clone();
+                        network.age_secs = age.as_secs();
+                        network.is_live = age < LIVE_WINDOW;
+                        network.observed_duration_secs = network
+                            .last_seen
+                            .duration_since(network.first_seen)
+                            .unwrap_or_default()
+                            .as_secs();
+                        Some(network)
                     })
-                    .cloned()
                     .collect();
+                // HashMap order changes between calls
+                self.sort_by.sort(&mut result);
 
                 info!(
-                    "Retrieved {} networks (total in cache: {})",
+                    "Retrieved {} networks ({} live, total in cache: {})",
                     result.len(),
+                    result.iter().filter(|n| n.is_live).count(),
                     networks.len()
                 );
 
@@ -222,44 +1193,173 @@ impl WiFiScanner {
     }
 }
 
-fn parse_security_info(frame_control: u16) -> String {
-    // Extract capability information bits
-    let privacy = (frame_control & 0x0010) != 0;
+fn unix_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A broadcast probe request with the wildcard SSID, behind a minimal
+/// radiotap header. The source is a fixed locally administered address,
+/// since pcap doesn't expose the adapter's MAC.
+fn probe_request_frame() -> Vec<u8> {
+    let mut frame = vec![
+        // Radiotap: version 0, length 8, no fields present
+        0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00,
+        // Frame control (management, probe request), duration
+        0x40, 0x00, 0x00, 0x00,
+    ];
+    frame.extend_from_slice(&[0xFF; 6]); // Receiver: broadcast
+    frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x57, 0x41, 0x31]); // Transmitter
+    frame.extend_from_slice(&[0xFF; 6]); // BSSID: wildcard
+    frame.extend_from_slice(&[0x00, 0x00]); // Sequence control
+    frame.extend_from_slice(&[0x00, 0x00]); // Wildcard SSID
+                                            // Supported rates: 1, 2, 5.5, 11, 6, 9, 12, 18 Mbps
+    frame.extend_from_slice(&[0x01, 0x08, 0x82, 0x84, 0x8B, 0x96, 0x0C, 0x12, 0x18, 0x24]);
+    frame
+}
+
+fn format_mac(addr: &[u8; 6]) -> String {
+    format!(
+        "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+        addr[0], addr[1], addr[2], addr[3], addr[4], addr[5]
+    )
+}
+
+// Capability elements that identify the PHY generation
+const IE_HT_CAPABILITIES: u8 = 45;
+const IE_VHT_CAPABILITIES: u8 = 191;
+const IE_EXTENSION: u8 = 255;
+const IE_EXT_HE_CAPABILITIES: u8 = 35;
+
+fn phy_mode(frame: &WiFiFrame) -> String {
+    let elements = &frame.information_elements;
+    let has = |id: u8| elements.iter().any(|ie| ie.id == id);
+    let has_he = elements
+        .iter()
+        .any(|ie| ie.id == IE_EXTENSION && ie.data.first() == Some(&IE_EXT_HE_CAPABILITIES));
+
+    // Without a radiotap frequency, the DS channel tells 2.4 GHz apart
+    let freq = frame.radiotap.channel_freq.unwrap_or(0) as u32;
+    let band = Band::from_frequency(freq).unwrap_or(match frame.channel {
+        Some(channel) if channel > 14 => Band::Five,
+        _ => Band::TwoPointFour,
+    });
+
+    classify_phy(
+        &frame.rates,
+        band,
+        has(IE_HT_CAPABILITIES),
+        has(IE_VHT_CAPABILITIES),
+        has_he,
+    )
+}
 
-    if privacy {
-        "WPA/WPA2".to_string()
-    } else {
-        "Open".to_string()
+/// Reads the little-endian u16 `offset` bytes into the 802.11 frame behind
+/// the radiotap header.
+fn read_frame_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let radiotap = RadiotapParser::new(data).parse_radiotap_header().ok()?;
+    let start = radiotap.length as usize + offset;
+    data.get(start..start + 2).map(LittleEndian::read_u16)
+}
+
+/// The visible SSID of a beacon or probe response, read straight from its
+/// first element (the standard puts the SSID there) without parsing the rest.
+fn read_leading_ssid(data: &[u8]) -> Option<String> {
+    let radiotap = RadiotapParser::new(data).parse_radiotap_header().ok()?;
+    // Management header (24) + timestamp, beacon interval, capability (12)
+    let start = radiotap.length as usize + 36;
+    let (&id, &len) = (data.get(start)?, data.get(start + 1)?);
+    let ssid = data.get(start + 2..start + 2 + len as usize)?;
+    (id == 0 && ssid.iter().any(|&b| b != 0)).then(|| String::from_utf8_lossy(ssid).into_owned())
+}
+
+/// Builds the scanner's pcap filter from the configured frame classes and,
+/// for management frames, subtypes.
+pub fn scan_filter(config: &ScannerConfig) -> String {
+    let mut subtypes = vec![MgmtSubtype::Beacon];
+    subtypes.extend(&config.subtypes);
+    if config.active_probing {
+        subtypes.push(MgmtSubtype::ProbeResp);
+    }
+    if config.probe_requests {
+        subtypes.push(MgmtSubtype::ProbeReq);
+    }
+
+    let mut filter: Vec<String> = Vec::new();
+    if config.frame_classes.contains(&FrameClass::Management) {
+        for name in subtypes.iter().flat_map(|subtype| subtype.bpf_names()) {
+            let expression = format!("(type mgt subtype {})", name);
+            if !filter.contains(&expression) {
+                filter.push(expression);
+            }
+        }
     }
+    if config.frame_classes.contains(&FrameClass::Control) {
+        filter.push("type ctl".to_string());
+    }
+    if config.frame_classes.contains(&FrameClass::Data) {
+        filter.push("type data".to_string());
+    }
+    filter.join(" or ")
 }
 
 pub fn scan_wifi_internal(
     interface: &str,
-) -> Result<(Sender<()>, std::sync::mpsc::Receiver<ScanProgress>), String> {
+    config: &ScannerConfig,
+) -> Result<(Sender<()>, std::sync::mpsc::Receiver<ScanProgress>), WifiError> {
     info!("Initializing WiFi scanner for interface: {}", interface);
 
-    let scanner = Arc::new(Mutex::new(WiFiScanner::new(interface)?));
+    let scanner = Arc::new(Mutex::new(WiFiScanner::new(interface, config)?));
     let scanner_clone = Arc::clone(&scanner);
 
     let (progress_tx, progress_rx) = channel();
     let (stop_tx, stop_rx) = channel();
+    let timeout = Duration::from_secs(config.scan_timeout_secs);
+    let settle = config.settle_secs.map(Duration::from_secs);
 
     info!("Starting scan...");
     thread::spawn(move || {
         if let Ok(mut scanner) = scanner_clone.lock() {
             let mut last_update_time = std::time::Instant::now();
             let update_interval = Duration::from_millis(500); // Reduced interval for more frequent updates
+            let started = Instant::now();
+            let mut last_discovery = started;
+            let mut known_networks = 0;
+
+            let completion = loop {
+                if stop_rx.try_recv().is_ok() {
+                    break ScanCompletion::Stopped;
+                }
+                if started.elapsed() >= timeout {
+                    break ScanCompletion::Timeout;
+                }
+                if let Some(settle) = settle {
+                    if started.elapsed() >= MIN_ADAPTIVE_SCAN && last_discovery.elapsed() >= settle
+                    {
+                        break ScanCompletion::Stabilized;
+                    }
+                }
 
-            while stop_rx.try_recv().is_err() {
-                let packet_data = match scanner.capture.next_packet() {
-                    Ok(packet) => packet.data.to_vec(),
+                scanner.probe_if_due();
+                let (packet_data, caplen, len) = match scanner.capture.next_packet() {
+                    Ok(packet) => (
+                        packet.data.to_vec(),
+                        packet.header.caplen,
+                        packet.header.len,
+                    ),
                     Err(pcap::Error::TimeoutExpired) => {
                         // Send progress update even on timeout
                         if last_update_time.elapsed() >= update_interval {
                             let current_networks = scanner.get_networks();
                             let progress = ScanProgress {
                                 networks: current_networks,
-                                is_complete: false,
+                                completion: None,
+                                auth_exchanges: std::mem::take(&mut scanner.auth_exchanges),
+                                probe_requests: std::mem::take(&mut scanner.probe_requests),
+                                control_frames: scanner.control_frames.stats(),
+                                karma_alerts: std::mem::take(&mut scanner.karma_alerts),
                             };
                             if let Err(e) = progress_tx.send(progress) {
                                 warn!("Failed to send progress update: {}", e);
@@ -270,14 +1370,21 @@ pub fn scan_wifi_internal(
                     }
                     Err(e) => {
                         error!("Error capturing packet: {}", e);
-                        break;
+                        break ScanCompletion::CaptureFailed;
                     }
                 };
 
+                scanner.note_truncation(caplen, len);
                 if let Err(e) = scanner.process_packet(&packet_data) {
                     warn!("Error processing packet: {}", e);
                 }
 
+                let network_count = scanner.network_count();
+                if network_count > known_networks {
+                    known_networks = network_count;
+                    last_discovery = Instant::now();
+                }
+
                 // Send progress update if interval elapsed
                 if last_update_time.elapsed() >= update_interval {
                     let current_networks = scanner.get_networks();
@@ -287,24 +1394,35 @@ pub fn scan_wifi_internal(
                     );
                     let progress = ScanProgress {
                         networks: current_networks,
-                        is_complete: false,
+                        completion: None,
+                        auth_exchanges: std::mem::take(&mut scanner.auth_exchanges),
+                        probe_requests: std::mem::take(&mut scanner.probe_requests),
+                        control_frames: scanner.control_frames.stats(),
+                        karma_alerts: std::mem::take(&mut scanner.karma_alerts),
                     };
                     if let Err(e) = progress_tx.send(progress) {
                         warn!("Failed to send progress update: {}", e);
                     }
                     last_update_time = std::time::Instant::now();
                 }
-            }
+            };
 
             // Send final update with actual networks
             let final_networks = scanner.get_networks();
             info!(
-                "Scan completed, sending final update with {} networks",
-                final_networks.len()
+                "Scan completed ({:?}) after {:?}, sending final update with {} networks ({} frames truncated by snaplen)",
+                completion,
+                started.elapsed(),
+                final_networks.len(),
+                scanner.truncated_frames
             );
             let progress = ScanProgress {
                 networks: final_networks,
-                is_complete: true,
+                completion: Some(completion),
+                auth_exchanges: std::mem::take(&mut scanner.auth_exchanges),
+                probe_requests: std::mem::take(&mut scanner.probe_requests),
+                control_frames: scanner.control_frames.stats(),
+                karma_alerts: std::mem::take(&mut scanner.karma_alerts),
             };
             if let Err(e) = progress_tx.send(progress) {
                 warn!("Failed to send final progress update: {}", e);
@@ -314,3 +1432,176 @@ pub fn scan_wifi_internal(
 
     Ok((stop_tx, progress_rx))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(frame_classes: &[FrameClass], subtypes: &[MgmtSubtype]) -> ScannerConfig {
+        ScannerConfig {
+            frame_classes: frame_classes.to_vec(),
+            subtypes: subtypes.to_vec(),
+            ..ScannerConfig::default()
+        }
+    }
+
+    #[test]
+    fn default_scan_filter() {
+        assert_eq!(
+            scan_filter(&ScannerConfig::default()),
+            "(type mgt subtype beacon) or (type mgt subtype action) \
+             or (type mgt subtype auth) or type data"
+        );
+    }
+
+    #[test]
+    fn beacons_are_always_captured() {
+        let config = config(&[FrameClass::Management], &[]);
+        assert_eq!(scan_filter(&config), "(type mgt subtype beacon)");
+    }
+
+    #[test]
+    fn subtypes_expand_and_dedupe() {
+        let config = config(
+            &[FrameClass::Management],
+            &[MgmtSubtype::Assoc, MgmtSubtype::Beacon, MgmtSubtype::Assoc],
+        );
+        assert_eq!(
+            scan_filter(&config),
+            "(type mgt subtype beacon) or (type mgt subtype assoc-req) \
+             or (type mgt subtype assoc-resp) or (type mgt subtype reassoc-req) \
+             or (type mgt subtype reassoc-resp)"
+        );
+    }
+
+    #[test]
+    fn probing_options_add_their_subtypes() {
+        let mut config = config(&[FrameClass::Management], &[MgmtSubtype::ProbeResp]);
+        config.active_probing = true;
+        config.probe_requests = true;
+        assert_eq!(
+            scan_filter(&config),
+            "(type mgt subtype beacon) or (type mgt subtype probe-resp) \
+             or (type mgt subtype probe-req)"
+        );
+    }
+
+    #[test]
+    fn subtypes_are_ignored_without_management_frames() {
+        let mut config = config(
+            &[FrameClass::Control, FrameClass::Data],
+            &[MgmtSubtype::Deauth, MgmtSubtype::Disassoc],
+        );
+        config.probe_requests = true;
+        assert_eq!(scan_filter(&config), "type ctl or type data");
+        config.frame_classes = vec![FrameClass::Control];
+        assert_eq!(scan_filter(&config), "type ctl");
+    }
+
+    #[test]
+    fn every_frame_class() {
+        let config = config(
+            &[
+                FrameClass::Data,
+                FrameClass::Control,
+                FrameClass::Management,
+            ],
+            &[MgmtSubtype::Deauth],
+        );
+        // Classes come out in a fixed order whatever order they're configured in
+        assert_eq!(
+            scan_filter(&config),
+            "(type mgt subtype beacon) or (type mgt subtype deauth) or type ctl or type data"
+        );
+    }
+
+    /// A radiotap header without fields, then a beacon whose first element is `first`.
+    fn beacon(first: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0, 0, 8, 0, 0, 0, 0, 0];
+        frame.extend_from_slice(&[0x80, 0x00, 0, 0]);
+        frame.extend_from_slice(&[0xFF; 6]);
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&[0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(&[0; 8]);
+        frame.extend_from_slice(&[100, 0, 0x01, 0x00]);
+        frame.extend_from_slice(first);
+        frame
+    }
+
+    #[test]
+    fn leading_ssid_of_beacons() {
+        assert_eq!(
+            read_leading_ssid(&beacon(&[0, 4, b'h', b'o', b'm', b'e'])).as_deref(),
+            Some("home")
+        );
+        // Hidden: zero length or null padded
+        assert_eq!(read_leading_ssid(&beacon(&[0, 0])), None);
+        assert_eq!(read_leading_ssid(&beacon(&[0, 3, 0, 0, 0])), None);
+        // Not an SSID element, or one cut short
+        assert_eq!(read_leading_ssid(&beacon(&[3, 1, 6])), None);
+        assert_eq!(read_leading_ssid(&beacon(&[0, 8, b'h', b'o'])), None);
+        assert_eq!(read_leading_ssid(&beacon(&[])), None);
+    }
+
+    fn sortable(
+        bssid: &str,
+        ssid: &str,
+        channel: u32,
+        signal_quality: u32,
+        age_secs: u64,
+    ) -> WiFiNetwork {
+        let mut network = WiFiNetwork::new(
+            bssid.to_string(),
+            ssid.to_string(),
+            SsidState::Broadcast(ssid.to_string()),
+            2412,
+            Some(channel as u8),
+        );
+        network.signal_quality = signal_quality;
+        network.last_seen = std::time::UNIX_EPOCH + Duration::from_secs(1_000 - age_secs);
+        network
+    }
+
+    fn sorted_by(key: NetworkSortKey) -> Vec<String> {
+        let mut networks = [
+            sortable("00:00:00:00:00:03", "beta", 6, 50, 30),
+            sortable("00:00:00:00:00:01", "Alpha", 11, 80, 10),
+            sortable("00:00:00:00:00:02", "gamma", 1, 50, 20),
+            sortable("00:00:00:00:00:04", "alpha", 6, 20, 10),
+        ];
+        key.sort(&mut networks);
+        networks.iter().map(|n| n.bssid[15..].to_string()).collect()
+    }
+
+    #[test]
+    fn sort_keys_order_networks_with_bssid_tie_breaks() {
+        assert_eq!(sorted_by(NetworkSortKey::Signal), ["01", "02", "03", "04"]);
+        assert_eq!(sorted_by(NetworkSortKey::Channel), ["02", "03", "04", "01"]);
+        assert_eq!(sorted_by(NetworkSortKey::Ssid), ["01", "04", "03", "02"]);
+        assert_eq!(
+            sorted_by(NetworkSortKey::LastSeen),
+            ["01", "04", "02", "03"]
+        );
+    }
+
+    #[test]
+    fn new_sighting_restarts_the_rate_window() {
+        let mut network = WiFiNetwork::new(
+            "00:00:00:00:00:01".to_string(),
+            "home".to_string(),
+            SsidState::Broadcast("home".to_string()),
+            2437,
+            Some(6),
+        );
+        network.beacon_count = 500;
+        network.sighting_beacon_count = 500;
+        network.first_seen = std::time::UNIX_EPOCH;
+
+        network.start_sighting();
+
+        assert_eq!(network.sighting_beacon_count, 0);
+        assert_eq!(network.beacon_count, 500);
+        assert!(network.first_seen > std::time::UNIX_EPOCH);
+    }
+}
@@ -1,16 +1,84 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
+export type SsidState =
+  | { state: "broadcast"; ssid: string }
+  | { state: "null_hidden" }
+  | { state: "zero_length" }
+  | { state: "absent" };
+
+export type PmfStatus = "disabled" | "capable" | "required";
+
+export interface CountryInfo {
+  code: string;
+  channels: number[];
+}
+
 export interface WiFiNetwork {
   ssid: string;
+  ssid_state: SsidState;
   bssid: string;
   signal_quality: number;
   frequency: number;
   channel: number;
+  channel_width_mhz: number;
+  center_frequency: number;
   security: string;
+  pmf: PmfStatus;
+  sae_h2e: boolean;
+  wps: boolean;
+  supports_bss_transition: boolean;
+  supports_neighbor_report: boolean;
+  ft_capable: boolean;
+  fils_capable: boolean;
+  phy_mode: string;
+  wifi_generation: string;
+  country: CountryInfo | null;
+  session_id: string | null;
+  label: string | null;
+  label_color: string | null;
   avg_signal: number;
+  min_signal: number;
+  max_signal: number;
   beacon_count: number;
+  sighting_beacon_count: number;
+  first_seen: number;
   last_seen: number;
+  observed_duration_secs: number;
+  tsf_timestamp: number | null;
+  estimated_uptime_secs: number | null;
+  beacon_interval: number | null;
+  ssid_history: [number, string][];
+  qos_breakdown: QosBreakdown;
+  frame_count: number;
+  retry_count: number;
+  retry_rate: number;
+  age_secs: number;
+  is_live: boolean;
+  antenna_signals: [number, number][];
+  best_antenna_signal: number | null;
+  signal_stddev: number | null;
+  recent_channels: number[];
+  multi_channel_anomaly: boolean;
+  radiotap_band: Band | null;
+  modulation: Modulation | null;
+  channel_mismatch: boolean;
+  action_frames: number;
+  btm_requests: number;
+  btm_disassociation_imminent: number;
+  observed_auth: AuthAlgorithm[];
+  auth_mismatch: boolean;
+  eap_methods: EapMethod[];
+  eap_identities: string[];
+}
+
+export type Modulation = "cck" | "ofdm" | "dynamic_cck_ofdm" | "gfsk";
+
+export interface QosBreakdown {
+  background: number;
+  best_effort: number;
+  video: number;
+  voice: number;
 }
 
 export interface PacketInfo {
@@ -18,23 +86,194 @@ export interface PacketInfo {
   dst_mac: string;
   src_ip: string | null;
   dst_ip: string | null;
+  src_ip_scope: IpScope | null;
+  dst_ip_scope: IpScope | null;
+  src_host: string | null;
+  dst_host: string | null;
   src_port: number | null;
   dst_port: number | null;
   protocol: string;
   length: number;
   payload: string | null;
   timestamp: number;
+  timestamp_iso: string;
+  eapol: EapolKeyInfo | null;
+  dhcp: DhcpInfo | null;
+  reassembled: boolean;
+  wifi: WifiFrameInfo | null;
+  raw_hex: string | null;
+  layers: Layer[];
+  session_id: string | null;
+  malformed: MalformedReason | null;
+}
+
+export type Layer =
+  | { layer: "ethernet"; src_mac: string; dst_mac: string; ethertype: number }
+  | { layer: "loopback"; family: number }
+  | { layer: "vlan"; id: number; priority: number; ethertype: number }
+  | {
+      layer: "arp";
+      operation: number;
+      sender_mac: string;
+      sender_ip: string;
+      target_ip: string;
+    }
+  | {
+      layer: "ipv4";
+      src_ip: string;
+      dst_ip: string;
+      protocol: number;
+      fragment: boolean;
+    }
+  | { layer: "ipv6"; src_ip: string; dst_ip: string; next_header: number }
+  | { layer: "tcp"; src_port: number; dst_port: number }
+  | { layer: "udp"; src_port: number; dst_port: number }
+  | { layer: "sctp"; src_port: number; dst_port: number; chunks: string[] }
+  | { layer: "quic"; version: string }
+  | ({ layer: "dhcp" } & DhcpInfo)
+  | {
+      layer: "dns";
+      id: number;
+      response: boolean;
+      query: string | null;
+      answers: string[];
+    }
+  | { layer: "http"; payload: string; host: string | null }
+  | ({ layer: "eapol" } & EapolKeyInfo)
+  | ({ layer: "ieee80211" } & WifiFrameInfo)
+  | { layer: "llc_snap"; ethertype: number }
+  | {
+      layer: "pppoe";
+      stage: string;
+      session_id: number;
+      ppp_protocol: string | null;
+    };
+
+export interface WifiFrameInfo {
+  frame_type: number;
+  frame_subtype: number;
+  bssid: string;
+  signal_dbm: number | null;
+  channel_freq: number | null;
+  tsft: number | null;
+  action: ActionFrame | null;
+  auth: AuthFrame | null;
+  ssid: string | null;
+}
+
+export type AuthAlgorithm =
+  | "open_system"
+  | "shared_key"
+  | "fast_transition"
+  | "sae"
+  | "fils"
+  | { other: number };
+
+export type EapMethod =
+  | "md5"
+  | "tls"
+  | "sim"
+  | "ttls"
+  | "aka"
+  | "peap"
+  | "ms_chap_v2"
+  | "fast"
+  | "aka_prime"
+  | { other: number };
+
+export interface AuthFrame {
+  algorithm: AuthAlgorithm;
+  sequence: number;
+  status: number;
+}
+
+export interface AuthExchange {
+  bssid: string;
+  client: string;
+  from_ap: boolean;
+  algorithm: AuthAlgorithm;
+  sequence: number;
+  status: number;
+  timestamp: number;
+}
+
+export interface ActionFrame {
+  category: number;
+  action: number;
+  btm_request: BtmRequest | null;
+}
+
+export interface BtmRequest {
+  candidate_list: boolean;
+  disassociation_imminent: boolean;
+  bss_termination_included: boolean;
+  ess_disassociation_imminent: boolean;
+  disassociation_timer: number;
+}
+
+export interface EapolKeyInfo {
+  key_info: number;
+  replay_counter: number;
+  handshake_message: number | null;
+}
+
+export interface DhcpInfo {
+  message_type: number | null;
+  client_mac: string;
+  requested_ip: string | null;
+}
+
+export interface HandshakeStatus {
+  ap_mac: string;
+  client_mac: string;
+  messages_seen: [boolean, boolean, boolean, boolean];
+  complete: boolean;
+  first_seen: number;
+  last_seen: number;
+}
+
+export type WifiErrorKind =
+  | "device_not_found"
+  | "device_busy"
+  | "permission_denied"
+  | "monitor_mode_unavailable"
+  | "parse_error"
+  | "capture_error"
+  | "session_error";
+
+export interface WifiError {
+  kind: WifiErrorKind;
+  message: string;
 }
 
+export type ChannelSeverity = "clear" | "moderate" | "congested";
+
 export interface ChannelData {
+  band: Band;
   channel: number;
   occupancy: number;
+  beacon_weighted_occupancy: number;
+  severity: ChannelSeverity;
+}
+
+export interface CongestionThresholds {
+  moderate: number;
+  congested: number;
 }
 
-export async function scanWifi(): Promise<WiFiNetwork[]> {
+export interface ChannelReport {
+  channels: ChannelData[];
+  thresholds: CongestionThresholds;
+}
+
+export type NetworkSortKey = "signal" | "channel" | "ssid" | "last_seen";
+
+export async function scanWifi(
+  sortBy?: NetworkSortKey,
+): Promise<WiFiNetwork[]> {
   try {
     console.log("Starting WiFi scan...");
-    const networks = await invoke<WiFiNetwork[]>("scan_wifi");
+    const networks = await invoke<WiFiNetwork[]>("scan_wifi", { sortBy });
     console.log("Scan completed, found networks:", networks);
     return networks;
   } catch (error) {
@@ -43,62 +282,1451 @@ export async function scanWifi(): Promise<WiFiNetwork[]> {
   }
 }
 
-export async function getChannelData(
-  networks: WiFiNetwork[],
-): Promise<ChannelData[]> {
+export interface InterfaceScan {
+  interface: string;
+  error: WifiError | null;
+  network_count: number;
+  bssids: string[];
+  average_rssi: number | null;
+}
+
+export interface InterfaceComparison {
+  a: InterfaceScan;
+  b: InterfaceScan;
+  only_a: string[];
+  only_b: string[];
+  common: number;
+}
+
+export async function compareInterfaces(
+  ifaceA: string,
+  ifaceB: string,
+  durationSecs: number,
+): Promise<InterfaceComparison> {
   try {
-    const channelData = await invoke<ChannelData[]>("get_channel_data", {
-      networks,
+    return await invoke<InterfaceComparison>("compare_interfaces", {
+      ifaceA,
+      ifaceB,
+      durationSecs,
     });
-    return channelData;
   } catch (error) {
-    console.error("Failed to get channel data:", error);
+    console.error("Failed to compare interfaces:", error);
     throw error;
   }
 }
 
-export async function listDevices(): Promise<string[]> {
+export interface NetworkLabel {
+  bssid: string;
+  label: string;
+  color: string | null;
+}
+
+export async function setNetworkLabel(
+  bssid: string,
+  label: string,
+  color?: string,
+): Promise<NetworkLabel | null> {
   try {
-    const devices = await invoke<string[]>("list_devices");
-    return devices;
+    return await invoke<NetworkLabel | null>("set_network_label", {
+      bssid,
+      label,
+      color,
+    });
   } catch (error) {
-    console.error("Failed to list network devices:", error);
+    console.error("Failed to set network label:", error);
     throw error;
   }
 }
 
-export async function startPacketCapture(deviceName: string): Promise<void> {
+export async function getNetworkLabels(): Promise<NetworkLabel[]> {
   try {
-    await invoke("start_packet_capture", { deviceName });
+    return await invoke<NetworkLabel[]>("get_network_labels");
   } catch (error) {
-    console.error("Failed to start packet capture:", error);
+    console.error("Failed to get network labels:", error);
     throw error;
   }
 }
 
-export async function stopPacketCapture(): Promise<void> {
+export async function setScanSnaplen(snaplen: number): Promise<void> {
   try {
-    await invoke("stop_packet_capture");
+    await invoke("set_scan_snaplen", { snaplen });
   } catch (error) {
-    console.error("Failed to stop packet capture:", error);
+    console.error("Failed to set scan snaplen:", error);
     throw error;
   }
 }
 
-export async function listenForPackets(
-  callback: (packet: PacketInfo) => void,
+export async function setNetworkRetention(
+  retentionSecs: number,
 ): Promise<void> {
-  await listen<PacketInfo>("packet", (event) => {
+  try {
+    await invoke("set_network_retention", { retentionSecs });
+  } catch (error) {
+    console.error("Failed to set network retention:", error);
+    throw error;
+  }
+}
+
+export async function setBeaconThrottle(ms: number): Promise<void> {
+  try {
+    await invoke("set_beacon_throttle", { ms });
+  } catch (error) {
+    console.error("Failed to set beacon throttle:", error);
+    throw error;
+  }
+}
+
+export async function setAdaptiveScan(
+  settleSecs: number | null,
+): Promise<void> {
+  try {
+    await invoke("set_adaptive_scan", { settleSecs });
+  } catch (error) {
+    console.error("Failed to set adaptive scan:", error);
+    throw error;
+  }
+}
+
+export type ScanCompletion =
+  | "stabilized"
+  | "timeout"
+  | "stopped"
+  | "capture_failed";
+
+export async function listenForScanComplete(
+  callback: (completion: ScanCompletion) => void,
+): Promise<void> {
+  await listen<ScanCompletion>("wifi_scan_complete", (event) => {
     callback(event.payload);
   });
 }
 
-export async function getLatestPackets(): Promise<PacketInfo[]> {
+export interface SsidChange {
+  bssid: string;
+  previous: string;
+  current: string;
+  timestamp: number;
+}
+
+export async function listenForSsidChanges(
+  callback: (change: SsidChange) => void,
+): Promise<void> {
+  await listen<SsidChange>("ssid_changed", (event) => {
+    callback(event.payload);
+  });
+}
+
+export async function listenForMultiChannelBssids(
+  callback: (network: WiFiNetwork) => void,
+): Promise<void> {
+  await listen<WiFiNetwork>("multi_channel_bssid", (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface SignalReading {
+  bssid: string;
+  signal_dbm: number | null;
+  frames: number;
+}
+
+export async function monitorBssidSignal(bssid: string, frequency: number): Promise<void> {
   try {
-    const latestPackets = await invoke<PacketInfo[]>("get_latest_packets");
-    return latestPackets;
+    await invoke("monitor_bssid_signal", { bssid, frequency });
   } catch (error) {
-    console.error("Failed to get latest packets:", error);
+    console.error("Failed to start signal meter:", error);
+    throw error;
+  }
+}
+
+export async function stopBssidMonitor(): Promise<void> {
+  try {
+    await invoke("stop_bssid_monitor");
+  } catch (error) {
+    console.error("Failed to stop signal meter:", error);
+    throw error;
+  }
+}
+
+export async function listenForBssidSignal(
+  callback: (reading: SignalReading) => void,
+): Promise<void> {
+  await listen<SignalReading>("bssid_signal", (event) => {
+    callback(event.payload);
+  });
+}
+
+export async function listenForAuthExchanges(
+  callback: (exchange: AuthExchange) => void,
+): Promise<void> {
+  await listen<AuthExchange>("auth_exchange", (event) => {
+    callback(event.payload);
+  });
+}
+
+export async function setActiveProbing(enabled: boolean): Promise<void> {
+  try {
+    await invoke("set_active_probing", { enabled });
+  } catch (error) {
+    console.error("Failed to set active probing:", error);
+    throw error;
+  }
+}
+
+export type MgmtSubtype =
+  | "beacon"
+  | "probe_req"
+  | "probe_resp"
+  | "assoc"
+  | "auth"
+  | "deauth"
+  | "disassoc"
+  | "action";
+
+export async function setScanSubtypes(subtypes: MgmtSubtype[]): Promise<void> {
+  try {
+    await invoke("set_scan_subtypes", { subtypes });
+  } catch (error) {
+    console.error("Failed to set scan subtypes:", error);
+    throw error;
+  }
+}
+
+export type FrameClass = "management" | "control" | "data";
+
+export async function setFrameClassFilter(classes: FrameClass[]): Promise<void> {
+  try {
+    await invoke("set_frame_class_filter", { classes });
+  } catch (error) {
+    console.error("Failed to set frame class filter:", error);
+    throw error;
+  }
+}
+
+export interface ControlTypeStats {
+  frames: number;
+  airtime_us: number;
+}
+
+export interface ControlFrameStats {
+  channel: number;
+  frequency: number;
+  rts: ControlTypeStats;
+  cts: ControlTypeStats;
+  ack: ControlTypeStats;
+  block_ack_request: number;
+  block_ack: number;
+  other: number;
+  rts_cts_ratio: number | null;
+  hidden_node_suspected: boolean;
+}
+
+export async function getControlFrameStats(): Promise<ControlFrameStats[]> {
+  try {
+    return await invoke<ControlFrameStats[]>("get_control_frame_stats");
+  } catch (error) {
+    console.error("Failed to get control frame stats:", error);
+    throw error;
+  }
+}
+
+export interface KarmaAlert {
+  bssid: string;
+  ssids: string[];
+  probed_ssids: string[];
+  timestamp: number;
+}
+
+export async function listenForKarmaAttacks(
+  callback: (alert: KarmaAlert) => void,
+): Promise<void> {
+  await listen<KarmaAlert>("karma_attack", (event) => {
+    callback(event.payload);
+  });
+}
+
+export async function setProbeRequestCollection(
+  enabled: boolean,
+): Promise<void> {
+  try {
+    await invoke("set_probe_request_collection", { enabled });
+  } catch (error) {
+    console.error("Failed to set probe request collection:", error);
+    throw error;
+  }
+}
+
+export async function setEapIdentityRedaction(
+  enabled: boolean,
+): Promise<void> {
+  try {
+    await invoke("set_eap_identity_redaction", { enabled });
+  } catch (error) {
+    console.error("Failed to set EAP identity redaction:", error);
+    throw error;
+  }
+}
+
+export interface ProbingClient {
+  mac: string;
+  vendor: string | null;
+  ssids: string[];
+  wildcard_probes: number;
+  first_seen: number;
+  last_seen: number;
+}
+
+export async function getProbingClients(): Promise<ProbingClient[]> {
+  try {
+    return await invoke<ProbingClient[]>("get_probing_clients");
+  } catch (error) {
+    console.error("Failed to get probing clients:", error);
+    throw error;
+  }
+}
+
+export async function clearProbingClients(): Promise<void> {
+  try {
+    await invoke("clear_probing_clients");
+  } catch (error) {
+    console.error("Failed to clear probing clients:", error);
+    throw error;
+  }
+}
+
+export interface BandSupport {
+  band: Band;
+  channels: number[];
+}
+
+export interface InterfaceCapabilities {
+  interface: string;
+  phy: string;
+  bands: BandSupport[];
+}
+
+export async function getInterfaceCapabilities(
+  iface: string,
+): Promise<InterfaceCapabilities> {
+  try {
+    return await invoke<InterfaceCapabilities>("get_interface_capabilities", {
+      interface: iface,
+    });
+  } catch (error) {
+    console.error("Failed to get interface capabilities:", error);
+    throw error;
+  }
+}
+
+export async function setScanBands(bands: Band[] | null): Promise<void> {
+  try {
+    await invoke("set_scan_bands", { bands });
+  } catch (error) {
+    console.error("Failed to set scan bands:", error);
+    throw error;
+  }
+}
+
+export async function getChannelData(
+  networks: WiFiNetwork[],
+  band?: Band,
+): Promise<ChannelReport> {
+  try {
+    const channelReport = await invoke<ChannelReport>("get_channel_data", {
+      networks,
+      band,
+    });
+    return channelReport;
+  } catch (error) {
+    console.error("Failed to get channel data:", error);
+    throw error;
+  }
+}
+
+export async function setCongestionThresholds(
+  moderate: number,
+  congested: number,
+): Promise<CongestionThresholds> {
+  try {
+    return await invoke<CongestionThresholds>("set_congestion_thresholds", {
+      moderate,
+      congested,
+    });
+  } catch (error) {
+    console.error("Failed to set congestion thresholds:", error);
+    throw error;
+  }
+}
+
+export interface ChannelRecommendation {
+  band: Band;
+  channel: number;
+  frequency: number;
+  occupancy: number;
+  severity: ChannelSeverity;
+  dfs: boolean;
+  reasons: string[];
+}
+
+export interface NeighborLoad {
+  networks: number;
+  total_signal: number;
+}
+
+export interface ChannelChoice {
+  band: Band;
+  channel: number;
+  co_channel: NeighborLoad;
+  overlapping: NeighborLoad;
+  verdict: string;
+}
+
+export async function evaluateChannelChoice(
+  channel: number,
+  band: Band,
+  networks: WiFiNetwork[],
+): Promise<ChannelChoice> {
+  try {
+    return await invoke<ChannelChoice>("evaluate_channel_choice", {
+      channel,
+      band,
+      networks,
+    });
+  } catch (error) {
+    console.error("Failed to evaluate channel choice:", error);
+    throw error;
+  }
+}
+
+export async function recommendChannel(
+  networks: WiFiNetwork[],
+  band?: Band,
+): Promise<ChannelRecommendation> {
+  try {
+    return await invoke<ChannelRecommendation>("recommend_channel", {
+      networks,
+      band,
+    });
+  } catch (error) {
+    console.error("Failed to recommend channel:", error);
+    throw error;
+  }
+}
+
+export interface OccupancyTimeline {
+  timestamps_ms: number[];
+  channels: { band: Band; channel: number; occupancy: number[] }[];
+}
+
+export async function getOccupancyTimeline(): Promise<OccupancyTimeline> {
+  try {
+    return await invoke<OccupancyTimeline>("get_occupancy_timeline");
+  } catch (error) {
+    console.error("Failed to get occupancy timeline:", error);
+    throw error;
+  }
+}
+
+export type Band = "2.4GHz" | "5GHz" | "6GHz";
+
+export interface NetworkRef {
+  ssid: string;
+  bssid: string;
+  signal: number;
+}
+
+export interface BandSummary {
+  band: Band;
+  network_count: number;
+  average_signal: number | null;
+  strongest_network: NetworkRef | null;
+  most_congested_channel: number | null;
+}
+
+export async function getBandSummary(
+  networks: WiFiNetwork[],
+): Promise<BandSummary[]> {
+  try {
+    return await invoke<BandSummary[]>("get_band_summary", { networks });
+  } catch (error) {
+    console.error("Failed to get band summary:", error);
+    throw error;
+  }
+}
+
+export type AnomalyKind =
+  | "interval_drift"
+  | "ssid_changed"
+  | "beacon_flood"
+  | "multi_channel"
+  | "forced_roaming";
+
+export interface BeaconAnomaly {
+  bssid: string;
+  ssid: string;
+  kind: AnomalyKind;
+  detail: string;
+}
+
+export async function detectBeaconAnomalies(
+  networks: WiFiNetwork[],
+): Promise<BeaconAnomaly[]> {
+  try {
+    return await invoke<BeaconAnomaly[]>("detect_beacon_anomalies", {
+      networks,
+    });
+  } catch (error) {
+    console.error("Failed to detect beacon anomalies:", error);
+    throw error;
+  }
+}
+
+export interface ApGroupingOptions {
+  max_last_octet_delta?: number;
+  ignore_local_bit?: boolean;
+  require_same_channel?: boolean;
+  max_uptime_delta_secs?: number;
+}
+
+export interface PhysicalAp {
+  bssids: string[];
+  ssids: string[];
+  channel: number;
+  vendor: string | null;
+  estimated_uptime_secs: number | null;
+}
+
+export async function groupPhysicalAps(
+  networks: WiFiNetwork[],
+  options?: ApGroupingOptions,
+): Promise<PhysicalAp[]> {
+  try {
+    return await invoke<PhysicalAp[]>("group_physical_aps", {
+      networks,
+      options: options ?? null,
+    });
+  } catch (error) {
+    console.error("Failed to group physical APs:", error);
+    throw error;
+  }
+}
+
+export interface SecuritySummary {
+  network_count: number;
+  by_security: [string, number][];
+  wps_enabled: number;
+  hidden: number;
+  risk_score: number;
+}
+
+export async function securitySummary(
+  networks: WiFiNetwork[],
+): Promise<SecuritySummary> {
+  try {
+    return await invoke<SecuritySummary>("security_summary", { networks });
+  } catch (error) {
+    console.error("Failed to summarize security:", error);
+    throw error;
+  }
+}
+
+export interface DefaultNetwork {
+  bssid: string;
+  ssid: string;
+  vendor: string;
+  pattern: string;
+  wps: boolean;
+}
+
+export async function flagDefaultNetworks(
+  networks: WiFiNetwork[],
+): Promise<DefaultNetwork[]> {
+  try {
+    return await invoke<DefaultNetwork[]>("flag_default_networks", {
+      networks,
+    });
+  } catch (error) {
+    console.error("Failed to flag default networks:", error);
+    throw error;
+  }
+}
+
+export interface RegulatoryViolation {
+  bssid: string;
+  ssid: string;
+  country: string;
+  band: Band;
+  channel: number;
+  expected_channels: number[];
+  advertised_channels: number[];
+}
+
+export async function checkRegulatoryCompliance(
+  networks: WiFiNetwork[],
+): Promise<RegulatoryViolation[]> {
+  try {
+    return await invoke<RegulatoryViolation[]>("check_regulatory_compliance", {
+      networks,
+    });
+  } catch (error) {
+    console.error("Failed to check regulatory compliance:", error);
+    throw error;
+  }
+}
+
+export interface InterferenceMatrix {
+  band: Band;
+  channels: number[];
+  matrix: (number | null)[][];
+}
+
+export async function interferenceMatrix(
+  networks: WiFiNetwork[],
+  band: Band,
+): Promise<InterferenceMatrix> {
+  try {
+    return await invoke<InterferenceMatrix>("interference_matrix", {
+      networks,
+      band,
+    });
+  } catch (error) {
+    console.error("Failed to compute interference matrix:", error);
+    throw error;
+  }
+}
+
+export interface NetworkAudit {
+  ssid: string;
+  bssid_count: number;
+  channel_distribution: { channel: number; bssids: string[] }[];
+  warnings: string[];
+}
+
+export async function auditNetwork(
+  ssid: string,
+  networks: WiFiNetwork[],
+): Promise<NetworkAudit> {
+  try {
+    return await invoke<NetworkAudit>("audit_network", { ssid, networks });
+  } catch (error) {
+    console.error("Failed to audit network:", error);
+    throw error;
+  }
+}
+
+export async function listDevices(): Promise<string[]> {
+  try {
+    const devices = await invoke<string[]>("list_devices");
+    return devices;
+  } catch (error) {
+    console.error("Failed to list network devices:", error);
+    throw error;
+  }
+}
+
+export interface CurrentConnection {
+  interface: string;
+  bssid: string;
+  ssid: string;
+  frequency: number;
+  channel: number;
+  band: Band | null;
+  signal_dbm: number | null;
+}
+
+export async function getCurrentConnection(
+  iface: string,
+): Promise<CurrentConnection | null> {
+  try {
+    return await invoke<CurrentConnection | null>("get_current_connection", {
+      interface: iface,
+    });
+  } catch (error) {
+    console.error("Failed to get current connection:", error);
+    throw error;
+  }
+}
+
+export async function startPacketCapture(
+  deviceName: string,
+  packetLimit?: number,
+  maxDurationSecs?: number,
+): Promise<void> {
+  try {
+    await invoke("start_packet_capture", {
+      deviceName,
+      packetLimit: packetLimit ?? null,
+      maxDurationSecs: maxDurationSecs ?? null,
+    });
+  } catch (error) {
+    console.error("Failed to start packet capture:", error);
+    throw error;
+  }
+}
+
+export async function setCaptureFilter(filter: string): Promise<void> {
+  try {
+    await invoke("set_capture_filter", { filter });
+  } catch (error) {
+    console.error("Failed to set capture filter:", error);
+    throw error;
+  }
+}
+
+export type ProtocolName = "TCP" | "UDP" | "DNS" | "HTTP" | "ARP" | "ICMP";
+
+export async function setProtocolFilters(
+  protocols: ProtocolName[],
+): Promise<string> {
+  try {
+    return await invoke<string>("set_protocol_filters", { protocols });
+  } catch (error) {
+    console.error("Failed to set protocol filters:", error);
+    throw error;
+  }
+}
+
+export async function getCaptureFilter(): Promise<string | null> {
+  try {
+    return await invoke<string | null>("get_capture_filter");
+  } catch (error) {
+    console.error("Failed to get capture filter:", error);
+    throw error;
+  }
+}
+
+export async function exportNdjson(
+  path: string,
+  compress = false,
+): Promise<number> {
+  try {
+    return await invoke<number>("export_ndjson", { path, compress });
+  } catch (error) {
+    console.error("Failed to export packets:", error);
+    throw error;
+  }
+}
+
+export interface PayloadSchemas {
+  events: Record<string, object>;
+  commands: Record<string, object>;
+}
+
+export async function getEventSchemas(): Promise<PayloadSchemas> {
+  try {
+    return await invoke<PayloadSchemas>("get_event_schemas");
+  } catch (error) {
+    console.error("Failed to get payload schemas:", error);
+    throw error;
+  }
+}
+
+export interface ProtocolTiming {
+  protocol: string;
+  packets: number;
+  total_micros: number;
+  avg_nanos: number;
+}
+
+export interface ParseBenchmark {
+  packets: number;
+  failed: number;
+  bytes: number;
+  elapsed_ms: number;
+  packets_per_sec: number;
+  mb_per_sec: number;
+  protocols: ProtocolTiming[];
+}
+
+export async function benchmarkParsing(
+  pcapPath: string,
+): Promise<ParseBenchmark> {
+  try {
+    return await invoke<ParseBenchmark>("benchmark_parsing", { pcapPath });
+  } catch (error) {
+    console.error("Failed to benchmark parser:", error);
+    throw error;
+  }
+}
+
+export interface ReplayInfo {
+  path: string;
+  packets: number;
+  start_ms: number;
+  end_ms: number;
+}
+
+export interface ReplayStatus {
+  playing: boolean;
+  speed: number;
+  position: number;
+  packets: number;
+  next_timestamp_ms: number | null;
+}
+
+export async function loadReplay(path: string): Promise<ReplayInfo> {
+  try {
+    return await invoke<ReplayInfo>("load_replay", { path });
+  } catch (error) {
+    console.error("Failed to load capture for replay:", error);
+    throw error;
+  }
+}
+
+export async function replayCapture(
+  path: string,
+  speed: number,
+): Promise<ReplayInfo> {
+  try {
+    return await invoke<ReplayInfo>("replay_capture", { path, speed });
+  } catch (error) {
+    console.error("Failed to replay capture:", error);
+    throw error;
+  }
+}
+
+export async function playReplay(): Promise<void> {
+  try {
+    await invoke("play_replay");
+  } catch (error) {
+    console.error("Failed to play replay:", error);
+    throw error;
+  }
+}
+
+export async function pauseReplay(): Promise<void> {
+  try {
+    await invoke("pause_replay");
+  } catch (error) {
+    console.error("Failed to pause replay:", error);
+    throw error;
+  }
+}
+
+export async function setReplaySpeed(speed: number): Promise<void> {
+  try {
+    await invoke("set_replay_speed", { speed });
+  } catch (error) {
+    console.error("Failed to set replay speed:", error);
+    throw error;
+  }
+}
+
+export async function seekReplay(timestampMs: number): Promise<void> {
+  try {
+    await invoke("seek_replay", { timestampMs });
+  } catch (error) {
+    console.error("Failed to seek replay:", error);
+    throw error;
+  }
+}
+
+export async function getReplayStatus(): Promise<ReplayStatus> {
+  try {
+    return await invoke<ReplayStatus>("get_replay_status");
+  } catch (error) {
+    console.error("Failed to get replay status:", error);
+    throw error;
+  }
+}
+
+export async function stopReplay(): Promise<void> {
+  try {
+    await invoke("stop_replay");
+  } catch (error) {
+    console.error("Failed to stop replay:", error);
+    throw error;
+  }
+}
+
+export async function listenForReplayFinished(
+  callback: () => void,
+): Promise<void> {
+  await listen("replay_finished", () => {
+    callback();
+  });
+}
+
+export async function captureNPackets(
+  device: string,
+  count: number,
+  filter?: string,
+): Promise<PacketInfo[]> {
+  try {
+    return await invoke<PacketInfo[]>("capture_n_packets", {
+      device,
+      count,
+      filter: filter ?? null,
+    });
+  } catch (error) {
+    console.error("Failed to capture packets:", error);
+    throw error;
+  }
+}
+
+export async function stopPacketCapture(): Promise<void> {
+  try {
+    await invoke("stop_packet_capture");
+  } catch (error) {
+    console.error("Failed to stop packet capture:", error);
+    throw error;
+  }
+}
+
+export async function pausePacketCapture(): Promise<void> {
+  try {
+    await invoke("pause_packet_capture");
+  } catch (error) {
+    console.error("Failed to pause packet capture:", error);
+    throw error;
+  }
+}
+
+export async function resumePacketCapture(): Promise<void> {
+  try {
+    await invoke("resume_packet_capture");
+  } catch (error) {
+    console.error("Failed to resume packet capture:", error);
+    throw error;
+  }
+}
+
+export async function listenForPackets(
+  callback: (packet: PacketInfo) => void,
+): Promise<void> {
+  await listen<PacketInfo>("packet", (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface CaptureRate {
+  device: string;
+  packets_per_sec: number;
+  bytes_per_sec: number;
+}
+
+export async function listenForCaptureRate(
+  callback: (rate: CaptureRate) => void,
+): Promise<void> {
+  await listen<CaptureRate>("capture_rate", (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface CaptureComplete {
+  device: string;
+  packets: number;
+}
+
+export async function listenForCaptureComplete(
+  callback: (complete: CaptureComplete) => void,
+): Promise<void> {
+  await listen<CaptureComplete>("capture_complete", (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface CaptureStalled {
+  device: string;
+  last_packet_age_ms: number;
+}
+
+export async function listenForCaptureStalled(
+  callback: (stalled: CaptureStalled) => void,
+): Promise<void> {
+  await listen<CaptureStalled>("capture_stalled", (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface CaptureSummary {
+  device: string | null;
+  running: boolean;
+  paused: boolean;
+  elapsed_secs: number;
+  packets: number;
+  bytes: number;
+  packets_per_sec: number;
+  bytes_per_sec: number;
+}
+
+export async function getCaptureSummary(): Promise<CaptureSummary> {
+  try {
+    return await invoke<CaptureSummary>("get_capture_summary");
+  } catch (error) {
+    console.error("Failed to get capture summary:", error);
+    throw error;
+  }
+}
+
+export async function getLastPacketAge(): Promise<number | null> {
+  try {
+    return await invoke<number | null>("get_last_packet_age");
+  } catch (error) {
+    console.error("Failed to get last packet age:", error);
+    throw error;
+  }
+}
+
+export async function listenForCaptureErrors(
+  callback: (error: WifiError) => void,
+): Promise<void> {
+  await listen<WifiError>("capture_error", (event) => {
+    callback(event.payload);
+  });
+}
+
+export async function getLatestPackets(): Promise<PacketInfo[]> {
+  try {
+    const latestPackets = await invoke<PacketInfo[]>("get_latest_packets");
+    return latestPackets;
+  } catch (error) {
+    console.error("Failed to get latest packets:", error);
+    throw error;
+  }
+}
+
+export async function getHandshakes(): Promise<HandshakeStatus[]> {
+  try {
+    return await invoke<HandshakeStatus[]>("get_handshakes");
+  } catch (error) {
+    console.error("Failed to get handshakes:", error);
+    throw error;
+  }
+}
+
+export interface SessionMeta {
+  id: string;
+  name: string;
+  notes: string;
+  location: string | null;
+  started_at: number;
+  ended_at: number | null;
+  network_count: number;
+  packet_count: number;
+}
+
+export interface Session extends SessionMeta {
+  networks: WiFiNetwork[];
+  packets: PacketInfo[];
+}
+
+export async function startSession(
+  name: string,
+  notes: string,
+  location?: string,
+): Promise<SessionMeta> {
+  try {
+    return await invoke<SessionMeta>("start_session", {
+      name,
+      notes,
+      location,
+    });
+  } catch (error) {
+    console.error("Failed to start session:", error);
+    throw error;
+  }
+}
+
+export async function endSession(): Promise<SessionMeta> {
+  try {
+    return await invoke<SessionMeta>("end_session");
+  } catch (error) {
+    console.error("Failed to end session:", error);
+    throw error;
+  }
+}
+
+export async function listSessions(): Promise<SessionMeta[]> {
+  try {
+    return await invoke<SessionMeta[]>("list_sessions");
+  } catch (error) {
+    console.error("Failed to list sessions:", error);
+    throw error;
+  }
+}
+
+export async function getSession(id: string): Promise<Session> {
+  try {
+    return await invoke<Session>("get_session", { id });
+  } catch (error) {
+    console.error("Failed to get session:", error);
+    throw error;
+  }
+}
+
+export type IpScope =
+  | "private"
+  | "loopback"
+  | "link_local"
+  | "cgnat"
+  | "multicast"
+  | "broadcast"
+  | "unspecified"
+  | "public";
+
+export interface ExternalEndpoint {
+  ip: string;
+  hostname: string | null;
+  packets: number;
+  bytes: number;
+  first_seen: number;
+  last_seen: number;
+}
+
+export async function getExternalEndpoints(): Promise<ExternalEndpoint[]> {
+  try {
+    return await invoke<ExternalEndpoint[]>("get_external_endpoints");
+  } catch (error) {
+    console.error("Failed to get external endpoints:", error);
+    throw error;
+  }
+}
+
+export interface FlowJitter {
+  flow_key: string;
+  packets: number;
+  mean_interarrival_ms: number;
+  jitter_ms: number;
+}
+
+const FLOW_TRANSPORTS: Partial<Record<Layer["layer"], string>> = {
+  tcp: "TCP",
+  udp: "UDP",
+  sctp: "SCTP",
+};
+
+export function flowKey(packet: PacketInfo): string | null {
+  const transport = packet.layers
+    .map((layer) => FLOW_TRANSPORTS[layer.layer])
+    .find((name) => name !== undefined);
+  if (
+    transport === undefined ||
+    packet.src_ip === null ||
+    packet.src_port === null ||
+    packet.dst_ip === null ||
+    packet.dst_port === null
+  ) {
+    return null;
+  }
+  return `${transport} ${packet.src_ip}:${packet.src_port} > ${packet.dst_ip}:${packet.dst_port}`;
+}
+
+export async function getFlowJitter(flowKey: string): Promise<FlowJitter> {
+  try {
+    return await invoke<FlowJitter>("get_flow_jitter", { flowKey });
+  } catch (error) {
+    console.error("Failed to get flow jitter:", error);
+    throw error;
+  }
+}
+
+export interface Conversation {
+  transport: string;
+  a_ip: string;
+  a_port: number;
+  b_ip: string;
+  b_port: number;
+  packets_a_to_b: number;
+  bytes_a_to_b: number;
+  packets_b_to_a: number;
+  bytes_b_to_a: number;
+  start_ms: number;
+  end_ms: number;
+  duration_ms: number;
+  app_protocol: string | null;
+}
+
+export async function getConversations(): Promise<Conversation[]> {
+  try {
+    return await invoke<Conversation[]>("get_conversations");
+  } catch (error) {
+    console.error("Failed to get conversations:", error);
+    throw error;
+  }
+}
+
+export interface KnownDevice {
+  mac: string;
+  vendor: string | null;
+  first_ip: string | null;
+  first_seen: number;
+  last_seen: number;
+  trusted: boolean;
+}
+
+export async function getKnownDevices(): Promise<KnownDevice[]> {
+  try {
+    return await invoke<KnownDevice[]>("get_known_devices");
+  } catch (error) {
+    console.error("Failed to get known devices:", error);
+    throw error;
+  }
+}
+
+export async function setDeviceTrusted(
+  mac: string,
+  trusted: boolean,
+): Promise<void> {
+  try {
+    await invoke("set_device_trusted", { mac, trusted });
+  } catch (error) {
+    console.error("Failed to update device trust:", error);
+    throw error;
+  }
+}
+
+export async function listenForNewDevices(
+  callback: (device: KnownDevice) => void,
+): Promise<void> {
+  await listen<KnownDevice>("new_device", (event) => {
+    callback(event.payload);
+  });
+}
+
+export type BindingConflict =
+  | {
+      kind: "mac_changed";
+      ip: string;
+      old_mac: string;
+      new_mac: string;
+      old_last_seen: number;
+      detected_at: number;
+    }
+  | { kind: "many_ips"; mac: string; ips: string[]; detected_at: number };
+
+export async function getBindingConflicts(): Promise<BindingConflict[]> {
+  try {
+    return await invoke<BindingConflict[]>("get_binding_conflicts");
+  } catch (error) {
+    console.error("Failed to get binding conflicts:", error);
+    throw error;
+  }
+}
+
+export async function listenForArpSpoofAlerts(
+  callback: (conflict: BindingConflict) => void,
+): Promise<void> {
+  await listen<BindingConflict>("arp_spoof_alert", (event) => {
+    callback(event.payload);
+  });
+}
+
+export interface LocalTraffic {
+  addresses: { mac: string | null; ips: string[] };
+  excluded: boolean;
+  packets: number;
+  bytes: number;
+}
+
+export async function setExcludeLocal(exclude: boolean): Promise<void> {
+  try {
+    await invoke("set_exclude_local", { exclude });
+  } catch (error) {
+    console.error("Failed to set local traffic exclusion:", error);
+    throw error;
+  }
+}
+
+export async function setCaptureRaw(enabled: boolean): Promise<void> {
+  try {
+    await invoke("set_capture_raw", { enabled });
+  } catch (error) {
+    console.error("Failed to set raw frame capture:", error);
+    throw error;
+  }
+}
+
+export async function setPayloadCaptureBytes(bytes: number): Promise<void> {
+  try {
+    await invoke("set_payload_capture_bytes", { bytes });
+  } catch (error) {
+    console.error("Failed to set payload capture length:", error);
+    throw error;
+  }
+}
+
+export interface ResolverConfig {
+  enabled: boolean;
+  server: string;
+  cache_size: number;
+}
+
+export async function setReverseDns(
+  enabled: boolean,
+  server?: string,
+  cacheSize?: number,
+): Promise<ResolverConfig> {
+  try {
+    return await invoke<ResolverConfig>("set_reverse_dns", {
+      enabled,
+      server,
+      cacheSize,
+    });
+  } catch (error) {
+    console.error("Failed to configure reverse DNS:", error);
+    throw error;
+  }
+}
+
+export interface HostResolved {
+  ip: string;
+  host: string;
+}
+
+export async function listenForHostResolved(
+  callback: (resolved: HostResolved) => void,
+): Promise<void> {
+  await listen<HostResolved>("host_resolved", (event) => {
+    callback(event.payload);
+  });
+}
+
+export type ParseDepth = "l2" | "l4" | "application";
+
+export async function setParseDepth(depth: ParseDepth): Promise<void> {
+  try {
+    await invoke("set_parse_depth", { depth });
+  } catch (error) {
+    console.error("Failed to set parse depth:", error);
+    throw error;
+  }
+}
+
+export type TimestampPrecision = "micro" | "nano";
+
+export async function getTimestampPrecision(): Promise<TimestampPrecision> {
+  try {
+    return await invoke<TimestampPrecision>("get_timestamp_precision");
+  } catch (error) {
+    console.error("Failed to get timestamp precision:", error);
+    throw error;
+  }
+}
+
+export async function getLocalTraffic(): Promise<LocalTraffic> {
+  try {
+    return await invoke<LocalTraffic>("get_local_traffic");
+  } catch (error) {
+    console.error("Failed to get local traffic:", error);
+    throw error;
+  }
+}
+
+export type MalformedReason =
+  | "too_short"
+  | "unknown_ethertype"
+  | "truncated_header"
+  | "unsupported_header";
+
+export interface ParseFailures {
+  too_short: number;
+  unknown_ethertype: number;
+  truncated_header: number;
+  unsupported_header: number;
+}
+
+export async function getParseFailures(): Promise<ParseFailures> {
+  try {
+    return await invoke<ParseFailures>("get_parse_failures");
+  } catch (error) {
+    console.error("Failed to get parse failures:", error);
+    throw error;
+  }
+}
+
+export async function startForwarding(host: string, port: number): Promise<void> {
+  try {
+    await invoke("start_forwarding", { host, port });
+  } catch (error) {
+    console.error("Failed to start packet forwarding:", error);
+    throw error;
+  }
+}
+
+export async function stopForwarding(): Promise<number> {
+  try {
+    return await invoke<number>("stop_forwarding");
+  } catch (error) {
+    console.error("Failed to stop packet forwarding:", error);
+    throw error;
+  }
+}
+
+export interface CaptureOutput {
+  directory: string;
+  template: string;
+}
+
+export async function setCaptureOutput(
+  directory: string,
+  template?: string,
+): Promise<CaptureOutput> {
+  try {
+    return await invoke<CaptureOutput>("set_capture_output", { directory, template });
+  } catch (error) {
+    console.error("Failed to set capture output:", error);
+    throw error;
+  }
+}
+
+export async function getCaptureOutput(): Promise<CaptureOutput> {
+  try {
+    return await invoke<CaptureOutput>("get_capture_output");
+  } catch (error) {
+    console.error("Failed to get capture output:", error);
+    throw error;
+  }
+}
+
+export async function startSavingCapture(compress = false): Promise<string> {
+  try {
+    return await invoke<string>("start_saving_capture", { compress });
+  } catch (error) {
+    console.error("Failed to start saving capture:", error);
+    throw error;
+  }
+}
+
+export async function stopSavingCapture(): Promise<void> {
+  try {
+    await invoke("stop_saving_capture");
+  } catch (error) {
+    console.error("Failed to stop saving capture:", error);
+    throw error;
+  }
+}
+
+export async function listenForCaptureSaveFailures(
+  callback: (error: unknown) => void,
+): Promise<void> {
+  await listen<unknown>("capture_save_failed", (event) => {
+    callback(event.payload);
+  });
+}
+
+export type ParsedHexPacket =
+  | { link_type: "ethernet"; packet: PacketInfo }
+  | { link_type: "loopback"; packet: PacketInfo }
+  | { link_type: "radiotap"; packet: Record<string, unknown> };
+
+export async function parseHexPacket(
+  hex: string,
+  linkType: "ethernet" | "loopback" | "radiotap",
+): Promise<ParsedHexPacket> {
+  try {
+    return await invoke<ParsedHexPacket>("parse_hex_packet", { hex, linkType });
+  } catch (error) {
+    console.error("Failed to parse hex packet:", error);
+    throw error;
+  }
+}
+
+export async function decodeFrame(hex: string): Promise<ParsedHexPacket> {
+  try {
+    return await invoke<ParsedHexPacket>("decode_frame", { hex });
+  } catch (error) {
+    console.error("Failed to decode frame:", error);
     throw error;
   }
 }